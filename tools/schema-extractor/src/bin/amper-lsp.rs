@@ -0,0 +1,46 @@
+//! Amper language server
+//!
+//! Speaks the Language Server Protocol over stdin and stdout, answering from
+//! the Amper sources given by `--source`.
+
+use amper_schema_extractor::{load_context, lsp, mapping, schema};
+use anyhow::Result;
+use clap::Parser;
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser, Debug)]
+#[command(name = "amper-lsp", about = "Language server for Amper configuration files", version)]
+struct Args {
+    /// Path to the Amper source directory (vendor/amper/sources)
+    #[arg(short, long)]
+    source: PathBuf,
+
+    /// TOML or JSON file extending the built-in Kotlin-to-JSON-Schema type mapping
+    #[arg(long, value_name = "FILE")]
+    type_mapping: Option<PathBuf>,
+
+    /// Log progress to stderr
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+fn main() -> Result<ExitCode> {
+    let args = Args::parse();
+
+    let context = load_context(&args.source, args.verbose)?;
+    let type_mapping = match &args.type_mapping {
+        Some(path) => mapping::TypeMapping::load(path)?,
+        None => mapping::TypeMapping::default(),
+    };
+    let options = schema::SchemaOptions { type_mapping, ..schema::SchemaOptions::default() };
+    let mut server = lsp::Server::new(context, options)?;
+    if args.verbose {
+        eprintln!("amper-lsp v{} listening on stdin", env!("CARGO_PKG_VERSION"));
+    }
+
+    // Exiting without a `shutdown` request first is an error, per the protocol
+    let clean = lsp::run(&mut server, &mut io::stdin().lock(), &mut io::stdout().lock())?;
+    Ok(if clean { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
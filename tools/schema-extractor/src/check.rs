@@ -0,0 +1,138 @@
+//! Drift between committed schemas and the ones generated from the sources
+//!
+//! Schemas are compared as JSON values, so key order and formatting don't
+//! matter. A file that drifted is described by the semantic changes of its
+//! definitions, as `diff` reports them, and by the JSON pointers that differ.
+
+use crate::diff::{self, Change};
+use crate::error::Result;
+use crate::schema::{self, SchemaOptions};
+use crate::types::ParsingContext;
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Number of differing locations listed per file
+const PATH_LIMIT: usize = 20;
+
+/// How a committed schema file compares with the generated one
+#[derive(Debug)]
+pub enum Status {
+    UpToDate,
+    Missing,
+    OutOfDate {
+        /// Changes to the definitions, if both files have definitions
+        changes: Vec<Change>,
+        /// JSON pointers of the changed locations
+        paths: Vec<String>,
+    },
+}
+
+/// The result of checking one committed schema file
+#[derive(Debug)]
+pub struct FileCheck {
+    pub path: PathBuf,
+    pub status: Status,
+}
+
+impl FileCheck {
+    pub fn is_up_to_date(&self) -> bool {
+        matches!(self.status, Status::UpToDate)
+    }
+}
+
+/// The committed files `check` compares with
+#[derive(Debug, Clone, Copy)]
+pub enum Target<'a> {
+    /// A schema file holding the `schema_type` root
+    Schema { schema_type: &'a str, expected: &'a Path },
+    /// Every file of an `--out-dir` bundle
+    Bundle(&'a Path),
+}
+
+impl Target<'_> {
+    /// The `extract-schema` options that regenerate the target from the sources
+    pub fn regenerate_args(&self) -> String {
+        match self {
+            Target::Schema { schema_type, expected } => {
+                format!("--schema-type {} --output {}", schema_type, expected.display())
+            }
+            Target::Bundle(out_dir) => format!("--out-dir {}", out_dir.display()),
+        }
+    }
+}
+
+/// Regenerate the schemas of `target` in memory and compare them with the committed files
+pub fn run(context: &ParsingContext, target: &Target, options: &SchemaOptions) -> Result<Vec<FileCheck>> {
+    match target {
+        Target::Schema { schema_type, expected } => Ok(vec![check_schema(context, schema_type, expected, options)?]),
+        Target::Bundle(out_dir) => check_bundle(context, out_dir, options),
+    }
+}
+
+/// Compare a committed schema with the `schema_type` root generated from `context`
+pub fn check_schema(
+    context: &ParsingContext,
+    schema_type: &str,
+    expected: &Path,
+    options: &SchemaOptions,
+) -> Result<FileCheck> {
+    let root_type = crate::resolve_root_type(context, schema_type)?;
+    let generated = schema::generate_json_schema(context, root_type, options)?;
+    check_file(expected, &generated)
+}
+
+/// Compare every file of a committed `--out-dir` bundle with the generated one
+pub fn check_bundle(context: &ParsingContext, out_dir: &Path, options: &SchemaOptions) -> Result<Vec<FileCheck>> {
+    let bundle = crate::generate_bundle(context, options)?;
+    crate::bundle_files(&bundle)
+        .into_iter()
+        .map(|(file_name, generated)| check_file(&out_dir.join(file_name), generated))
+        .collect()
+}
+
+/// Compare the committed file at `path` with its `generated` content
+pub fn check_file(path: &Path, generated: &Value) -> Result<FileCheck> {
+    let check = |status| Ok(FileCheck { path: path.to_path_buf(), status });
+    if !path.exists() {
+        return check(Status::Missing);
+    }
+    let committed = crate::read_schema(path)?;
+
+    // Values compare by content: key order and formatting don't matter
+    if committed == *generated {
+        return check(Status::UpToDate);
+    }
+
+    let changes = match (diff::definitions(&committed), diff::definitions(generated)) {
+        (Some(old_defs), Some(new_defs)) => diff::diff_definitions(old_defs, new_defs),
+        _ => Vec::new(),
+    };
+    let paths = diff::changed_paths(&committed, generated);
+    check(Status::OutOfDate { changes, paths })
+}
+
+impl fmt::Display for FileCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (changes, paths) = match &self.status {
+            Status::UpToDate => return write!(f, "Schema is up to date: {}", self.path.display()),
+            Status::Missing => return write!(f, "Schema is missing: {}", self.path.display()),
+            Status::OutOfDate { changes, paths } => (changes, paths),
+        };
+
+        writeln!(f, "Schema is out of date: {}", self.path.display())?;
+        if !changes.is_empty() {
+            writeln!(f)?;
+            write!(f, "{}", diff::render_markdown(changes))?;
+        }
+        writeln!(f)?;
+        write!(f, "Changed locations (+ generated only, - committed only, ~ different):")?;
+        for path in paths.iter().take(PATH_LIMIT) {
+            write!(f, "\n  {}", path)?;
+        }
+        if paths.len() > PATH_LIMIT {
+            write!(f, "\n  ... and {} more", paths.len() - PATH_LIMIT)?;
+        }
+        Ok(())
+    }
+}
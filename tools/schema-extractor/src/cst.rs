@@ -0,0 +1,536 @@
+//! Comment-preserving YAML documents
+//!
+//! A line-based reader and printer for the block-style YAML of Amper files.
+//! Unlike `yaml`, it keeps comments and blank lines, so a document can be
+//! rearranged and printed back. Flow collections and quoted scalars must fit
+//! on one line; anchors, aliases, tags and multi-line plain scalars are not
+//! supported.
+
+use crate::error::{Error, Result};
+
+/// A comment line or a blank line between nodes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Blank,
+    /// The comment including its `#`
+    Comment(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Nothing after the key or dash
+    Empty,
+    /// A scalar as written, quotes included
+    Scalar(String),
+    /// A one-line flow sequence of scalars, items as written
+    FlowSequence(Vec<String>),
+    /// Any other flow collection, kept verbatim
+    Flow(String),
+    /// A `|` or `>` block scalar: its header and its lines without the
+    /// common indentation
+    BlockScalar { header: String, lines: Vec<String> },
+    Mapping(Vec<Entry>),
+    Sequence(Vec<Item>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Comments and blank lines above the entry
+    pub leading: Vec<Trivia>,
+    /// The key as written, quotes included
+    pub key: String,
+    pub value: Value,
+    /// Comment at the end of the key's line
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub leading: Vec<Trivia>,
+    pub value: Value,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// Comments at the top of the file, separated from the first node by a
+    /// blank line
+    pub header: Vec<Trivia>,
+    pub root: Value,
+    /// Comments after the last node
+    pub trailing: Vec<Trivia>,
+}
+
+/// Read a document; errors name the offending line
+pub fn parse(source: &str) -> Result<Document> {
+    let mut lines = Vec::new();
+    for (index, raw) in source.lines().enumerate() {
+        let text = raw.trim_start_matches(' ');
+        if text.starts_with('\t') {
+            return Err(Error::Unsupported(format!("line {}: tabs can't be used for indentation", index + 1)));
+        }
+        lines.push(Line { number: index + 1, indent: raw.len() - text.len(), text: text.trim_end().to_string() });
+    }
+
+    let mut reader = Reader { lines, pos: 0, pending: Vec::new() };
+    let mut root = match reader.peek() {
+        Some(line) if line.indent > 0 => return Err(Error::Unsupported(format!("line {}: unexpected indentation", line.number))),
+        Some(_) => reader.parse_node(0)?,
+        None => Value::Empty,
+    };
+    if let Some(line) = reader.peek() {
+        return Err(Error::Unsupported(format!("line {}: unexpected content", line.number)));
+    }
+
+    let first = match &mut root {
+        Value::Mapping(entries) => entries.first_mut().map(|entry| &mut entry.leading),
+        Value::Sequence(items) => items.first_mut().map(|item| &mut item.leading),
+        _ => None,
+    };
+    let header = match first {
+        Some(leading) => {
+            let end = leading.iter().rposition(|trivia| *trivia == Trivia::Blank).map_or(0, |index| index + 1);
+            leading.drain(..end).collect()
+        }
+        None => Vec::new(),
+    };
+    Ok(Document { header, root, trailing: reader.pending })
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    /// The line without its indentation and trailing whitespace
+    text: String,
+}
+
+impl Line {
+    fn is_trivia(&self) -> bool {
+        self.text.is_empty() || self.text.starts_with('#')
+    }
+}
+
+struct Reader {
+    lines: Vec<Line>,
+    pos: usize,
+    /// Trivia read but not yet attached to a node
+    pending: Vec<Trivia>,
+}
+
+impl Reader {
+    /// The next line holding a node, collecting the trivia before it
+    fn peek(&mut self) -> Option<Line> {
+        while let Some(line) = self.lines.get(self.pos).filter(|line| line.is_trivia()) {
+            if line.text.is_empty() {
+                if self.pending.last() != Some(&Trivia::Blank) {
+                    self.pending.push(Trivia::Blank);
+                }
+            } else {
+                self.pending.push(Trivia::Comment(line.text.clone()));
+            }
+            self.pos += 1;
+        }
+        self.lines.get(self.pos).cloned()
+    }
+
+    fn take_leading(&mut self) -> Vec<Trivia> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// A mapping or sequence whose first line is the next one, at `indent`
+    fn parse_node(&mut self, indent: usize) -> Result<Value> {
+        match self.peek() {
+            Some(line) if is_dash(&line.text) => self.parse_sequence(indent),
+            _ => self.parse_mapping(indent),
+        }
+    }
+
+    fn parse_mapping(&mut self, indent: usize) -> Result<Value> {
+        let mut entries = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent {
+                break;
+            }
+            if line.indent > indent {
+                return Err(Error::Unsupported(format!("line {}: unexpected indentation", line.number)));
+            }
+            let Some((key, rest)) = split_key(&line.text) else {
+                return Err(Error::Unsupported(format!("line {}: expected `key: value`", line.number)));
+            };
+            let leading = self.take_leading();
+            self.pos += 1;
+
+            let (text, comment) = split_comment(rest);
+            let value = if text.is_empty() {
+                match self.peek() {
+                    Some(next) if next.indent > indent => self.parse_node(next.indent)?,
+                    // A sequence may start at the indentation of its key
+                    Some(next) if next.indent == indent && is_dash(&next.text) => self.parse_sequence(indent)?,
+                    _ => Value::Empty,
+                }
+            } else {
+                self.parse_inline(text, indent, line.number)?
+            };
+            entries.push(Entry { leading, key: key.to_string(), value, comment });
+        }
+        Ok(Value::Mapping(entries))
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> Result<Value> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent || (line.indent == indent && !is_dash(&line.text)) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(Error::Unsupported(format!("line {}: unexpected indentation", line.number)));
+            }
+            let leading = self.take_leading();
+            let after = &line.text[1..];
+            let content = after.trim_start();
+            let column = indent + 1 + after.len() - content.len();
+
+            if content.is_empty() || content.starts_with('#') {
+                self.pos += 1;
+                let comment = (!content.is_empty()).then(|| content.to_string());
+                let value = match self.peek() {
+                    Some(next) if next.indent > indent => self.parse_node(next.indent)?,
+                    _ => Value::Empty,
+                };
+                items.push(Item { leading, value, comment });
+            } else if is_dash(content) || (!content.starts_with(['[', '{']) && split_key(content).is_some()) {
+                // The rest of the line starts a nested block at its own column
+                self.lines[self.pos] = Line { number: line.number, indent: column, text: content.to_string() };
+                let value = self.parse_node(column)?;
+                items.push(Item { leading, value, comment: None });
+            } else {
+                self.pos += 1;
+                let (text, comment) = split_comment(content);
+                let value = self.parse_inline(text, indent, line.number)?;
+                items.push(Item { leading, value, comment });
+            }
+        }
+        Ok(Value::Sequence(items))
+    }
+
+    /// A value written after a key or dash on the line `number`
+    fn parse_inline(&mut self, text: &str, indent: usize, number: usize) -> Result<Value> {
+        if text.starts_with(['|', '>']) {
+            return Ok(self.parse_block_scalar(text, indent));
+        }
+        if text.starts_with(['&', '*', '!']) {
+            return Err(Error::Unsupported(format!("line {}: anchors, aliases and tags are not supported", number)));
+        }
+
+        let value = if text.starts_with('[') && text.ends_with(']') {
+            match split_flow_items(&text[1..text.len() - 1]) {
+                Some(items) => Value::FlowSequence(items),
+                None => Value::Flow(text.to_string()),
+            }
+        } else if text.starts_with('{') && text.ends_with('}') {
+            Value::Flow(text.to_string())
+        } else if text.starts_with(['[', '{']) {
+            return Err(Error::Unsupported(format!("line {}: flow collections spanning several lines are not supported", number)));
+        } else if text.starts_with(['"', '\'']) && quoted_len(text) != Some(text.len()) {
+            return Err(Error::Unsupported(format!("line {}: quoted scalars spanning several lines are not supported", number)));
+        } else {
+            Value::Scalar(text.to_string())
+        };
+
+        if let Some(next) = self.peek()
+            && next.indent > indent
+        {
+            return Err(Error::Unsupported(format!("line {}: scalars spanning several lines are not supported", next.number)));
+        }
+        Ok(value)
+    }
+
+    fn parse_block_scalar(&mut self, header: &str, indent: usize) -> Value {
+        let start = self.pos;
+        while let Some(line) = self.lines.get(self.pos) {
+            if !line.text.is_empty() && line.indent <= indent {
+                break;
+            }
+            self.pos += 1;
+        }
+        // Blank lines after the content separate it from the next node
+        while self.pos > start && self.lines[self.pos - 1].text.is_empty() {
+            self.pos -= 1;
+        }
+
+        let body = &self.lines[start..self.pos];
+        let base = body.iter().filter(|line| !line.text.is_empty()).map(|line| line.indent).min().unwrap_or(0);
+        let lines = body
+            .iter()
+            .map(|line| match line.text.is_empty() {
+                true => String::new(),
+                false => format!("{}{}", " ".repeat(line.indent - base), line.text),
+            })
+            .collect();
+        Value::BlockScalar { header: header.to_string(), lines }
+    }
+}
+
+fn is_dash(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Length of the quoted scalar at the start of `text`, quotes included
+pub fn quoted_len(text: &str) -> Option<usize> {
+    let quote = text.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let mut chars = text.char_indices().skip(1).peekable();
+    while let Some((index, c)) = chars.next() {
+        if quote == '"' && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            // `''` is an escaped quote in single-quoted scalars
+            if quote == '\'' && chars.peek().is_some_and(|(_, next)| *next == '\'') {
+                chars.next();
+                continue;
+            }
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// Split `key: rest` into the key as written and what follows the colon
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if text.starts_with(['"', '\'']) {
+        let end = quoted_len(text)?;
+        let rest = text[end..].trim_start().strip_prefix(':')?;
+        return (rest.is_empty() || rest.starts_with(' ')).then(|| (&text[..end], rest));
+    }
+    if text.starts_with(['#', '[', '{', '&', '*', '!', '?', '|', '>']) || is_dash(text) {
+        return None;
+    }
+    let mut previous = ' ';
+    for (index, c) in text.char_indices() {
+        if c == '#' && previous == ' ' {
+            return None;
+        }
+        if c == ':' && text[index + 1..].chars().next().is_none_or(|next| next == ' ') {
+            return Some((text[..index].trim_end(), &text[index + 1..]));
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Split a value from the comment at the end of its line
+fn split_comment(text: &str) -> (&str, Option<String>) {
+    let text = text.trim_start();
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            Some('\'') if c == '\'' && chars.peek().is_some_and(|(_, next)| *next == '\'') => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '#' && previous.is_whitespace() => {
+                return (text[..index].trim_end(), Some(text[index..].to_string()));
+            }
+            // Quotes only open a scalar at the start of a token
+            None if (c == '"' || c == '\'') && matches!(previous, ' ' | '[' | '{' | ',') => quote = Some(c),
+            None => {}
+        }
+        previous = c;
+    }
+    (text, None)
+}
+
+/// Items of a flow sequence body, or `None` if it nests collections
+fn split_flow_items(body: &str) -> Option<Vec<String>> {
+    let mut items = Vec::new();
+    let mut rest = body.trim();
+    while !rest.is_empty() {
+        let end = if rest.starts_with(['"', '\'']) {
+            let end = quoted_len(rest)?;
+            if !rest[end..].trim_start().is_empty() && !rest[end..].trim_start().starts_with(',') {
+                return None;
+            }
+            end
+        } else {
+            rest.find(',').unwrap_or(rest.len())
+        };
+        let item = rest[..end].trim();
+        if item.is_empty() || item.contains(['[', ']', '{', '}', '#']) || item.starts_with(['&', '*', '!']) {
+            return None;
+        }
+        items.push(item.to_string());
+        rest = rest[end..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    Some(items)
+}
+
+/// Print a document with two spaces of indentation per level
+pub fn print(document: &Document) -> String {
+    let mut out = String::new();
+    print_trivia(&mut out, &document.header, "");
+    match &document.root {
+        Value::Mapping(entries) => print_mapping(&mut out, entries, 0, None),
+        Value::Sequence(items) => print_sequence(&mut out, items, 0),
+        _ => {}
+    }
+    print_trivia(&mut out, &document.trailing, "");
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    out
+}
+
+/// Comments keep their text; blank lines are kept between nodes only
+fn print_trivia(out: &mut String, trivia: &[Trivia], pad: &str) {
+    for item in trivia {
+        match item {
+            Trivia::Blank => {
+                if !out.is_empty() && !out.ends_with("\n\n") && !out.ends_with(":\n") && !out.ends_with("-\n") {
+                    out.push('\n');
+                }
+            }
+            Trivia::Comment(comment) => {
+                out.push_str(pad);
+                out.push_str(comment);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+/// Print entries at `indent`; with `dash`, the first entry goes on the line of
+/// a sequence item's dash
+fn print_mapping(out: &mut String, entries: &[Entry], indent: usize, dash: Option<&str>) {
+    let pad = " ".repeat(indent);
+    for (index, entry) in entries.iter().enumerate() {
+        let prefix = match dash {
+            Some(dash) if index == 0 => dash.to_string(),
+            _ => {
+                print_trivia(out, &entry.leading, &pad);
+                pad.clone()
+            }
+        };
+        out.push_str(&prefix);
+        out.push_str(&entry.key);
+        out.push(':');
+        print_inline(out, &entry.value, &entry.comment);
+        print_nested(out, &entry.value, indent + 2);
+    }
+}
+
+fn print_sequence(out: &mut String, items: &[Item], indent: usize) {
+    let pad = " ".repeat(indent);
+    for item in items {
+        print_trivia(out, &item.leading, &pad);
+        match &item.value {
+            Value::Mapping(entries) if !entries.is_empty() && item.comment.is_some() => {
+                // A comment after the dash keeps the mapping on the lines below it
+                out.push_str(&pad);
+                out.push('-');
+                print_inline(out, &Value::Empty, &item.comment);
+                print_mapping(out, entries, indent + 2, None);
+            }
+            Value::Mapping(entries) if !entries.is_empty() => {
+                // Comments above the first entry move above the dash
+                print_trivia(out, &entries[0].leading, &pad);
+                print_mapping(out, entries, indent + 2, Some(&format!("{}- ", pad)));
+            }
+            value => {
+                out.push_str(&pad);
+                out.push('-');
+                print_inline(out, value, &item.comment);
+                print_nested(out, value, indent + 2);
+            }
+        }
+    }
+}
+
+/// The part of a value on its key's or dash's line, and the line's comment
+fn print_inline(out: &mut String, value: &Value, comment: &Option<String>) {
+    match value {
+        Value::Scalar(text) | Value::Flow(text) => {
+            out.push(' ');
+            out.push_str(text);
+        }
+        Value::FlowSequence(items) => {
+            out.push_str(" [");
+            out.push_str(&items.join(", "));
+            out.push(']');
+        }
+        Value::BlockScalar { header, .. } => {
+            out.push(' ');
+            out.push_str(header);
+        }
+        Value::Mapping(entries) if entries.is_empty() => out.push_str(" {}"),
+        Value::Sequence(items) if items.is_empty() => out.push_str(" []"),
+        Value::Empty | Value::Mapping(_) | Value::Sequence(_) => {}
+    }
+    if let Some(comment) = comment {
+        out.push(' ');
+        out.push_str(comment);
+    }
+    out.push('\n');
+}
+
+/// The lines of a value below its key or dash
+fn print_nested(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Mapping(entries) => print_mapping(out, entries, indent, None),
+        Value::Sequence(items) => print_sequence(out, items, indent),
+        Value::BlockScalar { lines, .. } => {
+            let pad = " ".repeat(indent);
+            for line in lines {
+                if !line.is_empty() {
+                    out.push_str(&pad);
+                    out.push_str(line);
+                }
+                out.push('\n');
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Every comment of a document, sorted, to tell whether a rewrite kept them all
+pub fn comments(document: &Document) -> Vec<String> {
+    fn trivia(out: &mut Vec<String>, trivia: &[Trivia]) {
+        for item in trivia {
+            if let Trivia::Comment(comment) = item {
+                out.push(comment.clone());
+            }
+        }
+    }
+    fn value(out: &mut Vec<String>, node: &Value) {
+        match node {
+            Value::Mapping(entries) => {
+                for entry in entries {
+                    trivia(out, &entry.leading);
+                    out.extend(entry.comment.clone());
+                    value(out, &entry.value);
+                }
+            }
+            Value::Sequence(items) => {
+                for item in items {
+                    trivia(out, &item.leading);
+                    out.extend(item.comment.clone());
+                    value(out, &item.value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    trivia(&mut out, &document.header);
+    value(&mut out, &document.root);
+    trivia(&mut out, &document.trailing);
+    out.sort();
+    out
+}
@@ -0,0 +1,503 @@
+//! Semantic comparison of two generated schemas
+//!
+//! Definitions are compared by name: classes by their properties, enums by
+//! their values. The result is a list of changes that can be rendered as a
+//! Markdown changelog section or serialized as JSON.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Output format of a diff report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ReportFormat {
+    /// A Markdown section for the changelog
+    #[default]
+    Markdown,
+    /// Machine-readable list of changes
+    Json,
+}
+
+/// Keywords that only document a property and don't affect what it accepts
+const DOC_KEYWORDS: &[&str] = &[
+    "title",
+    "description",
+    "markdownDescription",
+    "deprecationMessage",
+    "deprecated",
+    "doNotSuggest",
+    "defaultSnippets",
+    "default",
+    "x-intellij-metadata",
+];
+
+/// A single difference in the configuration language
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Change {
+    TypeAdded {
+        name: String,
+    },
+    TypeRemoved {
+        name: String,
+    },
+    PropertyAdded {
+        owner: String,
+        property: String,
+        #[serde(rename = "type")]
+        type_name: String,
+    },
+    PropertyRemoved {
+        owner: String,
+        property: String,
+        #[serde(rename = "type")]
+        type_name: String,
+    },
+    PropertyRenamed {
+        owner: String,
+        from: String,
+        to: String,
+    },
+    TypeChanged {
+        owner: String,
+        property: Option<String>,
+        old: String,
+        new: String,
+    },
+    EnumValueAdded {
+        owner: String,
+        value: String,
+    },
+    EnumValueRemoved {
+        owner: String,
+        value: String,
+    },
+    DefaultChanged {
+        owner: String,
+        property: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+    DocChanged {
+        owner: String,
+        property: Option<String>,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+/// The definitions of a schema document, whichever dialect it is written in
+pub fn definitions(schema: &Value) -> Option<&Map<String, Value>> {
+    schema
+        .get("$defs")
+        .or_else(|| schema.get("definitions"))
+        .and_then(Value::as_object)
+}
+
+/// Compare two sets of schema definitions
+pub fn diff_definitions(old: &Map<String, Value>, new: &Map<String, Value>) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for name in old.keys().filter(|name| !new.contains_key(*name)) {
+        changes.push(Change::TypeRemoved { name: name.clone() });
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        changes.push(Change::TypeAdded { name: name.clone() });
+    }
+
+    for (name, old_def) in old {
+        let Some(new_def) = new.get(name) else {
+            continue;
+        };
+
+        let (old_doc, new_doc) = (definition_doc(name, old_def), definition_doc(name, new_def));
+        if old_doc != new_doc {
+            changes.push(Change::DocChanged {
+                owner: name.clone(),
+                property: None,
+                old: old_doc,
+                new: new_doc,
+            });
+        }
+
+        match (enum_values(old_def), enum_values(new_def)) {
+            (Some(old_values), Some(new_values)) => {
+                diff_enum_values(name, &old_values, &new_values, &mut changes);
+                continue;
+            }
+            (None, None) => {}
+            _ => {
+                changes.push(Change::TypeChanged {
+                    owner: name.clone(),
+                    property: None,
+                    old: definition_kind(old_def).to_string(),
+                    new: definition_kind(new_def).to_string(),
+                });
+                continue;
+            }
+        }
+
+        if let (Some(old_props), Some(new_props)) = (properties(old_def), properties(new_def)) {
+            diff_properties(name, old_props, new_props, &mut changes);
+        }
+    }
+
+    changes
+}
+
+fn diff_enum_values(owner: &str, old: &[String], new: &[String], changes: &mut Vec<Change>) {
+    for value in old.iter().filter(|v| !new.contains(v)) {
+        changes.push(Change::EnumValueRemoved { owner: owner.to_string(), value: value.clone() });
+    }
+    for value in new.iter().filter(|v| !old.contains(v)) {
+        changes.push(Change::EnumValueAdded { owner: owner.to_string(), value: value.clone() });
+    }
+}
+
+fn diff_properties(
+    owner: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    changes: &mut Vec<Change>,
+) {
+    let mut removed: Vec<&String> = old.keys().filter(|k| !new.contains_key(*k)).collect();
+    let mut added: Vec<&String> = new.keys().filter(|k| !old.contains_key(*k)).collect();
+
+    // A removed and an added property accepting exactly the same values is a rename,
+    // as long as the pairing is unambiguous
+    let renames: Vec<(&String, &String)> = removed
+        .iter()
+        .filter_map(|from| {
+            let shape = value_shape(&old[*from]);
+            let mut candidates = added.iter().filter(|to| value_shape(&new[**to]) == shape);
+            let to = candidates.next()?;
+            if candidates.next().is_some() {
+                return None;
+            }
+            let rivals = removed.iter().filter(|other| value_shape(&old[**other]) == shape).count();
+            (rivals == 1).then_some((*from, *to))
+        })
+        .collect();
+    for (from, to) in &renames {
+        removed.retain(|name| name != from);
+        added.retain(|name| name != to);
+        changes.push(Change::PropertyRenamed {
+            owner: owner.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+
+    for name in removed {
+        changes.push(Change::PropertyRemoved {
+            owner: owner.to_string(),
+            property: name.clone(),
+            type_name: type_summary(&old[name]),
+        });
+    }
+    for name in added {
+        changes.push(Change::PropertyAdded {
+            owner: owner.to_string(),
+            property: name.clone(),
+            type_name: type_summary(&new[name]),
+        });
+    }
+
+    for (name, old_schema) in old {
+        let Some(new_schema) = new.get(name) else {
+            continue;
+        };
+
+        let (old_type, new_type) = (type_summary(old_schema), type_summary(new_schema));
+        if old_type != new_type {
+            changes.push(Change::TypeChanged {
+                owner: owner.to_string(),
+                property: Some(name.clone()),
+                old: old_type,
+                new: new_type,
+            });
+        }
+
+        let (old_default, new_default) = (old_schema.get("default"), new_schema.get("default"));
+        if old_default != new_default {
+            changes.push(Change::DefaultChanged {
+                owner: owner.to_string(),
+                property: name.clone(),
+                old: old_default.cloned(),
+                new: new_default.cloned(),
+            });
+        }
+
+        let (old_doc, new_doc) = (description(old_schema), description(new_schema));
+        if old_doc != new_doc {
+            changes.push(Change::DocChanged {
+                owner: owner.to_string(),
+                property: Some(name.clone()),
+                old: old_doc,
+                new: new_doc,
+            });
+        }
+    }
+}
+
+/// The properties of a class definition, looking through a shorthand `anyOf`
+fn properties(definition: &Value) -> Option<&Map<String, Value>> {
+    if let Some(props) = definition.get("properties").and_then(Value::as_object) {
+        return Some(props);
+    }
+    definition
+        .get("anyOf")?
+        .as_array()?
+        .iter()
+        .find_map(|branch| branch.get("properties").and_then(Value::as_object))
+}
+
+fn enum_values(definition: &Value) -> Option<Vec<String>> {
+    let values = definition.get("enum")?.as_array()?;
+    Some(values.iter().map(value_label).collect())
+}
+
+fn definition_kind(definition: &Value) -> &'static str {
+    if definition.get("enum").is_some() { "enum" } else { "object" }
+}
+
+/// Class docs are emitted as `title`, enum docs as `description`
+fn definition_doc(name: &str, definition: &Value) -> Option<String> {
+    description(definition).or_else(|| {
+        definition
+            .get("title")
+            .and_then(Value::as_str)
+            .filter(|title| *title != name)
+            .map(str::to_string)
+    })
+}
+
+fn description(schema: &Value) -> Option<String> {
+    schema.get("description").and_then(Value::as_str).map(str::to_string)
+}
+
+/// A property schema without its documentation, for rename detection
+fn value_shape(schema: &Value) -> Value {
+    match schema {
+        Value::Object(obj) => Value::Object(
+            obj.iter()
+                .filter(|(key, _)| !DOC_KEYWORDS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// A short, readable description of the values a property accepts:
+/// `string`, `Platform`, `list<Dependency>`, `map<string>`, `boolean?`
+pub fn type_summary(schema: &Value) -> String {
+    let (summary, nullable) = summarize(schema);
+    if nullable { format!("{}?", summary) } else { summary }
+}
+
+fn summarize(schema: &Value) -> (String, bool) {
+    let Some(obj) = schema.as_object() else {
+        return ("any".to_string(), false);
+    };
+
+    if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+        return (ref_name(reference).to_string(), false);
+    }
+
+    // Nullable non-string schemas: `anyOf: [schema, {type: null}]`
+    if let Some(branches) = obj.get("anyOf").and_then(Value::as_array) {
+        let is_null = |b: &Value| b.get("type").and_then(Value::as_str) == Some("null");
+        let others: Vec<&Value> = branches.iter().filter(|b| !is_null(b)).collect();
+        if others.len() == 1 && others.len() < branches.len() {
+            return (summarize(others[0]).0, true);
+        }
+    }
+
+    // Draft-07 moves `$ref` into `allOf` next to the NonNull marker
+    if let Some(parts) = obj.get("allOf").and_then(Value::as_array)
+        && obj.get("type").is_none()
+        && let Some(reference) = parts
+            .iter()
+            .filter_map(|p| p.get("$ref").and_then(Value::as_str))
+            .find(|r| ref_name(r) != "NonNull")
+    {
+        return (ref_name(reference).to_string(), false);
+    }
+
+    let (json_type, nullable) = match obj.get("type") {
+        Some(Value::String(t)) => (t.clone(), false),
+        Some(Value::Array(types)) => {
+            let types: Vec<&str> = types.iter().filter_map(Value::as_str).collect();
+            let non_null: Vec<&str> = types.iter().copied().filter(|t| *t != "null").collect();
+            (non_null.join("|"), non_null.len() < types.len())
+        }
+        _ if obj.contains_key("enum") => ("enum".to_string(), false),
+        _ if obj.contains_key("const") => ("const".to_string(), false),
+        _ => ("any".to_string(), false),
+    };
+
+    if json_type == "array"
+        && let Some(items) = obj.get("items")
+    {
+        // Maps are lists of single-key objects
+        if let Some(value) = items
+            .get("patternProperties")
+            .and_then(Value::as_object)
+            .and_then(|p| p.values().next())
+        {
+            return (format!("map<{}>", type_summary(value)), nullable);
+        }
+        return (format!("list<{}>", type_summary(items)), nullable);
+    }
+
+    (json_type, nullable)
+}
+
+fn ref_name(reference: &str) -> &str {
+    reference.rsplit('/').next().unwrap_or(reference)
+}
+
+fn value_label(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// JSON pointers of every location where two documents differ, prefixed with
+/// `+` (only in `new`), `-` (only in `old`) or `~` (different value).
+/// Object key order never counts as a difference.
+pub fn changed_paths(old: &Value, new: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_changed_paths(old, new, String::new(), &mut paths);
+    paths
+}
+
+fn collect_changed_paths(old: &Value, new: &Value, pointer: String, paths: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old_obj), Value::Object(new_obj)) => {
+            for (key, old_value) in old_obj {
+                let child = format!("{}/{}", pointer, escape_pointer(key));
+                match new_obj.get(key) {
+                    Some(new_value) => collect_changed_paths(old_value, new_value, child, paths),
+                    None => paths.push(format!("- {}", child)),
+                }
+            }
+            for key in new_obj.keys().filter(|key| !old_obj.contains_key(*key)) {
+                paths.push(format!("+ {}/{}", pointer, escape_pointer(key)));
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for (index, old_value) in old_items.iter().enumerate() {
+                let child = format!("{}/{}", pointer, index);
+                match new_items.get(index) {
+                    Some(new_value) => collect_changed_paths(old_value, new_value, child, paths),
+                    None => paths.push(format!("- {}", child)),
+                }
+            }
+            for index in old_items.len()..new_items.len() {
+                paths.push(format!("+ {}/{}", pointer, index));
+            }
+        }
+        _ if old != new => paths.push(format!("~ {}", if pointer.is_empty() { "/" } else { &pointer })),
+        _ => {}
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Compare the definitions of two schemas, or `None` if either has none
+pub fn diff_schemas(old: &Value, new: &Value) -> Option<Vec<Change>> {
+    Some(diff_definitions(definitions(old)?, definitions(new)?))
+}
+
+/// Render changes in `format`
+pub fn render(changes: &[Change], format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Markdown => render_markdown(changes),
+        ReportFormat::Json => format!("{:#}\n", serde_json::json!({ "changes": changes })),
+    }
+}
+
+/// Render changes as a Markdown section for the changelog
+pub fn render_markdown(changes: &[Change]) -> String {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut renamed = Vec::new();
+    let mut changed = Vec::new();
+    let mut docs = Vec::new();
+
+    for change in changes {
+        match change {
+            Change::TypeAdded { name } => added.push(format!("Type `{}`", name)),
+            Change::TypeRemoved { name } => removed.push(format!("Type `{}`", name)),
+            Change::PropertyAdded { owner, property, type_name } => {
+                added.push(format!("`{}.{}` (`{}`)", owner, property, type_name))
+            }
+            Change::PropertyRemoved { owner, property, type_name } => {
+                removed.push(format!("`{}.{}` (`{}`)", owner, property, type_name))
+            }
+            Change::PropertyRenamed { owner, from, to } => {
+                renamed.push(format!("`{}.{}` → `{}.{}`", owner, from, owner, to))
+            }
+            Change::EnumValueAdded { owner, value } => added.push(format!("`{}` value `{}`", owner, value)),
+            Change::EnumValueRemoved { owner, value } => {
+                removed.push(format!("`{}` value `{}`", owner, value))
+            }
+            Change::TypeChanged { owner, property, old, new } => changed.push(format!(
+                "{}: type `{}` → `{}`",
+                qualified(owner, property.as_deref()),
+                old,
+                new
+            )),
+            Change::DefaultChanged { owner, property, old, new } => changed.push(format!(
+                "`{}.{}`: default {} → {}",
+                owner,
+                property,
+                default_label(old.as_ref()),
+                default_label(new.as_ref())
+            )),
+            Change::DocChanged { owner, property, new, .. } => docs.push(format!(
+                "{}: {}",
+                qualified(owner, property.as_deref()),
+                new.as_deref().map_or("documentation removed".to_string(), |doc| doc.replace('\n', " "))
+            )),
+        }
+    }
+
+    let mut out = String::from("## Configuration changes\n");
+    if changes.is_empty() {
+        out.push_str("\nNo changes.\n");
+        return out;
+    }
+    for (heading, items) in [
+        ("Added", added),
+        ("Removed", removed),
+        ("Renamed", renamed),
+        ("Changed", changed),
+        ("Documentation", docs),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n### {}\n\n", heading));
+        for item in items {
+            out.push_str(&format!("- {}\n", item));
+        }
+    }
+    out
+}
+
+fn qualified(owner: &str, property: Option<&str>) -> String {
+    match property {
+        Some(property) => format!("`{}.{}`", owner, property),
+        None => format!("`{}`", owner),
+    }
+}
+
+fn default_label(value: Option<&Value>) -> String {
+    value.map_or("none".to_string(), |v| format!("`{}`", value_label(v)))
+}
@@ -0,0 +1,376 @@
+//! Effective configuration of a module
+//!
+//! Merges a module.yaml with the templates it applies, for one platform, by
+//! Amper's precedence rules:
+//!
+//! - sections qualified with a more specific platform win: `settings` <
+//!   `settings@<alias>` < `settings@native` < `settings@ios` < `settings@iosArm64`
+//! - at the same specificity the module wins over its templates, and a later
+//!   template over an earlier one
+//! - for tests, `test-settings` and `test-dependencies` come on top of the
+//!   main sections
+//!
+//! Settings are merged key by key; dependencies are concatenated, a later
+//! declaration of the same dependency replacing the earlier one. Every value
+//! remembers the file and position it came from.
+
+use crate::error::{Error, Result};
+use crate::types::ParsingContext;
+use crate::workspace;
+use crate::yaml::{self, Node, NodeValue, Position};
+use indexmap::IndexMap;
+use serde_json::{Value, json};
+use std::fmt;
+use std::path::Path;
+
+/// Output format of the effective configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    /// YAML with the origin of each value in a comment
+    #[default]
+    Yaml,
+    /// JSON with each value wrapped as `{"value", "origin"}`
+    Json,
+}
+
+/// Where a value was written
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    /// The file as referenced from the module directory
+    pub file: String,
+    pub position: Position,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.position.line, self.position.column)
+    }
+}
+
+/// A merged value; scalars remember their origin
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolved {
+    Scalar { value: Value, origin: Origin },
+    List(Vec<Resolved>),
+    Map(IndexMap<String, Resolved>),
+}
+
+impl Resolved {
+    fn from_node(node: &Node, file: &str) -> Self {
+        match &node.value {
+            NodeValue::Sequence(items) => Resolved::List(items.iter().map(|item| Self::from_node(item, file)).collect()),
+            NodeValue::Mapping(entries) => Resolved::Map(
+                entries.iter().map(|entry| (entry.key.clone(), Self::from_node(&entry.value, file))).collect(),
+            ),
+            _ => Resolved::Scalar {
+                value: node.to_json(),
+                origin: Origin { file: file.to_string(), position: node.position },
+            },
+        }
+    }
+
+    /// Merge `other` on top of this value: mappings key by key, anything else replaced
+    fn merge(&mut self, other: Resolved) {
+        match (self, other) {
+            (Resolved::Map(base), Resolved::Map(overrides)) => {
+                for (key, value) in overrides {
+                    match base.get_mut(&key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            base.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other,
+        }
+    }
+
+    /// The value as JSON, with scalars as `{"value": ..., "origin": "file:line:column"}`
+    pub fn to_json(&self) -> Value {
+        match self {
+            Resolved::Scalar { value, origin } => json!({ "value": value, "origin": origin.to_string() }),
+            Resolved::List(items) => Value::Array(items.iter().map(Resolved::to_json).collect()),
+            Resolved::Map(entries) => {
+                Value::Object(entries.iter().map(|(key, value)| (key.clone(), value.to_json())).collect())
+            }
+        }
+    }
+}
+
+/// Amper's platform hierarchy, read from the `Platform` enum
+#[derive(Debug)]
+pub struct Platforms {
+    /// Schema value of each platform and of its parent
+    parents: IndexMap<String, Option<String>>,
+}
+
+impl Platforms {
+    pub fn from_context(context: &ParsingContext) -> Result<Self> {
+        let Some(platform) = context.enums.get("Platform") else {
+            return Err(Error::Platform("The sources don't define the Platform enum".to_string()));
+        };
+        let parents = platform
+            .entries
+            .iter()
+            .map(|entry| {
+                let parent = entry
+                    .parent_entry()
+                    .and_then(|name| platform.entry(name))
+                    .map(|parent| parent.schema_value.clone());
+                (entry.schema_value.clone(), parent)
+            })
+            .collect();
+        Ok(Platforms { parents })
+    }
+
+    /// Whether `platform` is a value of the `Platform` enum
+    pub fn contains(&self, platform: &str) -> bool {
+        self.parents.contains_key(platform)
+    }
+
+    /// The platform followed by its ancestors, up to `common`
+    pub fn lineage<'p>(&'p self, platform: &'p str) -> Vec<&'p str> {
+        let mut lineage = vec![platform];
+        let mut current = platform;
+        while let Some(Some(parent)) = self.parents.get(current) {
+            if lineage.contains(&parent.as_str()) {
+                break;
+            }
+            lineage.push(parent);
+            current = parent;
+        }
+        lineage
+    }
+
+    /// Whether one platform is the other or one of its ancestors
+    pub fn is_related(&self, a: &str, b: &str) -> bool {
+        self.lineage(a).contains(&b) || self.lineage(b).contains(&a)
+    }
+}
+
+/// The configuration a module gets on one platform
+#[derive(Debug)]
+pub struct Effective {
+    pub platform: String,
+    pub test: bool,
+    /// Merged files, templates first
+    pub files: Vec<String>,
+    pub settings: IndexMap<String, Resolved>,
+    pub dependencies: Vec<Resolved>,
+}
+
+/// A top-level section contributing to the result
+struct Layer<'d> {
+    test: bool,
+    rank: usize,
+    file: usize,
+    dependencies: bool,
+    node: &'d Node,
+}
+
+/// Resolve the configuration of the module at `module_file` for `platform`,
+/// including the test sections when `test` is set
+pub fn resolve(module_file: &Path, platform: &str, test: bool, platforms: &Platforms) -> Result<Effective> {
+    if !platforms.contains(platform) {
+        let known: Vec<&str> = platforms.parents.keys().map(String::as_str).collect();
+        return Err(Error::Platform(format!("Unknown platform `{}`. Expected one of: {}", platform, known.join(", "))));
+    }
+
+    let module_name = module_file.file_name().map_or_else(|| "module.yaml".into(), |name| name.to_string_lossy());
+    let module = load(module_file, &module_name)?;
+    check_targets(&module, platform, platforms)?;
+
+    // Templates are referenced relative to the module directory
+    let base = module_file.parent().unwrap_or(Path::new("."));
+    let mut documents = Vec::new();
+    for (reference, position) in workspace::applied_templates(&module) {
+        let path = base.join(reference);
+        if !path.is_file() {
+            return Err(Error::NotFound(format!(
+                "Template `{}` applied at {}:{}:{} does not exist",
+                reference, module_name, position.line, position.column
+            )));
+        }
+        documents.push((reference.to_string(), load(&path, reference)?));
+    }
+    documents.push((module_name.to_string(), module.clone()));
+
+    let lineage = platforms.lineage(platform);
+    // Aliases covering one of the platforms the module is resolved for
+    let aliases: Vec<String> = workspace::aliases(&module, Some(base))
+        .into_iter()
+        .filter(|(_, members)| members.iter().any(|member| lineage.contains(&member.as_str())))
+        .map(|(alias, _)| alias)
+        .collect();
+    let rank = |qualifier: Option<&str>| match qualifier {
+        None => Some(0),
+        Some(name) if aliases.iter().any(|alias| alias == name) => Some(1),
+        // More specific platforms are further from `common`
+        Some(name) => lineage.iter().position(|p| *p == name).map(|index| 2 + lineage.len() - index),
+    };
+
+    let mut layers = Vec::new();
+    for (file, (_, document)) in documents.iter().enumerate() {
+        let NodeValue::Mapping(entries) = &document.value else { continue };
+        for entry in entries {
+            let (name, qualifier) = match entry.key.split_once('@') {
+                Some((name, qualifier)) => (name, Some(qualifier)),
+                None => (entry.key.as_str(), None),
+            };
+            let (is_test, name) = match name.strip_prefix("test-") {
+                Some(name) => (true, name),
+                None => (false, name),
+            };
+            if (is_test && !test) || !matches!(name, "settings" | "dependencies") {
+                continue;
+            }
+            if let Some(rank) = rank(qualifier) {
+                layers.push(Layer { test: is_test, rank, file, dependencies: name == "dependencies", node: &entry.value });
+            }
+        }
+    }
+    layers.sort_by_key(|layer| (layer.test, layer.rank, layer.file));
+
+    let mut settings = Resolved::Map(IndexMap::new());
+    let mut dependencies: Vec<(String, Resolved)> = Vec::new();
+    for layer in layers {
+        let file = &documents[layer.file].0;
+        if !layer.dependencies {
+            if matches!(layer.node.value, NodeValue::Mapping(_)) {
+                settings.merge(Resolved::from_node(layer.node, file));
+            }
+            continue;
+        }
+        let NodeValue::Sequence(items) = &layer.node.value else { continue };
+        for item in items {
+            let key = workspace::dependency_notation(item)
+                .map_or_else(|| item.to_json().to_string(), |(notation, _)| notation.to_string());
+            let resolved = Resolved::from_node(item, file);
+            match dependencies.iter_mut().find(|(existing, _)| *existing == key) {
+                Some((_, existing)) => *existing = resolved,
+                None => dependencies.push((key, resolved)),
+            }
+        }
+    }
+
+    let Resolved::Map(settings) = settings else { unreachable!("settings start as a mapping") };
+    Ok(Effective {
+        platform: platform.to_string(),
+        test,
+        files: documents.into_iter().map(|(file, _)| file).collect(),
+        settings,
+        dependencies: dependencies.into_iter().map(|(_, dependency)| dependency).collect(),
+    })
+}
+
+fn load(path: &Path, label: &str) -> Result<Node> {
+    let content = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    yaml::parse(&content).map_err(|error| Error::Syntax { file: Some(label.to_string()), error })
+}
+
+/// Reject a platform unrelated to those listed in `product.platforms`
+fn check_targets(module: &Node, platform: &str, platforms: &Platforms) -> Result<()> {
+    let Some(NodeValue::Sequence(items)) =
+        workspace::top_level(module, "product").and_then(|product| workspace::top_level(product, "platforms")).map(|node| &node.value)
+    else {
+        return Ok(());
+    };
+    let targets: Vec<&str> = items.iter().filter_map(Node::scalar_text).collect();
+    if !targets.is_empty() && !targets.iter().any(|target| platforms.is_related(target, platform)) {
+        return Err(Error::Platform(format!(
+            "The module doesn't target `{}`. Its platforms are: {}",
+            platform,
+            targets.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+impl Effective {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "platform": self.platform,
+            "test": self.test,
+            "files": self.files,
+            "settings": Resolved::Map(self.settings.clone()).to_json(),
+            "dependencies": Resolved::List(self.dependencies.clone()).to_json(),
+        })
+    }
+
+    /// The configuration in `format`
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Yaml => self.to_yaml(),
+            OutputFormat::Json => format!("{:#}\n", self.to_json()),
+        }
+    }
+
+    /// YAML in Amper's layout, each scalar followed by a comment with its origin
+    pub fn to_yaml(&self) -> String {
+        let mut out = format!(
+            "# Effective {}configuration for {}\n# Merged from: {}\n",
+            if self.test { "test " } else { "" },
+            self.platform,
+            self.files.join(", ")
+        );
+        emit_entry(&mut out, "settings", &Resolved::Map(self.settings.clone()), 0);
+        emit_entry(&mut out, "dependencies", &Resolved::List(self.dependencies.clone()), 0);
+        out
+    }
+}
+
+fn emit_entry(out: &mut String, key: &str, value: &Resolved, indent: usize) {
+    let pad = " ".repeat(indent);
+    let key = scalar_text(&Value::String(key.to_string()));
+    match value {
+        Resolved::Scalar { value, origin } => out.push_str(&format!("{}{}: {}  # {}\n", pad, key, scalar_text(value), origin)),
+        Resolved::Map(entries) if entries.is_empty() => out.push_str(&format!("{}{}: {{}}\n", pad, key)),
+        Resolved::List(items) if items.is_empty() => out.push_str(&format!("{}{}: []\n", pad, key)),
+        Resolved::Map(entries) => {
+            out.push_str(&format!("{}{}:\n", pad, key));
+            for (key, value) in entries {
+                emit_entry(out, key, value, indent + 2);
+            }
+        }
+        Resolved::List(items) => {
+            out.push_str(&format!("{}{}:\n", pad, key));
+            for item in items {
+                emit_item(out, item, indent + 2);
+            }
+        }
+    }
+}
+
+fn emit_item(out: &mut String, item: &Resolved, indent: usize) {
+    let pad = " ".repeat(indent);
+    match item {
+        Resolved::Scalar { value, origin } => out.push_str(&format!("{}- {}  # {}\n", pad, scalar_text(value), origin)),
+        Resolved::Map(entries) if !entries.is_empty() => {
+            // The first entry of a mapping item shares the line of its dash
+            let mut nested = String::new();
+            for (key, value) in entries {
+                emit_entry(&mut nested, key, value, indent + 2);
+            }
+            out.push_str(&pad);
+            out.push_str("- ");
+            out.push_str(&nested[indent + 2..]);
+        }
+        Resolved::Map(_) => out.push_str(&format!("{}- {{}}\n", pad)),
+        Resolved::List(items) => {
+            out.push_str(&format!("{}-\n", pad));
+            for item in items {
+                emit_item(out, item, indent + 2);
+            }
+        }
+    }
+}
+
+/// A scalar as YAML, quoted only when it would otherwise read back differently
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(text) if yaml::is_plain_string(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,92 @@
+//! Errors of the extractor library
+
+use crate::yaml::SyntaxError;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A file, or the stream given by `path: None`, couldn't be read or written
+    Io { path: Option<PathBuf>, source: io::Error },
+    /// The directory isn't an Amper source tree: `expected` is missing from it
+    InvalidSources { expected: PathBuf },
+    /// A Kotlin file the parser can't make sense of
+    Kotlin { path: PathBuf, message: String },
+    /// A schema root or schema type that names no parsed class; `is_enum` when it names an enum
+    UnknownType { name: String, is_enum: bool },
+    /// Two schema definitions, described by `first` and `second`, would share `name`
+    DefinitionClash { name: String, first: &'static str, second: &'static str },
+    /// A type mapping file that can't be used
+    TypeMapping { path: PathBuf, message: String },
+    /// A schema file that isn't JSON
+    Schema { path: PathBuf, source: serde_json::Error },
+    /// A YAML document that doesn't parse, in `file` when the caller doesn't know it already
+    Syntax { file: Option<String>, error: SyntaxError },
+    /// A YAML document that can't be rewritten: it uses unsupported constructs,
+    /// or the rewrite would change its meaning
+    Unsupported(String),
+    /// A platform the sources or the module don't know
+    Platform(String),
+    /// A project directory, template or schema that doesn't exist
+    NotFound(String),
+    /// A malformed Language Server Protocol message
+    Protocol(String),
+    /// A lint rule ID that doesn't exist
+    UnknownRule(String),
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> Error {
+        let path = path.into();
+        move |source| Error::Io { path: Some(path), source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path: Some(path), source } => write!(f, "Failed to access {}: {}", path.display(), source),
+            Error::Io { path: None, source } => write!(f, "{}", source),
+            Error::InvalidSources { expected } => {
+                write!(f, "Invalid source directory. Expected to find: {}", expected.display())
+            }
+            Error::Kotlin { path, message } => write!(f, "Failed to parse {}: {}", path.display(), message),
+            Error::UnknownType { name, is_enum: true } => {
+                write!(f, "'{}' is an enum; the schema root must be a class", name)
+            }
+            Error::UnknownType { name, is_enum: false } => write!(
+                f,
+                "Unknown schema type '{}'. Use module, template, project or a class listed by `list-types`",
+                name
+            ),
+            Error::DefinitionClash { name, first, second } => {
+                write!(f, "Two schema definitions would be named '{}': {} and {}", name, first, second)
+            }
+            Error::TypeMapping { path, message } => write!(f, "Invalid type mapping {}: {}", path.display(), message),
+            Error::Schema { path, source } => write!(f, "Invalid JSON Schema {}: {}", path.display(), source),
+            Error::Syntax { file: Some(file), error } => write!(f, "{}:{}: syntax error", file, error),
+            Error::Syntax { file: None, error } => write!(f, "{}: syntax error", error),
+            Error::UnknownRule(id) => {
+                let known: Vec<&str> = crate::lint::RULES.iter().map(|rule| rule.id).collect();
+                write!(f, "Unknown lint rule `{}`. Expected one of: {}", id, known.join(", "))
+            }
+            Error::Unsupported(message)
+            | Error::Platform(message)
+            | Error::NotFound(message)
+            | Error::Protocol(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// The messages include the underlying I/O and JSON errors, so there is no `source`
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Error::Io { path: None, source }
+    }
+}
@@ -0,0 +1,258 @@
+//! Canonical layout of Amper configuration files
+//!
+//! Rewrites a file without changing what it means:
+//!
+//! - keys follow the declaration order of the Kotlin properties, with unknown
+//!   keys after the known ones in their original order
+//! - `@platform` and `test-` variants follow their base key: `settings`,
+//!   `settings@jvm`, `settings@ios`, `test-settings`, `test-settings@jvm`,
+//!   qualifiers in the order of the `Platform` enum
+//! - scalars are unquoted where that reads back the same, and double-quoted
+//!   otherwise
+//! - lists of enum values (like `platforms`) are written inline, other lists
+//!   of known properties one item per line
+//! - two spaces of indentation per level; comments are kept with the node
+//!   below them
+
+use crate::cst::{self, Entry, Item, Value};
+use crate::error::{Error, Result};
+use crate::mapping::TypeMapping;
+use crate::parser::with_builtin_roots;
+use crate::report::plural;
+use crate::root_type;
+use crate::shape::Shape;
+use crate::types::{ClassDef, ParsingContext};
+use crate::yaml;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Characters that end a plain scalar inside a flow sequence
+const FLOW_INDICATORS: &[char] = &[',', '[', ']', '{', '}'];
+
+/// What formatting did to one file
+#[derive(Debug)]
+pub enum Outcome {
+    Unchanged,
+    Reformatted,
+    /// The file was left as it is
+    Failed(Error),
+}
+
+/// Totals of formatting files, or with `check` of only checking them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub files: usize,
+    pub reformatted: usize,
+    pub failed: usize,
+    pub check: bool,
+}
+
+impl Summary {
+    pub fn new(outcomes: &[Outcome], check: bool) -> Self {
+        let count = |matches: fn(&Outcome) -> bool| outcomes.iter().filter(|outcome| matches(outcome)).count();
+        Summary {
+            files: outcomes.len(),
+            reformatted: count(|outcome| matches!(outcome, Outcome::Reformatted)),
+            failed: count(|outcome| matches!(outcome, Outcome::Failed(_))),
+            check,
+        }
+    }
+
+    /// No file failed, and with `check` none needs formatting
+    pub fn is_clean(&self) -> bool {
+        self.failed == 0 && (!self.check || self.reformatted == 0)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.check { "would be reformatted" } else { "reformatted" };
+        write!(f, "{} of {} {} {}", self.reformatted, self.files, plural(self.files, "file", "files"), verb)?;
+        if self.failed > 0 {
+            write!(f, "\n{} {} could not be formatted", self.failed, plural(self.failed, "file", "files"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Format configuration files, each against the root class of its kind
+/// (see [`with_builtin_roots`]).
+/// Reformatted files are written back unless `check` is set.
+pub fn format_files(
+    files: &[PathBuf],
+    context: &ParsingContext,
+    mapping: &TypeMapping,
+    check: bool,
+) -> Result<Vec<Outcome>> {
+    let context = &*with_builtin_roots(context);
+    let mut outcomes = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(file).map_err(Error::io(file))?;
+        let outcome = match format(&content, root_type(file), context, mapping) {
+            Ok(formatted) if formatted == content => Outcome::Unchanged,
+            Ok(formatted) => {
+                if !check {
+                    std::fs::write(file, formatted).map_err(Error::io(file))?;
+                }
+                Outcome::Reformatted
+            }
+            Err(error) => Outcome::Failed(error),
+        };
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// Format `source`, a configuration file whose root is the class `root`
+pub fn format(source: &str, root: &str, context: &ParsingContext, mapping: &TypeMapping) -> Result<String> {
+    let original = yaml::parse(source).map_err(|error| Error::Syntax { file: None, error })?;
+    let mut document = cst::parse(source)?;
+    let comments = cst::comments(&document);
+
+    let formatter = Formatter { context, mapping };
+    let shape = Shape::class(context, root);
+    formatter.canonicalize(&mut document.root, shape);
+    let formatted = cst::print(&document);
+
+    // Mapping order doesn't matter to the comparison, anything else does,
+    // and every comment must survive
+    let keeps_comments = || cst::parse(&formatted).is_ok_and(|result| cst::comments(&result) == comments);
+    match yaml::parse(&formatted) {
+        Ok(result) if result.to_json() == original.to_json() && keeps_comments() => Ok(formatted),
+        _ => Err(Error::Unsupported("formatting would change the meaning of the file or drop a comment; it was left unchanged".to_string())),
+    }
+}
+
+struct Formatter<'c> {
+    context: &'c ParsingContext,
+    mapping: &'c TypeMapping,
+}
+
+impl<'c> Formatter<'c> {
+    fn canonicalize(&self, value: &mut Value, shape: Shape<'c>) {
+        match value {
+            Value::Scalar(text) => *text = canonical_scalar(text, false),
+            // Lists of unknown values keep the style they were written in
+            Value::FlowSequence(items) if !shape.list => {
+                for item in items.iter_mut() {
+                    *item = canonical_scalar(item, true);
+                }
+            }
+            Value::FlowSequence(items) => {
+                let items: Vec<Item> = std::mem::take(items)
+                    .into_iter()
+                    .map(|text| Item { leading: Vec::new(), value: Value::Scalar(text), comment: None })
+                    .collect();
+                *value = Value::Sequence(items);
+                self.canonicalize(value, shape);
+            }
+            Value::Sequence(items) => {
+                let element = if shape.list { shape.element() } else { Shape::default() };
+                for item in items.iter_mut() {
+                    self.canonicalize(&mut item.value, element);
+                }
+                if shape.list
+                    && shape.enum_def.is_some()
+                    && let Some(inline) = inline_items(items)
+                {
+                    *value = Value::FlowSequence(inline);
+                }
+            }
+            Value::Mapping(entries) => {
+                for entry in entries.iter_mut() {
+                    entry.key = canonical_scalar(&entry.key, false);
+                }
+                if shape.map {
+                    for entry in entries.iter_mut() {
+                        self.canonicalize(&mut entry.value, shape.element());
+                    }
+                } else if let Some(class) = shape.class.filter(|_| !shape.list) {
+                    self.order_entries(entries, class);
+                } else {
+                    for entry in entries.iter_mut() {
+                        self.canonicalize(&mut entry.value, Shape::default());
+                    }
+                }
+            }
+            Value::Empty | Value::Flow(_) | Value::BlockScalar { .. } => {}
+        }
+    }
+
+    /// Sort the entries of an object of `class` and format their values
+    fn order_entries(&self, entries: &mut [Entry], class: &'c ClassDef) {
+        let keys: Vec<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+        let class = self.context.variant(class, &keys);
+        let properties = self.context.all_properties(class);
+        let platforms: Vec<&str> = self
+            .context
+            .enums
+            .get("Platform")
+            .map(|platform| platform.entries.iter().map(|entry| entry.schema_value.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut keyed: Vec<((usize, bool, usize, usize), Entry)> = Vec::new();
+        for (index, mut entry) in entries.iter().cloned().enumerate() {
+            let (name, qualifier) = match entry.key.split_once('@') {
+                Some((name, qualifier)) => (name, Some(qualifier)),
+                None => (entry.key.as_str(), None),
+            };
+            let (position, test) = match properties.iter().position(|p| p.name == name) {
+                Some(position) => (Some(position), false),
+                None => match name.strip_prefix("test-") {
+                    Some(base) => (properties.iter().position(|p| p.name == base), true),
+                    None => (None, false),
+                },
+            };
+            let qualifier_rank = match qualifier {
+                None => 0,
+                Some(qualifier) => platforms.iter().position(|p| *p == qualifier).map_or(platforms.len() + 1, |p| p + 1),
+            };
+
+            let shape = position.map(|position| Shape::of(&properties[position], self.context, self.mapping)).unwrap_or_default();
+            self.canonicalize(&mut entry.value, shape);
+            let order = match position {
+                Some(position) => (position, test, qualifier_rank, index),
+                None => (properties.len(), false, 0, index),
+            };
+            keyed.push((order, entry));
+        }
+
+        keyed.sort_by_key(|(order, _)| *order);
+        for (slot, (_, entry)) in entries.iter_mut().zip(keyed) {
+            *slot = entry;
+        }
+    }
+}
+
+/// The items of a sequence as flow items, if they are all simple scalars
+fn inline_items(items: &[Item]) -> Option<Vec<String>> {
+    items
+        .iter()
+        .map(|item| match &item.value {
+            Value::Scalar(text) if item.leading.is_empty() && item.comment.is_none() => {
+                let text = canonical_scalar(text, true);
+                (!text.contains(['[', ']', '{', '}', '#'])).then_some(text)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A scalar unquoted when that reads back the same, otherwise double-quoted.
+/// Plain scalars are kept as written.
+fn canonical_scalar(text: &str, in_flow: bool) -> String {
+    let needs_quotes = |value: &str| !yaml::is_plain_string(value) || (in_flow && value.contains(FLOW_INDICATORS));
+    if !text.starts_with(['"', '\'']) {
+        return match in_flow && text.contains(FLOW_INDICATORS) {
+            true => serde_json::to_string(text).unwrap_or_else(|_| text.to_string()),
+            false => text.to_string(),
+        };
+    }
+    match yaml::parse(text).map(|node| node.value) {
+        Ok(yaml::NodeValue::String(value)) if needs_quotes(&value) => {
+            serde_json::to_string(&value).unwrap_or_else(|_| text.to_string())
+        }
+        Ok(yaml::NodeValue::String(value)) => value,
+        _ => text.to_string(),
+    }
+}
@@ -0,0 +1,258 @@
+//! Amper Schema Extractor
+//!
+//! Parses the Kotlin sources of Amper's configuration language into a
+//! [`types::ParsingContext`] and builds on it: JSON Schema generation,
+//! validation, linting, formatting and migration of configuration files,
+//! and the language server. The `extract-schema` and `amper-lsp` binaries
+//! are front ends to this library.
+//!
+//! The parsed model is serializable with serde, and failures are reported as
+//! an [`Error`] so that embedding tools can tell them apart.
+
+pub mod check;
+mod cst;
+pub mod diff;
+mod error;
+pub mod effective;
+pub mod format;
+pub mod lint;
+pub mod lsp;
+pub mod mapping;
+pub mod migrate;
+pub mod parser;
+pub mod report;
+pub mod schema;
+mod shape;
+mod suggest;
+pub mod types;
+pub mod validate;
+pub mod workspace;
+pub mod yaml;
+
+use report::plural;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub use error::{Error, Result};
+pub use parser::{parse_source_directory, parse_sources};
+pub use schema::{SchemaBundle, SchemaDialect, SchemaFlavor, SchemaOptions, generate_json_schema, generate_schema_bundle};
+pub use types::{ClassDef, EnumArgument, EnumDef, EnumEntry, ParsingContext, Property};
+
+/// Schema types with their root class and the file written by `--out-dir`
+pub const SCHEMA_TYPES: &[(&str, &str, &str)] = &[
+    ("module", "Module", "module-schema.json"),
+    ("template", "Template", "template-schema.json"),
+    ("project", "Project", "project-schema.json"),
+];
+
+/// Shared definitions file written by `--out-dir`
+pub const DEFINITIONS_FILE: &str = "amper-definitions.json";
+
+/// Root class of a configuration file, by its name. Files that don't
+/// follow Amper's naming are module files.
+pub fn root_type(path: &Path) -> &'static str {
+    let kind = workspace::config_kind(path).unwrap_or("module");
+    SCHEMA_TYPES.iter().find(|(name, _, _)| *name == kind).map_or("Module", |(_, root, _)| *root)
+}
+
+/// Resolve a schema type to a class: a known schema type (`module`, `template`,
+/// `project`, in any case) or a parsed class name
+pub fn resolve_root_type<'a>(context: &'a ParsingContext, schema_type: &str) -> Result<&'a str> {
+    let class_name = SCHEMA_TYPES
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(schema_type))
+        .map_or(schema_type, |(_, root_type, _)| *root_type);
+
+    if let Some((name, _)) = context.classes.get_key_value(class_name) {
+        return Ok(name);
+    }
+    // Schema generation provides the roots of the known schema types
+    if let Some((_, root_type, _)) = SCHEMA_TYPES.iter().find(|(_, root_type, _)| *root_type == class_name)
+        && parser::with_builtin_roots(context).classes.contains_key(*root_type)
+    {
+        return Ok(root_type);
+    }
+    Err(Error::UnknownType {
+        name: schema_type.to_string(),
+        is_enum: context.enums.contains_key(class_name),
+    })
+}
+
+/// Parse the Kotlin sources below `source`
+pub fn load_context(source: &Path, verbose: bool) -> Result<types::ParsingContext> {
+    // Validate source directory
+    let frontend_api_path = source.join("frontend-api/src/org/jetbrains/amper/frontend/schema");
+    if !frontend_api_path.exists() {
+        return Err(Error::InvalidSources { expected: frontend_api_path });
+    }
+
+    // Parse Kotlin source files
+    let context = parser::parse_source_directory(source, verbose)?;
+
+    if verbose {
+        eprintln!("Parsed {} types, {} enums", context.classes.len(), context.enums.len());
+    }
+
+    Ok(context)
+}
+
+/// Generate the schema of every file kind into a store for validation.
+/// Returns the store and the name of the root schema of each kind.
+pub fn generated_schemas(
+    context: &types::ParsingContext,
+    options: &schema::SchemaOptions,
+) -> Result<(validate::SchemaStore, HashMap<&'static str, String>)> {
+    let bundle = generate_bundle(context, options)?;
+
+    let mut store = validate::SchemaStore::default();
+    let mut roots = HashMap::new();
+    store.insert(DEFINITIONS_FILE, bundle.definitions);
+    store.set_context(context.clone());
+    for (kind, root_type, file_name) in SCHEMA_TYPES {
+        store.insert(file_name, bundle.roots[*root_type].clone());
+        roots.insert(*kind, file_name.to_string());
+    }
+    Ok((store, roots))
+}
+
+/// Generate every root schema with the shared definitions, as `--out-dir` writes them
+pub fn generate_bundle(context: &ParsingContext, options: &SchemaOptions) -> Result<SchemaBundle> {
+    let roots: Vec<(&str, &str)> = SCHEMA_TYPES
+        .iter()
+        .map(|(_, root_type, file_name)| (*root_type, *file_name))
+        .collect();
+    schema::generate_schema_bundle(context, &roots, DEFINITIONS_FILE, options)
+}
+
+/// The files of a bundle by name: the definitions first, then the root schemas
+pub fn bundle_files(bundle: &SchemaBundle) -> Vec<(&'static str, &Value)> {
+    let mut files = vec![(DEFINITIONS_FILE, &bundle.definitions)];
+    for (_, root_type, file_name) in SCHEMA_TYPES {
+        if let Some(schema) = bundle.roots.get(*root_type) {
+            files.push((*file_name, schema));
+        }
+    }
+    files
+}
+
+/// Where `extract` writes the generated schemas
+#[derive(Debug, Clone, Copy)]
+pub enum Output<'a> {
+    /// The root of `schema_type` (see [`resolve_root_type`]) as a single schema file
+    File { path: &'a Path, schema_type: &'a str },
+    /// Every root schema and the shared definitions, as `--out-dir` writes them
+    Bundle(&'a Path),
+}
+
+/// Generate the schemas `output` asks for and write them.
+/// Returns the paths written, each reported with `verbose`.
+pub fn extract(context: &ParsingContext, output: &Output, options: &SchemaOptions, verbose: bool) -> Result<Vec<PathBuf>> {
+    let paths = match output {
+        Output::Bundle(out_dir) => write_bundle(&generate_bundle(context, options)?, out_dir)?,
+        Output::File { path, schema_type } => {
+            let root_type = resolve_root_type(context, schema_type)?;
+            write_schema(path, &generate_json_schema(context, root_type, options)?)?;
+            vec![path.to_path_buf()]
+        }
+    };
+    if verbose {
+        for path in &paths {
+            eprintln!("Successfully wrote schema to {}", path.display());
+        }
+    }
+    Ok(paths)
+}
+
+/// Every parsed class and enum with its property or entry count, one per line
+pub fn describe_types(context: &ParsingContext) -> String {
+    let mut listing = String::from("Classes:\n");
+    for class in context.classes.values() {
+        let count = context.all_properties(class).len();
+        listing.push_str(&format!("  {} ({} {})", class.name, count, plural(count, "property", "properties")));
+        if class.is_sealed {
+            listing.push_str(&format!(", sealed: {}", class.subclasses.join(", ")));
+        }
+        if let Some(parent) = &class.parent {
+            listing.push_str(&format!(", extends {}", parent));
+        }
+        listing.push('\n');
+    }
+
+    listing.push_str("Enums:\n");
+    for enum_def in context.enums.values() {
+        let count = enum_def.entries.len();
+        listing.push_str(&format!("  {} ({} {})\n", enum_def.name, count, plural(count, "entry", "entries")));
+    }
+    listing
+}
+
+/// Write every file of a bundle into `out_dir`, creating it if needed.
+/// Returns the paths written.
+pub fn write_bundle(bundle: &SchemaBundle, out_dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(out_dir).map_err(Error::io(out_dir))?;
+    let mut paths = Vec::new();
+    for (file_name, schema) in bundle_files(bundle) {
+        let path = out_dir.join(file_name);
+        write_schema(&path, schema)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Write a schema to `path` as pretty-printed JSON
+pub fn write_schema(path: &Path, schema: &Value) -> Result<()> {
+    std::fs::write(path, format!("{:#}", schema)).map_err(Error::io(path))
+}
+
+/// Read a schema file
+pub fn read_schema(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path).map_err(Error::io(path))?;
+    serde_json::from_str(&content).map_err(|source| Error::Schema { path: path.to_path_buf(), source })
+}
+
+/// Load a schema holding every definition: read from a schema file or an
+/// `--out-dir` directory, or generated for all roots from Amper sources
+pub fn load_definitions(path: &Path, verbose: bool) -> Result<Value> {
+    if !path.is_dir() {
+        return read_schema(path);
+    }
+    let definitions_file = path.join(DEFINITIONS_FILE);
+    if definitions_file.is_file() {
+        return read_schema(&definitions_file);
+    }
+
+    let context = load_context(path, verbose)?;
+    let roots: Vec<(&str, &str)> = SCHEMA_TYPES
+        .iter()
+        .filter(|(_, root_type, _)| context.classes.contains_key(*root_type))
+        .map(|(_, root_type, file_name)| (*root_type, *file_name))
+        .collect();
+    let bundle = schema::generate_schema_bundle(&context, &roots, DEFINITIONS_FILE, &SchemaOptions::default())?;
+    Ok(bundle.definitions)
+}
+
+/// Load the schemas to validate with from a schema file applied to every kind
+/// of file, or from a directory written by `--out-dir`. Returns the store and
+/// the name of the root schema of each kind, like [`generated_schemas`].
+pub fn load_schemas(path: &Path) -> Result<(validate::SchemaStore, HashMap<&'static str, String>)> {
+    let mut store = validate::SchemaStore::default();
+    let mut roots = HashMap::new();
+    if path.is_dir() {
+        for (kind, _, file_name) in SCHEMA_TYPES {
+            let file = path.join(file_name);
+            if file.is_file() {
+                roots.insert(*kind, store.load(&file)?);
+            }
+        }
+        if roots.is_empty() {
+            return Err(Error::NotFound(format!("No schemas found in {}", path.display())));
+        }
+    } else {
+        let name = store.load(path)?;
+        for (kind, _, _) in SCHEMA_TYPES {
+            roots.insert(*kind, name.clone());
+        }
+    }
+    Ok((store, roots))
+}
@@ -0,0 +1,347 @@
+//! Lints for mistakes the schema can't express
+//!
+//! Each rule has an ID to turn it off by, a severity and a section in the
+//! README explaining it. Rules see one file at a time; a module's platforms
+//! come from `product.platforms`, or from the defaults of its product type.
+
+use crate::effective::Platforms;
+use crate::error::{Error, Result};
+use crate::mapping::TypeMapping;
+use crate::parser::with_builtin_roots;
+use crate::report::{Finding, Rule, SYNTAX_ERROR, Severity, plural};
+use crate::root_type;
+use crate::workspace::{self, top_level};
+use crate::shape::Shape;
+use crate::types::{EnumDef, ParsingContext};
+use crate::yaml::{self, Node, NodeValue, Position};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+pub const PLATFORM_SPECIFIC_SETTING: Rule = Rule {
+    id: "platform-specific-setting",
+    severity: Severity::Error,
+    summary: "A `@PlatformSpecific` setting in a section for none of its platforms",
+    docs: "platform-specific-setting",
+};
+
+pub const UNTARGETED_QUALIFIER: Rule = Rule {
+    id: "untargeted-qualifier",
+    severity: Severity::Warning,
+    summary: "An `@platform` qualifier for a platform the module doesn't target",
+    docs: "untargeted-qualifier",
+};
+
+pub const ENUM_ORDER: Rule = Rule {
+    id: "enum-order",
+    severity: Severity::Warning,
+    summary: "A list of an order-sensitive enum not in declaration order",
+    docs: "enum-order",
+};
+
+pub const RULES: &[&Rule] = &[&PLATFORM_SPECIFIC_SETTING, &UNTARGETED_QUALIFIER, &ENUM_ORDER];
+
+/// A rule violation
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub rule: &'static Rule,
+    pub position: Position,
+    pub message: String,
+}
+
+/// The rules left enabled when the rules with the IDs in `disabled` are turned off
+pub fn enabled_rules(disabled: &[String]) -> Result<Vec<&'static Rule>> {
+    if let Some(unknown) = disabled.iter().find(|id| !RULES.iter().any(|rule| rule.id == id.as_str())) {
+        return Err(Error::UnknownRule(unknown.clone()));
+    }
+    Ok(RULES.iter().copied().filter(|rule| !disabled.iter().any(|id| id == rule.id)).collect())
+}
+
+/// Lint configuration files with `rules`, each against the root class of its kind
+/// (see [`with_builtin_roots`])
+pub fn lint_files(
+    files: &[PathBuf],
+    context: &ParsingContext,
+    mapping: &TypeMapping,
+    platforms: &Platforms,
+    rules: &[&Rule],
+) -> Result<Vec<Finding>> {
+    let context = &*with_builtin_roots(context);
+    let enabled = |rule: &Rule| rules.contains(&rule);
+    let mut findings = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(file).map_err(Error::io(file))?;
+        for diagnostic in lint(&content, root_type(file), file.parent(), context, mapping, platforms, &enabled) {
+            findings.push(Finding {
+                file: file.display().to_string(),
+                rule: diagnostic.rule,
+                message: diagnostic.message,
+                start: diagnostic.position,
+                end: None,
+                path: None,
+                fixes: Vec::new(),
+            });
+        }
+    }
+    Ok(findings)
+}
+
+/// Totals of a lint run, printed after its findings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub files: usize,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl Summary {
+    pub fn new(findings: &[Finding], files: usize) -> Self {
+        let count = |severity| findings.iter().filter(|finding| finding.rule.severity == severity).count();
+        Summary { files, errors: count(Severity::Error), warnings: count(Severity::Warning) }
+    }
+
+    /// Whether the run fails: on errors, and with `deny_warnings` on warnings too
+    pub fn fails(&self, deny_warnings: bool) -> bool {
+        self.errors > 0 || (deny_warnings && self.warnings > 0)
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = format!("{} {}", self.files, plural(self.files, "file", "files"));
+        if self.errors + self.warnings == 0 {
+            return write!(f, "No problems found in {}", files);
+        }
+        write!(
+            f,
+            "{} {} and {} {} in {}",
+            self.errors,
+            plural(self.errors, "error", "errors"),
+            self.warnings,
+            plural(self.warnings, "warning", "warnings"),
+            files
+        )
+    }
+}
+
+/// A finding as a line of text output: `file:line:column: severity[rule]: message`
+pub fn text_line(finding: &Finding) -> String {
+    let rule = finding.rule;
+    format!(
+        "{}:{}:{}: {}[{}]: {}",
+        finding.file, finding.start.line, finding.start.column, rule.severity, rule.id, finding.message
+    )
+}
+
+/// Lint `source`, a configuration file whose root is the class `root`.
+/// Rules for which `enabled` returns false are skipped. `base`, the directory of the
+/// file, locates the templates it applies and the aliases they declare.
+pub fn lint(
+    source: &str,
+    root: &str,
+    base: Option<&Path>,
+    context: &ParsingContext,
+    mapping: &TypeMapping,
+    platforms: &Platforms,
+    enabled: &dyn Fn(&Rule) -> bool,
+) -> Vec<Diagnostic> {
+    let document = match yaml::parse(source) {
+        Ok(document) => document,
+        Err(error) => {
+            let message = format!("Syntax error: {}", error.message);
+            return vec![Diagnostic { rule: &SYNTAX_ERROR, position: error.position, message }];
+        }
+    };
+    let mut linter = Linter {
+        context,
+        mapping,
+        platforms,
+        enabled,
+        targets: module_targets(&document, context),
+        aliases: workspace::aliases(&document, base),
+        diagnostics: Vec::new(),
+    };
+
+    let NodeValue::Mapping(entries) = &document.value else { return Vec::new() };
+    let properties = context.classes.get(root).map(|class| context.all_properties(class)).unwrap_or_default();
+    for entry in entries {
+        let (name, qualifier) = match entry.key.split_once('@') {
+            Some((name, qualifier)) => (name, Some(qualifier)),
+            None => (entry.key.as_str(), None),
+        };
+        let base = name.strip_prefix("test-").unwrap_or(name);
+        let Some(property) = properties.iter().find(|p| p.name == name || p.name == base) else { continue };
+
+        let section = match qualifier {
+            Some(qualifier) => linter.qualified_section(&entry.key, qualifier, entry.key_position),
+            None => linter.targets.clone(),
+        };
+        linter.walk(&entry.value, Shape::of(property, context, mapping), &entry.key, section.as_deref());
+    }
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.position);
+    diagnostics
+}
+
+struct Linter<'c> {
+    context: &'c ParsingContext,
+    mapping: &'c TypeMapping,
+    platforms: &'c Platforms,
+    enabled: &'c dyn Fn(&Rule) -> bool,
+    /// Platforms of the module, when they are known
+    targets: Option<Vec<String>>,
+    aliases: Vec<(String, Vec<String>)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'c> Linter<'c> {
+    fn report(&mut self, rule: &'static Rule, position: Position, message: String) {
+        if (self.enabled)(rule) {
+            self.diagnostics.push(Diagnostic { rule, position, message });
+        }
+    }
+
+    /// The platforms a section qualified with `@qualifier` applies to, if known
+    fn qualified_section(&mut self, key: &str, qualifier: &str, position: Position) -> Option<Vec<String>> {
+        let members = match self.aliases.iter().find(|(name, _)| name == qualifier) {
+            Some((_, members)) => members.clone(),
+            None if self.platforms.contains(qualifier) => vec![qualifier.to_string()],
+            // Unknown qualifiers are reported by the schema
+            None => return None,
+        };
+        let Some(targets) = &self.targets else { return Some(members) };
+
+        // The more specific of each related target and member
+        let mut section = Vec::new();
+        for target in targets {
+            for member in &members {
+                if self.platforms.lineage(member).contains(&target.as_str()) {
+                    section.push(member.clone());
+                } else if self.platforms.lineage(target).contains(&member.as_str()) {
+                    section.push(target.clone());
+                }
+            }
+        }
+        if section.is_empty() {
+            let message = format!(
+                "`{}` applies to none of the module's platforms: {}",
+                key,
+                targets.join(", ")
+            );
+            self.report(&UNTARGETED_QUALIFIER, position, message);
+        }
+        Some(section)
+    }
+
+    /// Check a value of `shape` in a section `key` applying to `section` platforms
+    fn walk(&mut self, node: &Node, shape: Shape<'c>, key: &str, section: Option<&[String]>) {
+        match &node.value {
+            NodeValue::Mapping(entries) if shape.map => {
+                for entry in entries {
+                    self.walk(&entry.value, shape.element(), key, section);
+                }
+            }
+            NodeValue::Mapping(entries) => {
+                let Some(class) = shape.class.filter(|_| !shape.list) else { return };
+                let keys: Vec<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+                let properties = self.context.all_properties(self.context.variant(class, &keys));
+                for entry in entries {
+                    let Some(property) = properties.iter().find(|p| p.name == entry.key) else { continue };
+                    // Sections for none of the module's platforms are reported already
+                    if let Some(section) = section.filter(|section| !section.is_empty()) {
+                        let specific = property.get_platform_specific();
+                        self.check_platform_specific(&entry.key, &specific, key, section, entry.key_position);
+                    }
+                    let shape = Shape::of(property, self.context, self.mapping);
+                    self.walk(&entry.value, shape, key, section);
+                }
+            }
+            NodeValue::Sequence(items) if shape.list => {
+                if let Some(enum_def) = shape.enum_def.filter(|enum_def| enum_def.is_order_sensitive) {
+                    self.check_order(items, enum_def);
+                }
+                for item in items {
+                    self.walk(item, shape.element(), key, section);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_platform_specific(
+        &mut self,
+        name: &str,
+        specific: &[String],
+        key: &str,
+        section: &[String],
+        position: Position,
+    ) {
+        let Some(platform_enum) = self.context.enums.get("Platform") else { return };
+        let specific: Vec<&str> = specific
+            .iter()
+            .filter_map(|value| platform_enum.entry(value.rsplit('.').next().unwrap_or(value)))
+            .map(|entry| entry.schema_value.as_str())
+            .collect();
+        if specific.is_empty()
+            || section.iter().any(|platform| specific.iter().any(|p| self.platforms.is_related(platform, p)))
+        {
+            return;
+        }
+        let message = format!(
+            "`{}` only applies to {}, but `{}` applies to {}",
+            name,
+            specific.join(", "),
+            key,
+            section.join(", ")
+        );
+        self.report(&PLATFORM_SPECIFIC_SETTING, position, message);
+    }
+
+    fn check_order(&mut self, items: &[Node], enum_def: &EnumDef) {
+        let index = |node: &Node| {
+            let text = node.scalar_text()?;
+            let position = enum_def.entries.iter().position(|entry| entry.schema_value == text)?;
+            Some(if enum_def.is_order_reversed { enum_def.entries.len() - position } else { position })
+        };
+        let mut previous: Option<(usize, &str)> = None;
+        for item in items {
+            let (Some(current), Some(text)) = (index(item), item.scalar_text()) else { continue };
+            if let Some((last, last_text)) = previous
+                && current < last
+            {
+                let order = if enum_def.is_order_reversed { "reverse declaration order" } else { "declaration order" };
+                let message = format!(
+                    "`{}` is listed after `{}`; `{}` values go in {}",
+                    text, last_text, enum_def.name, order
+                );
+                self.report(&ENUM_ORDER, item.position, message);
+                return;
+            }
+            previous = Some((current, text));
+        }
+    }
+}
+
+/// The platforms a module builds for: `product.platforms`, or the default
+/// platforms of its product type
+fn module_targets(document: &Node, context: &ParsingContext) -> Option<Vec<String>> {
+    let product = top_level(document, "product")?;
+    if let Some(NodeValue::Sequence(items)) = top_level(product, "platforms").map(|node| &node.value) {
+        return Some(items.iter().filter_map(Node::scalar_text).map(str::to_string).collect());
+    }
+
+    let product_type = match &product.value {
+        NodeValue::Mapping(_) => top_level(product, "type")?.scalar_text()?,
+        _ => product.scalar_text()?,
+    };
+    let platform_enum = context.enums.get("Platform")?;
+    let defaults = context.enums.get("ProductType")?.entry_by_value(product_type)?.default_platforms()?;
+    Some(
+        defaults
+            .iter()
+            .filter_map(|name| platform_enum.entry(name))
+            .map(|entry| entry.schema_value.clone())
+            .collect(),
+    )
+}
+
@@ -0,0 +1,257 @@
+//! Completion of keys, values, `@platform` qualifiers, local paths and catalog references
+
+use super::document::{self, Cursor, Place, Segment};
+use super::hover::{property_docs, type_label};
+use super::{Server, project_root, relative_path};
+use crate::migrate::split_modifiers;
+use crate::types::EnumDef;
+use crate::{root_type, workspace, yaml};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// `CompletionItemKind`s
+const PROPERTY: u8 = 10;
+const VALUE: u8 = 12;
+const FILE: u8 = 17;
+const REFERENCE: u8 = 18;
+const FOLDER: u8 = 19;
+const ENUM_MEMBER: u8 = 20;
+
+/// Catalog of a project, relative to its root
+const CATALOG_FILE: &str = "gradle/libs.versions.toml";
+
+/// A completion item before it is placed in the document
+struct Candidate {
+    label: String,
+    kind: u8,
+    detail: Option<String>,
+    docs: Option<String>,
+    deprecated: bool,
+}
+
+/// Completion items for the token at `cursor` in the document `uri`
+pub fn complete(server: &Server, uri: &str, text: &str, cursor: &Cursor) -> Value {
+    let file = document::uri_to_path(uri).unwrap_or_default();
+    let root = root_type(&file);
+
+    let mut candidates = Vec::new();
+    match &cursor.place {
+        Place::Key => keys(server, root, &file, text, cursor, &mut candidates),
+        Place::Value(key) => {
+            let mut path = cursor.path.clone();
+            path.push(Segment::Key(key.clone()));
+            values(server, root, &path, &file, &mut candidates);
+        }
+        Place::Item => {
+            values(server, root, &cursor.path, &file, &mut candidates);
+            // `- coordinates: ...`: the item may be an object being typed
+            keys(server, root, &file, text, cursor, &mut candidates);
+        }
+    }
+
+    let range = document::to_lsp_range(text, cursor.start, cursor.end);
+    let items: Vec<Value> = candidates
+        .into_iter()
+        .map(|candidate| {
+            let new_text = match (&cursor.place, candidate.kind) {
+                (Place::Key | Place::Item, PROPERTY) if !cursor.has_colon => format!("{}: ", candidate.label),
+                _ => candidate.label.clone(),
+            };
+            let mut item = json!({
+                "label": candidate.label,
+                "kind": candidate.kind,
+                "textEdit": { "range": range, "newText": new_text },
+            });
+            if let Some(detail) = candidate.detail {
+                item["detail"] = json!(detail);
+            }
+            if let Some(docs) = candidate.docs {
+                item["documentation"] = json!({ "kind": "markdown", "value": docs });
+            }
+            if candidate.deprecated {
+                item["tags"] = json!([1]);
+            }
+            item
+        })
+        .collect();
+    json!({ "isIncomplete": false, "items": items })
+}
+
+/// Keys of the object at the cursor, or qualified forms of a key typed up to its `@`
+fn keys(server: &Server, root: &str, file: &Path, text: &str, cursor: &Cursor, candidates: &mut Vec<Candidate>) {
+    let Some((shape, _)) = server.resolve(root, &cursor.path) else { return };
+    let Some(class) = shape.class.filter(|_| !shape.list && !shape.map) else { return };
+    let properties = server.properties(class);
+
+    if let Some((key, _)) = cursor.prefix.split_once('@') {
+        let (_, name, _) = split_modifiers(key);
+        if !properties.iter().any(|p| p.name == name && p.is_modifier_aware()) {
+            return;
+        }
+        for (qualifier, detail, docs) in qualifiers(server, file, text, cursor) {
+            candidates.push(Candidate {
+                label: format!("{}@{}", key, qualifier),
+                kind: PROPERTY,
+                detail: Some(detail),
+                docs,
+                deprecated: false,
+            });
+        }
+        return;
+    }
+
+    for property in properties.iter().filter(|p| !p.is_hidden()) {
+        candidates.push(Candidate {
+            label: property.name.clone(),
+            kind: PROPERTY,
+            detail: Some(type_label(property)),
+            docs: Some(property_docs(property)),
+            deprecated: property.deprecation_message().is_some(),
+        });
+    }
+}
+
+/// Platforms and the aliases the document and its templates declare, with a detail and docs
+fn qualifiers(server: &Server, file: &Path, text: &str, cursor: &Cursor) -> Vec<(String, String, Option<String>)> {
+    let mut qualifiers: Vec<_> = server
+        .context
+        .enums
+        .get("Platform")
+        .map(|platforms| {
+            platforms
+                .entries
+                .iter()
+                .map(|entry| (entry.schema_value.clone(), "Platform".to_string(), entry.doc.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    // The line being typed usually breaks the document; the rest tells the aliases
+    let without_cursor_line: Vec<&str> = text
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| if index + 1 == cursor.start.line { "" } else { line })
+        .collect();
+    if let Ok(document) = yaml::parse(text).or_else(|_| yaml::parse(&without_cursor_line.join("\n"))) {
+        for (alias, members) in workspace::aliases(&document, file.parent()) {
+            qualifiers.push((alias, format!("Alias of {}", members.join(", ")), None));
+        }
+    }
+    qualifiers
+}
+
+/// Values for the node at `path`
+fn values(server: &Server, root: &str, path: &[Segment], file: &Path, candidates: &mut Vec<Candidate>) {
+    let Some((shape, property)) = server.resolve(root, path) else { return };
+    // The items of a list go on lines of their own
+    if shape.list || shape.map {
+        return;
+    }
+    if let Some(enum_def) = shape.enum_def {
+        enum_values(enum_def, candidates);
+    }
+
+    if let Some(class) = shape.class {
+        let properties = server.properties(class);
+        for shorthand in properties.iter().filter(|p| p.is_shorthand()) {
+            let type_name = server.mapping.unwrap_all(&shorthand.type_name);
+            match server.context.enums.get(type_name) {
+                Some(enum_def) => enum_values(enum_def, candidates),
+                // `compose: enabled` stands for `enabled: true`
+                None if type_name == "Boolean" => candidates.push(Candidate {
+                    label: shorthand.name.clone(),
+                    kind: VALUE,
+                    detail: Some(format!("{}: true", shorthand.name)),
+                    docs: shorthand.doc.clone(),
+                    deprecated: false,
+                }),
+                None => {}
+            }
+        }
+        // Dependencies on other modules and on catalog entries
+        if properties.iter().any(|p| p.name == "path") {
+            local_paths(file, false, candidates);
+        }
+        if properties.iter().any(|p| p.name == "catalogKey") {
+            catalog_references(file, candidates);
+        }
+        return;
+    }
+
+    let Some(property) = property else { return };
+    match server.mapping.unwrap_all(&property.type_name) {
+        "Boolean" => {
+            for value in ["true", "false"] {
+                candidates.push(Candidate { label: value.to_string(), kind: VALUE, detail: None, docs: None, deprecated: false });
+            }
+        }
+        // Templates are applied by `apply`; other paths point to modules
+        "Path" => local_paths(file, property.name == "apply", candidates),
+        _ => {}
+    }
+}
+
+fn enum_values(enum_def: &EnumDef, candidates: &mut Vec<Candidate>) {
+    for entry in &enum_def.entries {
+        candidates.push(Candidate {
+            label: entry.schema_value.clone(),
+            kind: ENUM_MEMBER,
+            detail: Some(enum_def.name.clone()),
+            docs: entry.doc.clone(),
+            deprecated: entry.is_outdated || entry.replacement.is_some(),
+        });
+    }
+}
+
+/// Modules, or templates, of the project relative to `file`
+fn local_paths(file: &Path, templates: bool, candidates: &mut Vec<Candidate>) {
+    let Some(dir) = file.parent() else { return };
+    let wanted = if templates { "template" } else { "module" };
+    for config in workspace::collect_config_files(&[project_root(dir)]) {
+        if workspace::config_kind(&config) != Some(wanted) {
+            continue;
+        }
+        let (target, kind): (PathBuf, u8) = if templates {
+            (config, FILE)
+        } else {
+            (config.parent().unwrap_or(&config).to_path_buf(), FOLDER)
+        };
+        if target == dir {
+            continue;
+        }
+        candidates.push(Candidate {
+            label: relative_path(dir, &target),
+            kind,
+            detail: Some(wanted.to_string()),
+            docs: None,
+            deprecated: false,
+        });
+    }
+}
+
+/// `$libs.*` references to the libraries of the project's version catalog
+fn catalog_references(file: &Path, candidates: &mut Vec<Candidate>) {
+    let Some(dir) = file.parent() else { return };
+    let Ok(content) = std::fs::read_to_string(project_root(dir).join(CATALOG_FILE)) else { return };
+    let Ok(catalog) = content.parse::<toml::Table>() else { return };
+    let Some(libraries) = catalog.get("libraries").and_then(toml::Value::as_table) else { return };
+
+    for (alias, library) in libraries {
+        let notation = match library {
+            toml::Value::String(notation) => Some(notation.clone()),
+            toml::Value::Table(table) => table.get("module").and_then(toml::Value::as_str).map(str::to_string).or_else(|| {
+                let group = table.get("group")?.as_str()?;
+                let name = table.get("name")?.as_str()?;
+                Some(format!("{}:{}", group, name))
+            }),
+            _ => None,
+        };
+        candidates.push(Candidate {
+            label: format!("$libs.{}", alias.replace(['-', '_'], ".")),
+            kind: REFERENCE,
+            detail: notation,
+            docs: None,
+            deprecated: false,
+        });
+    }
+}
+
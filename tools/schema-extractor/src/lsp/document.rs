@@ -0,0 +1,288 @@
+//! Positions in open documents, and what the cursor is on
+//!
+//! Completion happens in half-typed files that rarely parse, so the cursor
+//! is placed by indentation alone: the lines above it with less indentation
+//! give the keys and list items leading to it.
+
+use crate::cst;
+use crate::report;
+use crate::yaml::Position;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+
+/// A step from a mapping or list to one of its values
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Item,
+}
+
+/// What the token at the cursor is
+#[derive(Debug, Clone, PartialEq)]
+pub enum Place {
+    /// A key of the mapping at the path
+    Key,
+    /// The value of the key in the mapping at the path
+    Value(String),
+    /// A scalar list item; the path ends with [`Segment::Item`]
+    Item,
+}
+
+/// The token at the cursor and where it is in the document
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    /// Keys and list items from the root to the mapping or list holding the token
+    pub path: Vec<Segment>,
+    pub place: Place,
+    /// The token up to the cursor
+    pub prefix: String,
+    /// The whole token, quotes included
+    pub token: String,
+    /// 1-based line and character columns of the token
+    pub start: Position,
+    pub end: Position,
+    /// Whether the line has a `:` after the token
+    pub has_colon: bool,
+}
+
+impl Cursor {
+    /// The token without its quotes
+    pub fn text(&self) -> &str {
+        let token = self.token.as_str();
+        match token.chars().next() {
+            Some(quote @ ('"' | '\'')) => token[1..].strip_suffix(quote).unwrap_or(&token[1..]),
+            _ => token,
+        }
+    }
+}
+
+/// The token at a 1-based `position` of `text`
+pub fn cursor_at(text: &str, position: Position) -> Option<Cursor> {
+    let lines: Vec<&str> = text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+    let index = position.line.checked_sub(1)?;
+    let line = *lines.get(index)?;
+    let cursor = line.char_indices().nth(position.column.checked_sub(1)?).map_or(line.len(), |(i, _)| i);
+
+    let (dashes, content) = structure(line);
+    if cursor < content || line[content..].starts_with('#') {
+        return None;
+    }
+    let mut path = match dashes.first() {
+        Some(&dash) => parents(&lines[..index], dash, true),
+        None => parents(&lines[..index], content, false),
+    };
+    path.extend(dashes.iter().map(|_| Segment::Item));
+
+    let entry = mapping_key(&line[content..]).map(|(key, colon)| (key, content + colon));
+    let has_colon = entry.is_some();
+    let (place, start, end) = match entry {
+        Some((key, colon)) if cursor > colon => {
+            let value_start = colon + 1 + (line[colon + 1..].len() - line[colon + 1..].trim_start().len());
+            let value = &line[value_start..];
+            if value.starts_with('[') && cursor > value_start {
+                // An item of a flow list: `platforms: [jvm, |`
+                let item = line[..cursor].rfind(['[', ',']).unwrap_or(value_start) + 1;
+                let item = item + (line[item..].len() - line[item..].trim_start().len());
+                let end = line[item..].find([',', ']']).map_or(line.len(), |i| item + i);
+                path.push(Segment::Key(key));
+                path.push(Segment::Item);
+                (Place::Item, item.min(cursor), end)
+            } else {
+                let end = value_start + strip_comment(value).trim_end().len();
+                (Place::Value(key), value_start.min(cursor), end.max(cursor))
+            }
+        }
+        Some((_, colon)) => (Place::Key, content, colon),
+        None if !dashes.is_empty() => (Place::Item, content, content + strip_comment(&line[content..]).trim_end().len()),
+        None => (Place::Key, content, content + strip_comment(&line[content..]).trim_end().len()),
+    };
+    let end = end.max(cursor);
+    let column = |byte: usize| line[..byte].chars().count() + 1;
+
+    Some(Cursor {
+        path,
+        place,
+        prefix: line[start..cursor].to_string(),
+        token: line[start..end].trim_end().to_string(),
+        start: Position { line: position.line, column: column(start) },
+        end: Position { line: position.line, column: column(end) },
+        has_colon,
+    })
+}
+
+/// Keys and list items leading to a node whose content starts at byte
+/// `column` of the line after `lines`. A `sequence` starting there may be
+/// indented as much as the key holding it.
+fn parents(lines: &[&str], column: usize, mut sequence: bool) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = column;
+    for line in lines.iter().rev() {
+        if current == 0 && !sequence {
+            break;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+            continue;
+        }
+        let (dashes, content) = structure(line);
+        let indent = dashes.first().copied().unwrap_or(content);
+        let holds_sequence = sequence && dashes.is_empty() && indent == current;
+        if indent > current || (indent == current && !holds_sequence) {
+            continue;
+        }
+
+        if content < current || holds_sequence {
+            match mapping_key(&line[content..]) {
+                Some((key, colon)) if strip_comment(&line[content + colon + 1..]).trim().is_empty() => {
+                    segments.push(Segment::Key(key));
+                }
+                // A scalar can't hold anything; the document is broken here
+                _ => break,
+            }
+            segments.extend(dashes.iter().map(|_| Segment::Item));
+        } else {
+            // A sibling key in the same list item
+            segments.extend(dashes.iter().filter(|dash| **dash < current).map(|_| Segment::Item));
+        }
+        current = indent;
+        sequence = false;
+    }
+    segments.reverse();
+    segments
+}
+
+/// Byte columns of the `- ` markers starting a line, and of its content
+fn structure(line: &str) -> (Vec<usize>, usize) {
+    let skip_spaces = |from: usize| from + (line[from..].len() - line[from..].trim_start_matches(' ').len());
+    let mut dashes = Vec::new();
+    let mut column = skip_spaces(0);
+    while line[column..] == *"-" || line[column..].starts_with("- ") {
+        dashes.push(column);
+        column = skip_spaces(column + 1);
+    }
+    (dashes, column)
+}
+
+/// The key of a `key: value` line and the byte offset of its colon
+fn mapping_key(content: &str) -> Option<(String, usize)> {
+    if content.starts_with(['#', '[', '{', '-']) {
+        return None;
+    }
+    let search_from = match content.chars().next() {
+        Some('"' | '\'') => cst::quoted_len(content)?,
+        _ => 0,
+    };
+    let bytes = content.as_bytes();
+    let colon = (search_from..bytes.len())
+        .find(|&i| bytes[i] == b':' && bytes.get(i + 1).is_none_or(|next| *next == b' '))?;
+    let key = content[..colon].trim();
+    let key = match key.chars().next() {
+        Some(quote @ ('"' | '\'')) => key[1..].strip_suffix(quote).unwrap_or(&key[1..]),
+        _ => key,
+    };
+    Some((key.to_string(), colon))
+}
+
+fn strip_comment(text: &str) -> &str {
+    if text.starts_with('#') {
+        return "";
+    }
+    text.find(" #").map_or(text, |index| &text[..index])
+}
+
+/// The end of the key or scalar starting at `position`, for diagnostics
+/// that only know where they start
+pub fn token_end(text: &str, position: Position) -> Position {
+    let line = text.lines().nth(position.line - 1).unwrap_or("");
+    let rest: String = line.chars().skip(position.column - 1).collect();
+    let length = match rest.chars().next() {
+        Some('"' | '\'') => cst::quoted_len(&rest).map_or(rest.len(), |len| rest[..len].chars().count()),
+        _ => {
+            let mut end = strip_comment(&rest).len();
+            if let Some(index) = rest.find(": ") {
+                end = end.min(index);
+            }
+            if let Some(index) = rest.find([',', ']', '}']) {
+                end = end.min(index);
+            }
+            let token = rest[..end].trim_end();
+            token.strip_suffix(':').unwrap_or(token).chars().count()
+        }
+    };
+    Position { line: position.line, column: position.column + length }
+}
+
+/// An LSP position: 0-based line and UTF-16 offset
+pub fn to_lsp(text: &str, position: Position) -> Value {
+    let line = text.lines().nth(position.line.saturating_sub(1)).unwrap_or("");
+    let character: usize = line.chars().take(position.column.saturating_sub(1)).map(char::len_utf16).sum();
+    json!({ "line": position.line.saturating_sub(1), "character": character })
+}
+
+pub fn to_lsp_range(text: &str, start: Position, end: Position) -> Value {
+    json!({ "start": to_lsp(text, start), "end": to_lsp(text, end) })
+}
+
+/// The position of an LSP position in `text`
+pub fn from_lsp(text: &str, position: &Value) -> Option<Position> {
+    let line = usize::try_from(position["line"].as_u64()?).ok()?;
+    let character = usize::try_from(position["character"].as_u64()?).ok()?;
+    let mut units = 0;
+    let mut column = 1;
+    for c in text.lines().nth(line).unwrap_or("").chars() {
+        if units >= character {
+            break;
+        }
+        units += c.len_utf16();
+        column += 1;
+    }
+    Some(Position { line: line + 1, column })
+}
+
+/// The range of the whole of `text`
+pub fn full_range(text: &str) -> Value {
+    let lines = text.split('\n').count();
+    let last = text.rsplit('\n').next().unwrap_or("");
+    json!({
+        "start": { "line": 0, "character": 0 },
+        "end": { "line": lines - 1, "character": last.encode_utf16().count() },
+    })
+}
+
+/// The file a `file://` URI names
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `/C:/project` on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// The `file://` URI of an absolute path
+pub fn path_to_uri(path: &std::path::Path) -> String {
+    let path = path.display().to_string().replace('\\', "/");
+    let path = if path.starts_with('/') { path } else { format!("/{}", path) };
+    format!("file://{}", report::artifact_uri(&path))
+}
@@ -0,0 +1,90 @@
+//! Hover documentation of keys and enum values, from `@SchemaDoc`
+
+use super::Server;
+use super::document::{self, Cursor, Place, Segment};
+use crate::migrate::split_modifiers;
+use crate::root_type;
+use crate::types::{EnumDef, Property};
+use serde_json::{Value, json};
+
+/// The documentation of the key or value at `cursor` in the document `uri`
+pub fn hover(server: &Server, uri: &str, text: &str, cursor: &Cursor) -> Option<Value> {
+    let root = root_type(&document::uri_to_path(uri).unwrap_or_default());
+
+    let contents = match &cursor.place {
+        Place::Key => {
+            let (shape, _) = server.resolve(root, &cursor.path)?;
+            let class = shape.class.filter(|_| !shape.list && !shape.map)?;
+            let (_, name, _) = split_modifiers(cursor.text());
+            let property = server.properties(class).into_iter().find(|p| p.name == name)?;
+            property_hover(&property)
+        }
+        Place::Value(key) => {
+            let mut path = cursor.path.clone();
+            path.push(Segment::Key(key.clone()));
+            let (shape, property) = server.resolve(root, &path)?;
+            let value = shape.enum_def.and_then(|enum_def| value_hover(enum_def, cursor.text()));
+            let shorthand = || {
+                let class = shape.class?;
+                server.properties(class).iter().filter(|p| p.is_shorthand()).find_map(|shorthand| {
+                    let enum_def = server.context.enums.get(server.mapping.unwrap_all(&shorthand.type_name))?;
+                    value_hover(enum_def, cursor.text())
+                })
+            };
+            value.or_else(shorthand).or_else(|| property.as_ref().map(property_hover))?
+        }
+        Place::Item => {
+            let (shape, _) = server.resolve(root, &cursor.path)?;
+            value_hover(shape.enum_def?, cursor.text())?
+        }
+    };
+
+    Some(json!({
+        "contents": { "kind": "markdown", "value": contents },
+        "range": document::to_lsp_range(text, cursor.start, cursor.end),
+    }))
+}
+
+fn property_hover(property: &Property) -> String {
+    format!("**{}**: `{}`\n\n{}", property.name, type_label(property), property_docs(property))
+}
+
+fn value_hover(enum_def: &EnumDef, value: &str) -> Option<String> {
+    let entry = enum_def.entry_by_value(value)?;
+    let mut hover = format!("**{}**: `{}`", entry.schema_value, enum_def.name);
+    if let Some(doc) = entry.doc.as_ref().or(enum_def.doc.as_ref()) {
+        hover.push_str(&format!("\n\n{}", doc));
+    }
+    if entry.is_outdated {
+        hover.push_str("\n\n*Outdated*");
+    }
+    Some(hover)
+}
+
+/// The `@SchemaDoc` of a property with its deprecation, as Markdown
+pub fn property_docs(property: &Property) -> String {
+    let mut docs = property.doc.clone().unwrap_or_default();
+    if let Some(message) = property.deprecation_message() {
+        if !docs.is_empty() {
+            docs.push_str("\n\n");
+        }
+        match message.as_str() {
+            "" => docs.push_str("*Deprecated*"),
+            message => docs.push_str(&format!("*Deprecated: {}*", message)),
+        }
+    }
+    docs
+}
+
+/// The Kotlin type of a property's values, as written in the sources
+pub fn type_label(property: &Property) -> String {
+    let mut label = match (property.is_list, property.is_map) {
+        (true, _) => format!("List<{}>", property.type_name),
+        (_, true) => format!("Map<String, {}>", property.type_name),
+        _ => property.type_name.clone(),
+    };
+    if property.is_nullable {
+        label.push('?');
+    }
+    label
+}
@@ -0,0 +1,444 @@
+//! Language server for Amper configuration files
+//!
+//! Answers from the parsed Kotlin model directly rather than through the
+//! generated schema: completion of keys, values, `@platform` qualifiers,
+//! local module paths and `$libs` catalog references, hover with the
+//! `@SchemaDoc` text, go-to-definition of local dependencies and templates,
+//! and diagnostics from validation and lint with their fixes as code actions.
+//!
+//! [`Server`] turns each incoming JSON-RPC message into the messages to send
+//! back, so it can be driven in-process; [`run`] connects it to a byte stream.
+
+mod completion;
+mod document;
+mod hover;
+mod transport;
+
+use crate::effective::Platforms;
+use crate::error::{Error, Result};
+use crate::mapping::TypeMapping;
+use crate::migrate::{self, split_modifiers};
+use crate::report::{Finding, SYNTAX_ERROR, Severity};
+use crate::schema::SchemaOptions;
+use crate::shape::Shape;
+use crate::types::{ClassDef, ParsingContext, Property};
+use crate::validate::SchemaStore;
+use crate::yaml;
+use crate::{lint, root_type, validate, workspace};
+use document::{Cursor, Segment};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path, PathBuf};
+
+pub use transport::{read_message, write_message};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_REQUEST: i64 = -32600;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// Where the server is in the LSP lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Uninitialized,
+    Running,
+    ShutDown,
+    /// `exit` was received; `clean` if it came after `shutdown`
+    Exited { clean: bool },
+}
+
+/// An error response to a request
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+fn invalid_params(message: &str) -> ResponseError {
+    ResponseError { code: INVALID_PARAMS, message: message.to_string() }
+}
+
+/// An open document
+struct Document {
+    text: String,
+    version: Option<i64>,
+}
+
+pub struct Server {
+    context: ParsingContext,
+    mapping: TypeMapping,
+    platforms: Option<Platforms>,
+    store: SchemaStore,
+    /// Root schema in `store` of each kind of file
+    roots: HashMap<&'static str, String>,
+    documents: HashMap<String, Document>,
+    state: State,
+}
+
+impl Server {
+    pub fn new(mut context: ParsingContext, options: SchemaOptions) -> Result<Self> {
+        // Template and project files are answered like the generated schemas describe them
+        crate::parser::add_builtin_roots(&mut context);
+        let (store, roots) = crate::generated_schemas(&context, &options)?;
+        // Without a `Platform` enum there is nothing to lint against
+        let platforms = Platforms::from_context(&context).ok();
+        Ok(Server {
+            context,
+            mapping: options.type_mapping,
+            platforms,
+            store,
+            roots,
+            documents: HashMap::new(),
+            state: State::Uninitialized,
+        })
+    }
+
+    /// Whether `exit` was received, and if it followed `shutdown`
+    pub fn exited(&self) -> Option<bool> {
+        match self.state {
+            State::Exited { clean } => Some(clean),
+            _ => None,
+        }
+    }
+
+    /// Handle a message from the client, returning the messages to send back
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let Some(method) = message["method"].as_str() else {
+            // Responses to requests the server never sends
+            return Vec::new();
+        };
+        let params = &message["params"];
+        let Some(id) = message.get("id") else {
+            return self.notification(method, params);
+        };
+
+        let result = match (self.state, method) {
+            (State::Uninitialized, "initialize") => {
+                self.state = State::Running;
+                Ok(self.capabilities())
+            }
+            (State::Uninitialized, _) => Err(ResponseError {
+                code: SERVER_NOT_INITIALIZED,
+                message: "The server is not initialized".to_string(),
+            }),
+            (State::Running, "shutdown") => {
+                self.state = State::ShutDown;
+                Ok(Value::Null)
+            }
+            (State::Running, _) => self.request(method, params),
+            _ => Err(ResponseError { code: INVALID_REQUEST, message: "The server is shut down".to_string() }),
+        };
+        let response = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": error.code, "message": error.message },
+            }),
+        };
+        vec![response]
+    }
+
+    fn capabilities(&self) -> Value {
+        json!({
+            "capabilities": {
+                "positionEncoding": "utf-16",
+                "textDocumentSync": { "openClose": true, "change": 1 },
+                "completionProvider": { "triggerCharacters": ["@", "$", ".", "/"] },
+                "hoverProvider": true,
+                "definitionProvider": true,
+                "codeActionProvider": { "codeActionKinds": ["quickfix", "source.fixAll"] },
+            },
+            "serverInfo": { "name": "amper-lsp", "version": env!("CARGO_PKG_VERSION") },
+        })
+    }
+
+    fn request(&self, method: &str, params: &Value) -> Result<Value, ResponseError> {
+        match method {
+            "textDocument/completion" => {
+                let (uri, text, cursor) = self.cursor(params)?;
+                Ok(cursor.map_or(Value::Null, |cursor| completion::complete(self, uri, text, &cursor)))
+            }
+            "textDocument/hover" => {
+                let (uri, text, cursor) = self.cursor(params)?;
+                Ok(cursor.and_then(|cursor| hover::hover(self, uri, text, &cursor)).unwrap_or(Value::Null))
+            }
+            "textDocument/definition" => {
+                let (uri, _, cursor) = self.cursor(params)?;
+                Ok(cursor.and_then(|cursor| self.definition(uri, &cursor)).unwrap_or(Value::Null))
+            }
+            "textDocument/codeAction" => self.code_actions(params),
+            _ => Err(ResponseError { code: METHOD_NOT_FOUND, message: format!("Unknown method `{}`", method) }),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        if method == "exit" {
+            self.state = State::Exited { clean: self.state == State::ShutDown };
+            return Vec::new();
+        }
+        if self.state != State::Running {
+            return Vec::new();
+        }
+        let Some(uri) = params["textDocument"]["uri"].as_str() else { return Vec::new() };
+        let version = params["textDocument"]["version"].as_i64();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default().to_string();
+                self.documents.insert(uri.to_string(), Document { text, version });
+            }
+            "textDocument/didChange" => {
+                // Full synchronization: the last change holds the whole text
+                let Some(text) = params["contentChanges"].as_array().and_then(|changes| changes.last()) else {
+                    return Vec::new();
+                };
+                let text = text["text"].as_str().unwrap_or_default().to_string();
+                self.documents.insert(uri.to_string(), Document { text, version });
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, None, Vec::new())];
+            }
+            _ => return Vec::new(),
+        }
+        let document = &self.documents[uri];
+        vec![publish_diagnostics(uri, document.version, self.diagnostics(uri, &document.text))]
+    }
+
+    /// The URI and text of the document of a request, and the token at its position
+    fn cursor<'p>(&self, params: &'p Value) -> Result<(&'p str, &str, Option<Cursor>), ResponseError> {
+        let uri = params["textDocument"]["uri"].as_str().ok_or_else(|| invalid_params("Missing textDocument"))?;
+        let document = self.documents.get(uri).ok_or_else(|| invalid_params("The document is not open"))?;
+        let position =
+            document::from_lsp(&document.text, &params["position"]).ok_or_else(|| invalid_params("Missing position"))?;
+        Ok((uri, &document.text, document::cursor_at(&document.text, position)))
+    }
+
+    /// Validation and lint results for a document, as LSP diagnostics
+    fn diagnostics(&self, uri: &str, text: &str) -> Vec<Value> {
+        let path = document::uri_to_path(uri).unwrap_or_default();
+        let root = root_type(&path);
+        let kind = workspace::config_kind(&path).unwrap_or("module");
+
+        let mut findings = Vec::new();
+        match yaml::parse(text) {
+            Ok(node) => {
+                if let Some(schema) = self.roots.get(kind) {
+                    for diagnostic in validate::validate(&self.store, schema, &node) {
+                        findings.push(diagnostic.into_finding(uri, text));
+                    }
+                }
+                if let Some(platforms) = &self.platforms {
+                    for diagnostic in lint::lint(text, root, path.parent(), &self.context, &self.mapping, platforms, &|_| true) {
+                        findings.push(Finding {
+                            file: uri.to_string(),
+                            rule: diagnostic.rule,
+                            message: diagnostic.message,
+                            start: diagnostic.position,
+                            end: None,
+                            path: None,
+                            fixes: Vec::new(),
+                        });
+                    }
+                }
+            }
+            Err(error) => findings.push(Finding {
+                file: uri.to_string(),
+                rule: &SYNTAX_ERROR,
+                message: error.message,
+                start: error.position,
+                end: None,
+                path: None,
+                fixes: Vec::new(),
+            }),
+        }
+        findings.sort_by_key(|finding| finding.start);
+        findings.iter().map(|finding| lsp_diagnostic(text, finding)).collect()
+    }
+
+    /// Fixes of the diagnostics in the request, and the migration of the whole document
+    fn code_actions(&self, params: &Value) -> Result<Value, ResponseError> {
+        let uri = params["textDocument"]["uri"].as_str().ok_or_else(|| invalid_params("Missing textDocument"))?;
+        let document = self.documents.get(uri).ok_or_else(|| invalid_params("The document is not open"))?;
+
+        let mut actions = Vec::new();
+        let diagnostics = params["context"]["diagnostics"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for diagnostic in diagnostics {
+            for fix in diagnostic["data"]["fixes"].as_array().map(Vec::as_slice).unwrap_or_default() {
+                actions.push(json!({
+                    "title": fix["title"],
+                    "kind": "quickfix",
+                    "diagnostics": [diagnostic],
+                    "isPreferred": true,
+                    "edit": { "changes": { uri: [{ "range": fix["range"], "newText": fix["newText"] }] } },
+                }));
+            }
+        }
+
+        let path = document::uri_to_path(uri).unwrap_or_default();
+        if let Ok(migration) = migrate::migrate(&document.text, root_type(&path), &self.context, &self.mapping)
+            && !migration.changes.is_empty()
+        {
+            let count = migration.changes.len();
+            actions.push(json!({
+                "title": format!("Migrate {} deprecated {}", count, if count == 1 { "name" } else { "names" }),
+                "kind": "source.fixAll",
+                "edit": {
+                    "changes": { uri: [{ "range": document::full_range(&document.text), "newText": migration.text }] },
+                },
+            }));
+        }
+        Ok(Value::Array(actions))
+    }
+
+    /// The module or template a local path under the cursor points to
+    fn definition(&self, uri: &str, cursor: &Cursor) -> Option<Value> {
+        let reference = cursor.text();
+        if !workspace::is_local_path(reference) {
+            return None;
+        }
+        let dir = document::uri_to_path(uri)?.parent()?.to_path_buf();
+        // `app/../lib`, resolved without following links as Amper does
+        let mut target = PathBuf::new();
+        for component in dir.join(reference).components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    target.pop();
+                }
+                component => target.push(component),
+            }
+        }
+        let file = if target.is_dir() { target.join("module.yaml") } else { target };
+        if !file.is_file() {
+            return None;
+        }
+        let start = json!({ "line": 0, "character": 0 });
+        Some(json!({ "uri": document::path_to_uri(&file), "range": { "start": start, "end": start } }))
+    }
+
+    /// The shape of the node at `path` in a file whose root is the class
+    /// `root`, and the property declaring it
+    fn resolve(&self, root: &str, path: &[Segment]) -> Option<(Shape<'_>, Option<Property>)> {
+        let mut shape = Shape::class(&self.context, root);
+        let mut property = None;
+        for segment in path {
+            match segment {
+                Segment::Key(_) if shape.map && !shape.list => shape = shape.element(),
+                Segment::Key(key) => {
+                    let class = shape.class.filter(|_| !shape.list && !shape.map)?;
+                    let (_, name, _) = split_modifiers(key);
+                    let found = self.properties(class).into_iter().find(|p| p.name == name)?;
+                    shape = Shape::of(&found, &self.context, &self.mapping);
+                    property = Some(found);
+                }
+                Segment::Item if shape.list => shape = shape.element(),
+                Segment::Item => return None,
+            }
+        }
+        Some((shape, property))
+    }
+
+    /// Properties of a class and, for a sealed class, of all its variants
+    fn properties(&self, class: &ClassDef) -> Vec<Property> {
+        let mut properties = self.context.all_properties(class);
+        let mut pending = class.subclasses.clone();
+        while let Some(name) = pending.pop() {
+            let Some(subclass) = self.context.classes.get(&name) else { continue };
+            pending.extend(subclass.subclasses.iter().cloned());
+            for property in self.context.all_properties(subclass) {
+                if !properties.iter().any(|p| p.name == property.name) {
+                    properties.push(property);
+                }
+            }
+        }
+        properties
+    }
+}
+
+/// Serve `input` until the client exits or closes it.
+/// Returns whether the exit was clean, after `shutdown`.
+///
+/// Malformed messages are answered with a parse error and skipped.
+pub fn run(server: &mut Server, input: &mut impl BufRead, output: &mut impl Write) -> Result<bool> {
+    loop {
+        let message = match read_message(input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(false),
+            Err(Error::Protocol(message)) => {
+                let error = json!({ "code": PARSE_ERROR, "message": message });
+                write_message(output, &json!({ "jsonrpc": "2.0", "id": null, "error": error }))?;
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        for reply in server.handle(&message) {
+            write_message(output, &reply)?;
+        }
+        if let Some(clean) = server.exited() {
+            return Ok(clean);
+        }
+    }
+}
+
+fn publish_diagnostics(uri: &str, version: Option<i64>, diagnostics: Vec<Value>) -> Value {
+    let mut params = json!({ "uri": uri, "diagnostics": diagnostics });
+    if let Some(version) = version {
+        params["version"] = json!(version);
+    }
+    json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": params })
+}
+
+fn lsp_diagnostic(text: &str, finding: &Finding) -> Value {
+    let end = finding.end.unwrap_or_else(|| document::token_end(text, finding.start));
+    let mut diagnostic = json!({
+        "range": document::to_lsp_range(text, finding.start, end),
+        "severity": match finding.rule.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        },
+        "code": finding.rule.id,
+        "codeDescription": { "href": finding.rule.docs_url() },
+        "source": "amper",
+        "message": finding.message,
+    });
+    if !finding.fixes.is_empty() {
+        let fixes: Vec<Value> = finding
+            .fixes
+            .iter()
+            .map(|fix| {
+                json!({
+                    "title": fix.description,
+                    "range": document::to_lsp_range(text, fix.start, fix.end),
+                    "newText": fix.replacement,
+                })
+            })
+            .collect();
+        diagnostic["data"] = json!({ "fixes": fixes });
+    }
+    diagnostic
+}
+
+/// The directory of the project holding `dir`: the closest one with a
+/// project.yaml, or `dir` itself for a standalone module
+fn project_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join("project.yaml").is_file())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// `to` relative to the directory `from`, as written in Amper files: `./lib`, `../lib`
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(to[common..].iter().map(|part| part.as_os_str().to_string_lossy().into_owned()));
+    match parts.first().map(String::as_str) {
+        Some("..") => parts.join("/"),
+        _ => format!("./{}", parts.join("/")),
+    }
+}
@@ -0,0 +1,59 @@
+//! JSON-RPC messages framed by `Content-Length` headers, as LSP sends them over stdio
+
+use crate::error::{Error, Result};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+
+/// Read the next message, or `None` at the end of the input.
+///
+/// A message with a bad header or a body that isn't JSON is a
+/// [`Error::Protocol`]; its body is skipped when its length is known, and
+/// otherwise the next `Content-Length` header is searched for, so that reading
+/// can go on with the following message.
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    let mut seen_header = false;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if !seen_header {
+                continue;
+            }
+            break;
+        }
+        seen_header = true;
+        // The body of a message with an unknown length runs into the next header
+        let header = line
+            .to_ascii_lowercase()
+            .find("content-length:")
+            .map_or(line, |start| &line[start..]);
+        // `Content-Type` is the only other header, and always JSON-RPC in UTF-8
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            let value = value.trim();
+            length = Some(value.parse::<usize>().map_err(|_| format!("Invalid Content-Length header: {}", value)));
+        }
+    }
+    let length = match length {
+        Some(Ok(length)) => length,
+        Some(Err(message)) => return Err(Error::Protocol(message)),
+        None => return Err(Error::Protocol("Message without a Content-Length header".to_string())),
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    let message = serde_json::from_slice(&body).map_err(|error| Error::Protocol(format!("Message is not JSON: {}", error)))?;
+    Ok(Some(message))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
@@ -3,24 +3,30 @@
 //! This tool parses Kotlin source files from the Amper project to extract
 //! schema definitions and generate a JSON Schema file for VS Code IntelliSense.
 
-mod parser;
-mod schema;
-mod types;
-
+use amper_schema_extractor::{
+    Output, check, describe_types, diff, effective, format, generated_schemas, lint, load_context, load_definitions,
+    load_schemas, mapping, migrate, report, schema, validate, workspace,
+};
 use anyhow::{Context, Result};
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 #[derive(Parser, Debug)]
 #[command(
     name = "extract-schema",
     about = "Extracts JSON Schema from Amper Kotlin sources",
-    version
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the Amper source directory (vendor/amper/sources)
-    #[arg(short, long)]
-    source: PathBuf,
+    #[arg(short, long, required = true)]
+    source: Option<PathBuf>,
 
     /// Output file path for the generated JSON Schema
     #[arg(short, long, default_value = "module-schema.json")]
@@ -30,62 +36,514 @@ struct Args {
     #[arg(short, long)]
     verbose: bool,
 
-    /// Generate schema for: module, template, project
+    /// Generate schema for: module, template, project, or any parsed class name
     #[arg(long, default_value = "module")]
     schema_type: String,
+
+    /// Write every root schema into this directory, sharing one definitions file
+    #[arg(long, conflicts_with_all = ["output", "schema_type"])]
+    out_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    schema: SchemaArgs,
 }
 
-fn main() -> Result<()> {
+/// Options shaping the generated schema
+#[derive(clap::Args, Debug)]
+struct SchemaArgs {
+    /// Editor flavor of the schema annotations
+    #[arg(long, value_enum, default_value_t = schema::SchemaFlavor::VsCode)]
+    flavor: schema::SchemaFlavor,
+
+    /// JSON Schema dialect of the output
+    #[arg(long, value_enum, default_value_t = schema::SchemaDialect::Draft2020_12)]
+    dialect: schema::SchemaDialect,
+
+    /// Drop @HiddenFromCompletion properties instead of marking them doNotSuggest
+    #[arg(long)]
+    drop_hidden: bool,
+
+    /// TOML or JSON file extending the built-in Kotlin-to-JSON-Schema type mapping
+    #[arg(long, value_name = "FILE")]
+    type_mapping: Option<PathBuf>,
+}
+
+impl SchemaArgs {
+    fn options(&self) -> Result<schema::SchemaOptions> {
+        let type_mapping = match &self.type_mapping {
+            Some(path) => mapping::TypeMapping::load(path)?,
+            None => mapping::TypeMapping::default(),
+        };
+
+        Ok(schema::SchemaOptions {
+            flavor: self.flavor,
+            dialect: self.dialect,
+            drop_hidden: self.drop_hidden,
+            type_mapping,
+        })
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List every parsed class and enum
+    ListTypes {
+        /// Path to the Amper source directory (vendor/amper/sources)
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Compare the configuration language of two source trees or generated schemas
+    Diff {
+        /// Old version: Amper sources, a schema file, or an `--out-dir` directory
+        #[arg(long)]
+        old: PathBuf,
+
+        /// New version: Amper sources, a schema file, or an `--out-dir` directory
+        #[arg(long)]
+        new: PathBuf,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = diff::ReportFormat::Markdown)]
+        format: diff::ReportFormat,
+
+        /// Write the report to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Check that a committed schema matches the one generated from the sources
+    Check {
+        /// Path to the Amper source directory (vendor/amper/sources)
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// The committed schema file to compare against
+        #[arg(long, required_unless_present = "out_dir")]
+        expected: Option<PathBuf>,
+
+        /// A committed `--out-dir` directory: compares every root schema and the definitions file
+        #[arg(long, conflicts_with_all = ["expected", "schema_type"])]
+        out_dir: Option<PathBuf>,
+
+        /// Schema root: module, template, project, or any parsed class name
+        #[arg(long, default_value = "module")]
+        schema_type: String,
+
+        #[command(flatten)]
+        schema: SchemaArgs,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Validate module.yaml, template and project.yaml files against the schema
+    Validate {
+        /// YAML files, or directories searched for Amper configuration files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Path to the Amper source directory to generate the schema from
+        #[arg(short, long, required_unless_present = "schema", conflicts_with = "schema")]
+        source: Option<PathBuf>,
+
+        /// Schema file applied to every file, or a directory written by `--out-dir`
+        #[arg(long)]
+        schema: Option<PathBuf>,
+
+        #[command(flatten)]
+        options: SchemaArgs,
+
+        /// Output format of the results
+        #[arg(long, value_enum, default_value = "text")]
+        format: report::ReportFormat,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Rewrite module.yaml, template and project.yaml files in a canonical layout
+    Fmt {
+        /// YAML files, or directories searched for Amper configuration files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Path to the Amper source directory, for the order of the keys
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// Only list the files that aren't formatted, exiting with status 1 if there are any
+        #[arg(long)]
+        check: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Report mistakes the schema can't catch, like settings for platforms a module doesn't target
+    Lint {
+        /// YAML files, or directories searched for Amper configuration files
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+
+        /// Path to the Amper source directory
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// Rules to skip, by ID (repeatable or comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        disable: Vec<String>,
+
+        /// Exit with status 1 on warnings too
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// Output format of the results
+        #[arg(long, value_enum, default_value = "text")]
+        format: report::ReportFormat,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Rename deprecated keys and replace outdated values with their successors
+    Migrate {
+        /// YAML files, or directories searched for Amper configuration files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Path to the Amper source directory, for the deprecations
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// Print the changes as a diff instead of writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Show the settings and dependencies a module gets on one platform
+    Effective {
+        /// The module.yaml file
+        module: PathBuf,
+
+        /// Path to the Amper source directory, for the platform hierarchy
+        #[arg(short, long)]
+        source: PathBuf,
+
+        /// Platform to resolve for, e.g. jvm or iosArm64
+        #[arg(short, long)]
+        platform: String,
+
+        /// Include the `test-settings` and `test-dependencies` sections
+        #[arg(long)]
+        test: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = effective::OutputFormat::Yaml)]
+        format: effective::OutputFormat,
+
+        /// Write the result to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Check an Amper project for broken references between its files
+    CheckProject {
+        /// Project root directory
+        #[arg(default_value = ".")]
+        root: PathBuf,
+
+        /// Output format of the problems
+        #[arg(long, value_enum, default_value = "text")]
+        format: report::ReportFormat,
+
+        /// Enable verbose output
+        #[arg(short, long)]
+        verbose: bool,
+    },
+}
+
+fn main() -> Result<ExitCode> {
     let args = Args::parse();
 
+    match &args.command {
+        Some(Command::ListTypes { source, verbose }) => list_types(source, *verbose),
+        Some(Command::Diff { old, new, format, output, verbose }) => {
+            diff_schemas(old, new, *format, output.as_deref(), *verbose)
+        }
+        Some(Command::Check { source, expected, out_dir, schema_type, schema, verbose }) => {
+            return check_schema(source, expected.as_deref(), out_dir.as_deref(), schema_type, schema, *verbose);
+        }
+        Some(Command::Validate { paths, source, schema, options, format, verbose }) => {
+            return validate_files(paths, source.as_deref(), schema.as_deref(), options, *format, *verbose);
+        }
+        Some(Command::Fmt { paths, source, check, verbose }) => {
+            return format_files(paths, source, *check, *verbose);
+        }
+        Some(Command::Lint { paths, source, disable, deny_warnings, format, verbose }) => {
+            return lint_files(paths, source, disable, *deny_warnings, *format, *verbose);
+        }
+        Some(Command::Migrate { paths, source, dry_run, verbose }) => {
+            return migrate_files(paths, source, *dry_run, *verbose);
+        }
+        Some(Command::Effective { module, source, platform, test, format, output, verbose }) => {
+            show_effective(module, source, platform, *test, *format, output.as_deref(), *verbose)
+        }
+        Some(Command::CheckProject { root, format, verbose }) => return check_project(root, *format, *verbose),
+        None => extract(&args),
+    }?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Generate the schema selected by the command line options
+fn extract(args: &Args) -> Result<()> {
+    let source = args.source.as_deref().context("--source is required")?;
+
     if args.verbose {
         eprintln!("Amper Schema Extractor v{}", env!("CARGO_PKG_VERSION"));
-        eprintln!("Source directory: {}", args.source.display());
-        eprintln!("Output file: {}", args.output.display());
+        eprintln!("Source directory: {}", source.display());
+        match &args.out_dir {
+            Some(out_dir) => eprintln!("Output directory: {}", out_dir.display()),
+            None => eprintln!("Output file: {}", args.output.display()),
+        }
     }
 
-    // Validate source directory
-    let frontend_api_path = args.source.join("frontend-api/src/org/jetbrains/amper/frontend/schema");
-    if !frontend_api_path.exists() {
-        anyhow::bail!(
-            "Invalid source directory. Expected to find: {}",
-            frontend_api_path.display()
-        );
+    let context = load_context(source, args.verbose)?;
+    let output = match &args.out_dir {
+        Some(out_dir) => Output::Bundle(out_dir),
+        None => Output::File { path: &args.output, schema_type: &args.schema_type },
+    };
+    amper_schema_extractor::extract(&context, &output, &args.schema.options()?, args.verbose)?;
+
+    match &args.out_dir {
+        Some(out_dir) => println!("Schemas extracted successfully: {}", out_dir.display()),
+        None => println!("Schema extracted successfully: {}", args.output.display()),
     }
+    Ok(())
+}
+
+/// Print every parsed class and enum with its property or entry count
+fn list_types(source: &Path, verbose: bool) -> Result<()> {
+    print!("{}", describe_types(&load_context(source, verbose)?));
+    Ok(())
+}
 
-    // Parse Kotlin source files
-    let context = parser::parse_source_directory(&args.source, args.verbose)
-        .context("Failed to parse Kotlin source files")?;
+/// Compare the committed schema, or with `out_dir` every file of a bundle,
+/// with the one generated from the sources
+fn check_schema(
+    source: &Path,
+    expected: Option<&Path>,
+    out_dir: Option<&Path>,
+    schema_type: &str,
+    schema_args: &SchemaArgs,
+    verbose: bool,
+) -> Result<ExitCode> {
+    let target = match (out_dir, expected) {
+        (Some(out_dir), _) => check::Target::Bundle(out_dir),
+        (None, Some(expected)) => check::Target::Schema { schema_type, expected },
+        (None, None) => anyhow::bail!("--expected or --out-dir is required"),
+    };
+    let checks = check::run(&load_context(source, verbose)?, &target, &schema_args.options()?)?;
 
-    if args.verbose {
-        eprintln!("Parsed {} types, {} enums", context.classes.len(), context.enums.len());
+    for check in &checks {
+        if check.is_up_to_date() {
+            println!("{}", check);
+        } else {
+            eprintln!("{}", check);
+        }
+    }
+    if checks.iter().all(check::FileCheck::is_up_to_date) {
+        return Ok(ExitCode::SUCCESS);
     }
 
-    // Generate JSON Schema
-    let root_type = match args.schema_type.as_str() {
-        "module" => "Module",
-        "template" => "Template",
-        "project" => "Project",
-        other => {
-            eprintln!("Warning: Unknown schema type '{}', defaulting to 'Module'", other);
-            "Module"
-        }
+    eprintln!();
+    eprintln!("Regenerate it with: extract-schema --source {} {}", source.display(), target.regenerate_args());
+    Ok(ExitCode::FAILURE)
+}
+
+/// Validate configuration files, printing every violation as `file:line:column`
+fn validate_files(
+    paths: &[PathBuf],
+    source: Option<&Path>,
+    schema: Option<&Path>,
+    options: &SchemaArgs,
+    format: report::ReportFormat,
+    verbose: bool,
+) -> Result<ExitCode> {
+    let (store, roots) = match (source, schema) {
+        (Some(source), _) => generated_schemas(&load_context(source, verbose)?, &options.options()?)?,
+        (None, Some(schema)) => load_schemas(schema)?,
+        (None, None) => anyhow::bail!("Either --source or --schema is required"),
     };
+    let files = workspace::collect_config_files(paths);
+    let findings = validate::validate_files(&files, &store, &roots, verbose)?;
 
-    let json_schema = schema::generate_json_schema(&context, root_type)
-        .context("Failed to generate JSON Schema")?;
+    print!("{}", report::render(format, &findings, files.len(), validate::RULES, validate::text_line));
+    let summary = validate::Summary::new(&findings, files.len());
+    eprintln!("{}", summary);
+    Ok(exit_code(summary.is_valid()))
+}
 
-    // Write output
-    let output_json = serde_json::to_string_pretty(&json_schema)
-        .context("Failed to serialize JSON Schema")?;
-    
-    std::fs::write(&args.output, &output_json)
-        .context("Failed to write output file")?;
+/// Format configuration files in place, or only report the unformatted ones
+fn format_files(paths: &[PathBuf], source: &Path, check: bool, verbose: bool) -> Result<ExitCode> {
+    let context = load_context(source, verbose)?;
+    let files = workspace::collect_config_files(paths);
+    let outcomes = format::format_files(&files, &context, &mapping::TypeMapping::default(), check)?;
 
-    if args.verbose {
-        eprintln!("Successfully wrote schema to {}", args.output.display());
+    for (file, outcome) in files.iter().zip(&outcomes) {
+        match outcome {
+            format::Outcome::Unchanged if verbose => eprintln!("Already formatted: {}", file.display()),
+            format::Outcome::Unchanged => {}
+            format::Outcome::Reformatted if check => println!("Would reformat: {}", file.display()),
+            format::Outcome::Reformatted => println!("Formatted: {}", file.display()),
+            format::Outcome::Failed(error) => eprintln!("{}: {}", file.display(), error),
+        }
     }
 
-    println!("Schema extracted successfully: {}", args.output.display());
+    let summary = format::Summary::new(&outcomes, check);
+    eprintln!("{}", summary);
+    Ok(exit_code(summary.is_clean()))
+}
+
+/// Run the lint rules not in `disabled` over configuration files
+fn lint_files(
+    paths: &[PathBuf],
+    source: &Path,
+    disabled: &[String],
+    deny_warnings: bool,
+    format: report::ReportFormat,
+    verbose: bool,
+) -> Result<ExitCode> {
+    let rules = lint::enabled_rules(disabled)?;
+    let context = load_context(source, verbose)?;
+    let platforms = effective::Platforms::from_context(&context)?;
+    let files = workspace::collect_config_files(paths);
+    let findings = lint::lint_files(&files, &context, &mapping::TypeMapping::default(), &platforms, &rules)?;
+
+    print!("{}", report::render(format, &findings, files.len(), &rules, lint::text_line));
+    if format == report::ReportFormat::Text {
+        for rule in report::fired_rules(&findings) {
+            eprintln!("{}: {} ({})", rule.id, rule.summary, rule.docs_url());
+        }
+    }
+
+    let summary = lint::Summary::new(&findings, files.len());
+    eprintln!("{}", summary);
+    Ok(exit_code(!summary.fails(deny_warnings)))
+}
+
+/// Migrate configuration files in place, or print the migration as a diff
+fn migrate_files(paths: &[PathBuf], source: &Path, dry_run: bool, verbose: bool) -> Result<ExitCode> {
+    let context = load_context(source, verbose)?;
+    let files = workspace::collect_config_files(paths);
+    let migrations = migrate::migrate_files(&files, &context, &mapping::TypeMapping::default(), dry_run)?;
+
+    for (file, file_migration) in files.iter().zip(&migrations) {
+        let migration = match &file_migration.result {
+            Ok(migration) => migration,
+            Err(error) => {
+                eprintln!("{}: {}", file.display(), error);
+                continue;
+            }
+        };
+        for change in &migration.manual {
+            eprintln!("{}:{}:{}: {}", file.display(), change.position.line, change.position.column, change.message);
+        }
+        if migration.changes.is_empty() {
+            if verbose {
+                eprintln!("Nothing to migrate: {}", file.display());
+            }
+        } else if dry_run {
+            print!("{}", migrate::unified_diff(&file.display().to_string(), &file_migration.original, &migration.text));
+        } else {
+            for change in &migration.changes {
+                println!("{}:{}:{}: {}", file.display(), change.position.line, change.position.column, change.message);
+            }
+        }
+    }
+
+    let summary = migrate::Summary::new(&migrations, dry_run);
+    eprintln!("{}", summary);
+    Ok(exit_code(summary.is_clean()))
+}
+
+/// Print the effective configuration of a module for one platform
+fn show_effective(
+    module: &Path,
+    source: &Path,
+    platform: &str,
+    test: bool,
+    format: effective::OutputFormat,
+    output: Option<&Path>,
+    verbose: bool,
+) -> Result<()> {
+    let context = load_context(source, verbose)?;
+    let platforms = effective::Platforms::from_context(&context)?;
+    let resolved = effective::resolve(module, platform, test, &platforms)?;
+    if verbose {
+        eprintln!("Merged {}", resolved.files.join(", "));
+    }
+    write_report(&resolved.render(format), output)
+}
+
+/// Check the references between the files of the project at `root`
+fn check_project(root: &Path, format: report::ReportFormat, verbose: bool) -> Result<ExitCode> {
+    let report = workspace::check(root)?;
+    if verbose {
+        for module in &report.modules {
+            eprintln!("Found module {}", module.display());
+        }
+    }
+    print!("{}", report::render(format, &report.findings(), report.files, workspace::RULES, workspace::text_line));
+    eprintln!("{}", report.summary());
+    Ok(exit_code(report.problems.is_empty()))
+}
+
+/// Report the differences between two versions of the configuration language
+fn diff_schemas(
+    old: &Path,
+    new: &Path,
+    format: diff::ReportFormat,
+    output: Option<&Path>,
+    verbose: bool,
+) -> Result<()> {
+    let old_schema = load_definitions(old, verbose)?;
+    let new_schema = load_definitions(new, verbose)?;
+    let changes = diff::diff_schemas(&old_schema, &new_schema)
+        .context("Both schemas must contain `$defs` or `definitions`")?;
+    write_report(&diff::render(&changes, format), output)
+}
+
+/// Write a report to `output`, or print it if there is none
+fn write_report(report: &str, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => std::fs::write(path, report)
+            .with_context(|| format!("Failed to write output file: {}", path.display()))?,
+        None => print!("{}", report),
+    }
     Ok(())
 }
+
+fn exit_code(success: bool) -> ExitCode {
+    if success { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
@@ -0,0 +1,192 @@
+//! Mapping from Kotlin value types to JSON Schema primitives
+//!
+//! The built-in table lives in `type-mapping.toml`; a user table in the same
+//! format (TOML or JSON) can be layered on top of it.
+
+use crate::error::{Error, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Built-in mapping, also the reference for the config file format
+const DEFAULT_MAPPING: &str = include_str!("../type-mapping.toml");
+
+/// Kotlin type name to JSON Schema rules, plus wrapper unwrapping rules
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypeMapping {
+    /// Generic wrappers unwrapped to their type argument (`TraceableEnum<T>` -> `T`)
+    #[serde(default)]
+    pub wrappers: Vec<String>,
+    /// Name prefixes stripped from unmapped types (`TraceableString` -> `String`)
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    /// JSON Schema rule per Kotlin type name
+    #[serde(default)]
+    pub types: IndexMap<String, TypeRule>,
+    /// Constraints for string values of properties, keyed `Class.property`
+    #[serde(default)]
+    pub properties: IndexMap<String, StringConstraints>,
+}
+
+/// JSON Schema emitted for a mapped type
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypeRule {
+    /// JSON Schema `type`: string, integer, number or boolean
+    #[serde(rename = "type")]
+    pub json_type: String,
+    /// Optional `format`, e.g. `uri`
+    pub format: Option<String>,
+    /// Optional regular expression the value must match
+    pub pattern: Option<String>,
+}
+
+/// `format`/`pattern` constraints added to string properties of a class
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StringConstraints {
+    /// Optional `format`, e.g. `uri`
+    pub format: Option<String>,
+    /// Optional regular expression the value must match
+    pub pattern: Option<String>,
+}
+
+impl Default for TypeMapping {
+    fn default() -> Self {
+        toml::from_str(DEFAULT_MAPPING).expect("built-in type mapping is invalid")
+    }
+}
+
+impl TypeMapping {
+    /// Load the built-in mapping extended by the TOML or JSON file at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        let invalid = |message: String| Error::TypeMapping { path: path.to_path_buf(), message };
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let overrides: TypeMapping = if is_json {
+            serde_json::from_str(&content).map_err(|error| invalid(error.to_string()))?
+        } else {
+            toml::from_str(&content).map_err(|error| invalid(error.message().to_string()))?
+        };
+
+        let mut mapping = TypeMapping::default();
+        mapping.extend(overrides);
+        mapping.validate().map_err(invalid)?;
+        Ok(mapping)
+    }
+
+    /// Add the rules of `other`, replacing types mapped by both
+    fn extend(&mut self, other: TypeMapping) {
+        for wrapper in other.wrappers {
+            if !self.wrappers.contains(&wrapper) {
+                self.wrappers.push(wrapper);
+            }
+        }
+        for prefix in other.prefixes {
+            if !self.prefixes.contains(&prefix) {
+                self.prefixes.push(prefix);
+            }
+        }
+        self.types.extend(other.types);
+        self.properties.extend(other.properties);
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, rule) in &self.types {
+            if !matches!(rule.json_type.as_str(), "string" | "integer" | "number" | "boolean") {
+                return Err(format!(
+                    "type mapping for '{}' has unsupported type '{}': expected string, integer, number or boolean",
+                    name, rule.json_type
+                ));
+            }
+            validate_pattern(&rule.pattern, name)?;
+        }
+        for (name, constraints) in &self.properties {
+            if name.split_once('.').is_none_or(|(class, property)| class.is_empty() || property.is_empty()) {
+                return Err(format!(
+                    "property constraints for '{}' must be keyed as Class.property, e.g. Repository.url",
+                    name
+                ));
+            }
+            validate_pattern(&constraints.pattern, name)?;
+        }
+        Ok(())
+    }
+
+    /// The rule for a type name, if it is mapped directly
+    pub fn rule(&self, type_name: &str) -> Option<&TypeRule> {
+        self.types.get(type_name)
+    }
+
+    /// Add the constraints for property `property_name` of class `owner` to a
+    /// string schema. Constraints already set by the type mapping take precedence.
+    pub fn constrain(&self, owner: &str, property_name: &str, schema: &mut Value) {
+        let Some(constraints) = self.properties.get(&format!("{}.{}", owner, property_name)) else {
+            return;
+        };
+        let Some(obj) = schema.as_object_mut() else {
+            return;
+        };
+        if obj.get("type").and_then(Value::as_str) != Some("string") {
+            return;
+        }
+        if let Some(format) = &constraints.format {
+            obj.entry("format").or_insert_with(|| Value::String(format.clone()));
+        }
+        if let Some(pattern) = &constraints.pattern {
+            obj.entry("pattern").or_insert_with(|| Value::String(pattern.clone()));
+        }
+    }
+
+    /// Remove one layer of wrapping: a generic wrapper or a wrapper prefix.
+    /// Returns `None` when the type isn't wrapped.
+    pub fn unwrap<'a>(&self, type_name: &'a str) -> Option<&'a str> {
+        for wrapper in &self.wrappers {
+            if let Some(inner) = type_name
+                .strip_prefix(wrapper.as_str())
+                .and_then(|s| s.strip_prefix('<'))
+                .and_then(|s| s.strip_suffix('>'))
+            {
+                return Some(inner.trim());
+            }
+        }
+        self.prefixes.iter().find_map(|prefix| {
+            type_name
+                .strip_prefix(prefix.as_str())
+                .filter(|rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+        })
+    }
+
+    /// Unwrap a type name until no wrapper applies
+    pub fn unwrap_all<'a>(&self, mut type_name: &'a str) -> &'a str {
+        while let Some(inner) = self.unwrap(type_name) {
+            type_name = inner;
+        }
+        type_name
+    }
+}
+
+impl TypeRule {
+    /// The JSON Schema for values of this type
+    pub fn to_schema(&self) -> Value {
+        let mut schema = Map::new();
+        schema.insert("type".to_string(), Value::String(self.json_type.clone()));
+        if let Some(format) = &self.format {
+            schema.insert("format".to_string(), Value::String(format.clone()));
+        }
+        if let Some(pattern) = &self.pattern {
+            schema.insert("pattern".to_string(), Value::String(pattern.clone()));
+        }
+        Value::Object(schema)
+    }
+}
+
+fn validate_pattern(pattern: &Option<String>, name: &str) -> Result<(), String> {
+    if let Some(pattern) = pattern {
+        regex::Regex::new(pattern)
+            .map_err(|error| format!("type mapping for '{}' has an invalid pattern: {}", name, error))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,349 @@
+//! Migration of deprecated keys and outdated enum values
+//!
+//! Replacements come from `@Deprecated("...", ReplaceWith("..."))` in the
+//! Amper sources: on a property it names the property to use instead, on an
+//! enum entry the entry whose value to write instead. Each key or value is
+//! replaced where it is written and nothing else is touched, so comments and
+//! layout survive. Deprecated names without a replacement are only reported.
+
+use crate::cst;
+use crate::error::{Error, Result};
+use crate::mapping::TypeMapping;
+use crate::parser::with_builtin_roots;
+use crate::report::plural;
+use crate::root_type;
+use crate::shape::Shape;
+use crate::types::{ClassDef, EnumDef, ParsingContext, Property};
+use crate::yaml::{self, Node, NodeValue, Position};
+use std::fmt;
+use std::path::PathBuf;
+
+/// A key or value that was, or has to be, migrated
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub position: Position,
+    pub message: String,
+}
+
+/// The migrated text of a file
+#[derive(Debug)]
+pub struct Migration {
+    pub text: String,
+    /// Keys and values rewritten in `text`
+    pub changes: Vec<Change>,
+    /// Deprecated names left as they are, to be migrated by hand
+    pub manual: Vec<Change>,
+}
+
+/// The migration of one file
+#[derive(Debug)]
+pub struct FileMigration {
+    /// The file as it was before the migration
+    pub original: String,
+    /// The migration, or why the file was left as it is
+    pub result: Result<Migration>,
+}
+
+/// Totals of migrating files, or with `dry_run` of only previewing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub files: usize,
+    pub changed_files: usize,
+    pub changes: usize,
+    /// Deprecated names to migrate by hand
+    pub manual: usize,
+    pub failed: usize,
+    pub dry_run: bool,
+}
+
+impl Summary {
+    pub fn new(migrations: &[FileMigration], dry_run: bool) -> Self {
+        let migrated: Vec<&Migration> = migrations.iter().filter_map(|m| m.result.as_ref().ok()).collect();
+        Summary {
+            files: migrations.len(),
+            changed_files: migrated.iter().filter(|m| !m.changes.is_empty()).count(),
+            changes: migrated.iter().map(|m| m.changes.len()).sum(),
+            manual: migrated.iter().map(|m| m.manual.len()).sum(),
+            failed: migrations.len() - migrated.len(),
+            dry_run,
+        }
+    }
+
+    /// Every file migrated, with nothing left to do by hand
+    pub fn is_clean(&self) -> bool {
+        self.failed == 0 && self.manual == 0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "to make" } else { "made" };
+        write!(
+            f,
+            "{} {} {} in {} of {} {}",
+            self.changes,
+            plural(self.changes, "change", "changes"),
+            verb,
+            self.changed_files,
+            self.files,
+            plural(self.files, "file", "files")
+        )?;
+        if self.manual > 0 {
+            write!(f, "\n{} {} to migrate by hand", self.manual, plural(self.manual, "name", "names"))?;
+        }
+        if self.failed > 0 {
+            write!(f, "\n{} {} could not be migrated", self.failed, plural(self.failed, "file", "files"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Migrate configuration files, each against the root class of its kind
+/// (see [`with_builtin_roots`]).
+/// Files with changes are written back unless `dry_run` is set.
+pub fn migrate_files(
+    files: &[PathBuf],
+    context: &ParsingContext,
+    mapping: &TypeMapping,
+    dry_run: bool,
+) -> Result<Vec<FileMigration>> {
+    let context = &*with_builtin_roots(context);
+    let mut migrations = Vec::new();
+    for file in files {
+        let original = std::fs::read_to_string(file).map_err(Error::io(file))?;
+        let result = migrate(&original, root_type(file), context, mapping);
+        if let Ok(migration) = &result
+            && !dry_run
+            && !migration.changes.is_empty()
+        {
+            std::fs::write(file, &migration.text).map_err(Error::io(file))?;
+        }
+        migrations.push(FileMigration { original, result });
+    }
+    Ok(migrations)
+}
+
+/// Migrate `source`, a configuration file whose root is the class `root`
+pub fn migrate(source: &str, root: &str, context: &ParsingContext, mapping: &TypeMapping) -> Result<Migration> {
+    let document = yaml::parse(source).map_err(|error| Error::Syntax { file: None, error })?;
+
+    let mut migrator = Migrator { source, context, mapping, edits: Vec::new(), manual: Vec::new() };
+    let shape = Shape::class(context, root);
+    migrator.walk(&document, shape);
+
+    let mut edits = migrator.edits;
+    edits.sort_by_key(|edit| edit.start);
+    let mut text = source.to_string();
+    for edit in edits.iter().rev() {
+        text.replace_range(edit.start..edit.end, &edit.text);
+    }
+    if yaml::parse(&text).is_err() {
+        return Err(Error::Unsupported("the migrated file would not parse; it was left unchanged".to_string()));
+    }
+
+    let changes = edits.into_iter().map(|edit| edit.change).collect();
+    Ok(Migration { text, changes, manual: migrator.manual })
+}
+
+/// The changed lines of `after` as a unified diff without context lines.
+/// Migration replaces text within lines, so both sides have the same lines.
+pub fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    let before: Vec<&str> = before.lines().collect();
+    let after: Vec<&str> = after.lines().collect();
+    let mut diff = format!("--- {path}\n+++ {path}\n");
+
+    let mut line = 0;
+    while line < before.len().min(after.len()) {
+        if before[line] == after[line] {
+            line += 1;
+            continue;
+        }
+        let start = line;
+        while line < before.len().min(after.len()) && before[line] != after[line] {
+            line += 1;
+        }
+        let count = line - start;
+        diff.push_str(&format!("@@ -{},{} +{},{} @@\n", start + 1, count, start + 1, count));
+        for text in &before[start..line] {
+            diff.push_str(&format!("-{text}\n"));
+        }
+        for text in &after[start..line] {
+            diff.push_str(&format!("+{text}\n"));
+        }
+    }
+    diff
+}
+
+/// A replacement of the text between two byte offsets
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+    change: Change,
+}
+
+struct Migrator<'c> {
+    source: &'c str,
+    context: &'c ParsingContext,
+    mapping: &'c TypeMapping,
+    edits: Vec<Edit>,
+    manual: Vec<Change>,
+}
+
+impl<'c> Migrator<'c> {
+    fn walk(&mut self, node: &Node, shape: Shape<'c>) {
+        match &node.value {
+            NodeValue::Mapping(entries) if shape.map => {
+                for entry in entries {
+                    self.walk(&entry.value, shape.element());
+                }
+            }
+            NodeValue::Mapping(entries) => {
+                let Some(class) = shape.class.filter(|_| !shape.list) else { return };
+                let keys: Vec<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+                let properties = self.context.all_properties(self.context.variant(class, &keys));
+
+                for entry in entries {
+                    let (prefix, name, qualifier) = split_modifiers(&entry.key);
+                    let Some(property) = properties.iter().find(|p| p.name == name) else {
+                        // `- ../lib: exported`: a single key standing for the whole object
+                        if entries.len() == 1 {
+                            self.walk_shorthand(&entry.value, class);
+                        }
+                        continue;
+                    };
+
+                    let mut target = property;
+                    if property.deprecation_message().is_some() {
+                        let replacement = property
+                            .replacement()
+                            .and_then(|name| properties.iter().find(|p| p.name == name));
+                        match replacement {
+                            Some(replacement) => {
+                                let key = format!("{}{}{}", prefix, replacement.name, qualifier);
+                                if keys.contains(&key.as_str()) {
+                                    self.manual.push(Change {
+                                        position: entry.key_position,
+                                        message: format!(
+                                            "Property `{}` is deprecated, but `{}` is already set",
+                                            entry.key, key
+                                        ),
+                                    });
+                                } else {
+                                    let message = format!("Renamed `{}` to `{}`", entry.key, key);
+                                    self.replace(entry.key_position, &entry.key, &key, message);
+                                    target = replacement;
+                                }
+                            }
+                            None => self.manual.push(Change {
+                                position: entry.key_position,
+                                message: deprecation_note(property, &entry.key),
+                            }),
+                        }
+                    }
+                    let shape = Shape::of(target, self.context, self.mapping);
+                    self.walk(&entry.value, shape);
+                }
+            }
+            NodeValue::Sequence(items) => {
+                let element = if shape.list { shape.element() } else { Shape::default() };
+                for item in items {
+                    self.walk(item, element);
+                }
+            }
+            NodeValue::Null => {}
+            _ => match (shape.enum_def, shape.class) {
+                (Some(enum_def), _) => self.check_value(node, enum_def),
+                (None, Some(class)) => self.walk_shorthand(node, class),
+                (None, None) => {}
+            },
+        }
+    }
+
+    /// A scalar written for a whole object is the value of its `@Shorthand`
+    /// property, in the class or any of its variants
+    fn walk_shorthand(&mut self, node: &Node, class: &'c ClassDef) {
+        let Some(text) = node.scalar_text() else { return };
+        let mut pending = vec![class];
+        while let Some(class) = pending.pop() {
+            for property in self.context.all_properties(class).iter().filter(|p| p.is_shorthand()) {
+                let type_name = self.mapping.unwrap_all(&property.type_name);
+                if let Some(enum_def) = self.context.enums.get(type_name)
+                    && enum_def.entry_by_value(text).is_some()
+                {
+                    self.check_value(node, enum_def);
+                    return;
+                }
+            }
+            pending.extend(class.subclasses.iter().filter_map(|name| self.context.classes.get(name)));
+        }
+    }
+
+    fn check_value(&mut self, node: &Node, enum_def: &EnumDef) {
+        let Some(text) = node.scalar_text() else { return };
+        let Some(entry) = enum_def.entry_by_value(text) else { return };
+
+        match entry.replacement.as_deref().and_then(|name| enum_def.entry(name)) {
+            Some(replacement) => {
+                let message = format!("Replaced `{}` with `{}`", text, replacement.schema_value);
+                self.replace(node.position, text, &replacement.schema_value, message);
+            }
+            None if entry.is_outdated => self.manual.push(Change {
+                position: node.position,
+                message: format!("Value `{}` is outdated and has no replacement", text),
+            }),
+            None => {}
+        }
+    }
+
+    /// Replace the scalar `old` written at `position` with `new`, keeping its quotes
+    fn replace(&mut self, position: Position, old: &str, new: &str, message: String) {
+        let start = offset(self.source, position);
+        let written = &self.source[start..];
+        let (end, text) = match written.chars().next() {
+            Some(quote @ ('"' | '\'')) => match cst::quoted_len(written) {
+                Some(len) => (start + len, format!("{quote}{new}{quote}")),
+                None => (start, String::new()),
+            },
+            _ if written.starts_with(old) => (start + old.len(), new.to_string()),
+            _ => (start, String::new()),
+        };
+
+        if end == start {
+            self.manual.push(Change {
+                position,
+                message: format!("`{}` should be `{}`, but couldn't be rewritten in place", old, new),
+            });
+            return;
+        }
+        self.edits.push(Edit { start, end, text, change: Change { position, message } });
+    }
+}
+
+/// Split `test-settings@jvm` into `test-`, `settings` and `@jvm`
+pub(crate) fn split_modifiers(key: &str) -> (&str, &str, &str) {
+    let (rest, qualifier) = match key.find('@') {
+        Some(index) => key.split_at(index),
+        None => (key, ""),
+    };
+    match rest.strip_prefix("test-") {
+        Some(name) => ("test-", name, qualifier),
+        None => ("", rest, qualifier),
+    }
+}
+
+fn deprecation_note(property: &Property, key: &str) -> String {
+    match property.deprecation_message().filter(|message| !message.is_empty()) {
+        Some(message) => format!("Property `{}` is deprecated: {}", key, message),
+        None => format!("Property `{}` is deprecated", key),
+    }
+}
+
+/// Byte offset of a 1-based line and (character) column
+fn offset(source: &str, position: Position) -> usize {
+    let line_start: usize = source.split_inclusive('\n').take(position.line - 1).map(str::len).sum();
+    source[line_start..]
+        .char_indices()
+        .nth(position.column - 1)
+        .map_or(source.len(), |(index, _)| line_start + index)
+}
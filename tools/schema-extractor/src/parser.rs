@@ -1,18 +1,23 @@
 //! Kotlin source file parser
 
+use crate::error::{Error, Result};
 use crate::types::*;
-use anyhow::{Context, Result};
 use regex::Regex;
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::Path;
+use std::sync::LazyLock;
 use walkdir::WalkDir;
 
+/// Matches a single annotation with optional arguments.
+///
+/// String literals may contain parentheses (Markdown links in `@SchemaDoc`) and
+/// arguments may nest one level deep, as in `@Deprecated("...", ReplaceWith("x"))`.
+const ANNOTATION_PATTERN: &str = r#"@\w+(?:\((?:"(?:[^"\\]|\\.)*"|\((?:"(?:[^"\\]|\\.)*"|[^()"])*\)|[^()"])*\))?"#;
+
 /// Parse the entire source directory
 pub fn parse_source_directory(source_dir: &Path, verbose: bool) -> Result<ParsingContext> {
-    let mut context = ParsingContext::default();
-    context.verbose = verbose;
-
     // Primary schema location
     let schema_path = source_dir.join("frontend-api/src/org/jetbrains/amper/frontend/schema");
 
@@ -20,6 +25,7 @@ pub fn parse_source_directory(source_dir: &Path, verbose: bool) -> Result<Parsin
         eprintln!("Scanning schema files in: {}", schema_path.display());
     }
 
+    let mut sources = Vec::new();
     for entry in WalkDir::new(&schema_path)
         .follow_links(true)
         .into_iter()
@@ -30,10 +36,28 @@ pub fn parse_source_directory(source_dir: &Path, verbose: bool) -> Result<Parsin
             if verbose {
                 eprintln!("  Parsing: {}", path.file_name().unwrap().to_string_lossy());
             }
-            parse_kotlin_file(path, &mut context, verbose)?;
+            let content = fs::read_to_string(path).map_err(Error::io(path))?;
+            sources.push((path.to_path_buf(), content));
         }
     }
 
+    parse_sources(sources.iter().map(|(path, content)| (path.as_path(), content.as_str())), verbose)
+}
+
+/// Parse Kotlin sources given as their path and content, e.g. by a build
+/// script that has them in memory. Paths are only used in errors.
+pub fn parse_sources<'a>(sources: impl IntoIterator<Item = (&'a Path, &'a str)>, verbose: bool) -> Result<ParsingContext> {
+    let mut context = ParsingContext {
+        verbose,
+        ..Default::default()
+    };
+
+    for (path, content) in sources {
+        parse_enums(content, &mut context);
+        parse_classes(content, &mut context, verbose)
+            .map_err(|message| Error::Kotlin { path: path.to_path_buf(), message: message.to_string() })?;
+    }
+
     // Resolve sealed class hierarchies
     resolve_sealed_hierarchies(&mut context);
     
@@ -50,53 +74,31 @@ pub fn parse_source_directory(source_dir: &Path, verbose: bool) -> Result<Parsin
     Ok(context)
 }
 
-/// Parse a single Kotlin file
-fn parse_kotlin_file(path: &Path, context: &mut ParsingContext, verbose: bool) -> Result<()> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file: {}", path.display()))?;
-
-    // Parse enums
-    parse_enums(&content, context)?;
-
-    // Parse classes
-    parse_classes(&content, context, verbose)?;
-
-    Ok(())
-}
-
 /// Parse enum definitions
-fn parse_enums(content: &str, context: &mut ParsingContext) -> Result<()> {
-    let enum_regex = Regex::new(
-        r"(?s)(@SchemaDoc\([^\)]+\)\s*)?(@EnumOrderSensitive[^\n]*)?\s*enum\s+class\s+(\w+)\s*\([^)]*\)\s*:\s*SchemaEnum\s*\{([^}]+)\}"
-    )?;
-
-    for cap in enum_regex.captures_iter(content) {
-        let doc = cap.get(1).map(|m| extract_doc_string(m.as_str()));
-        let is_order_sensitive = cap.get(2).is_some();
-        let name = cap[3].to_string();
-        let body = &cap[4];
-
-        let mut entries = Vec::new();
-        let entry_regex = Regex::new(
-            r#"(@SchemaDoc\([^\)]+\)\s*)?(\w+)\s*\(\s*"([^"]+)""#
-        )?;
-
-        for entry_cap in entry_regex.captures_iter(body) {
-            let entry_doc = entry_cap.get(1).map(|m| extract_doc_string(m.as_str()));
-            let entry_name = entry_cap[2].to_string();
-            let schema_value = entry_cap[3].to_string();
-
-            // Check if marked as outdated in the body
-            let is_outdated = body.contains(&format!("outdated: Boolean = true"))
-                && body[..entry_cap.get(0).unwrap().end()].contains(&entry_name);
-
-            entries.push(EnumEntry {
-                name: entry_name,
-                schema_value,
-                doc: entry_doc,
-                is_outdated,
-            });
-        }
+fn parse_enums(content: &str, context: &mut ParsingContext) {
+    static ENUM_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(&format!(
+            r"(?s)((?:{ANNOTATION_PATTERN}\s*)*)\s*enum\s+class\s+(\w+)\s*\([^)]*\)\s*:\s*SchemaEnum\s*\{{([^}}]+)\}}"
+        ))
+        .unwrap()
+    });
+    static REVERSED_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"@EnumOrderSensitive\(\s*reverse\s*=\s*true").unwrap());
+
+    for cap in ENUM_REGEX.captures_iter(content) {
+        let annotations_str = &cap[1];
+        let doc = annotations_str
+            .contains("@SchemaDoc")
+            .then(|| extract_doc_string(annotations_str));
+        let is_order_sensitive = annotations_str.contains("@EnumOrderSensitive");
+        let is_order_reversed = REVERSED_REGEX.is_match(annotations_str);
+        let name = cap[2].to_string();
+        let body = &cap[3];
+
+        let entries = split_enum_entries(body)
+            .into_iter()
+            .filter_map(|(text, kdoc)| parse_enum_entry(&text, kdoc))
+            .collect();
 
         context.enums.insert(
             name.clone(),
@@ -105,22 +107,198 @@ fn parse_enums(content: &str, context: &mut ParsingContext) -> Result<()> {
                 doc,
                 entries,
                 is_order_sensitive,
+                is_order_reversed,
             },
         );
     }
+}
 
-    Ok(())
+/// Split an enum body into its entry declarations, each paired with the KDoc
+/// written in front of it.
+///
+/// Entries are separated by top-level commas and terminated by `;` (or the end
+/// of the body), so commas inside constructor arguments are kept intact.
+/// Comments are dropped from the entry text, so neither a comment before an
+/// entry nor a comma inside one changes the entries.
+fn split_enum_entries(body: &str) -> Vec<(String, Option<String>)> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut kdoc = None;
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut rest = body;
+
+    while let Some(ch) = rest.chars().next() {
+        if !in_string {
+            if rest.starts_with("//") {
+                rest = rest.find('\n').map_or("", |end| &rest[end..]);
+                continue;
+            }
+            if let Some(comment) = rest.strip_prefix("/*") {
+                let end = comment.find("*/").unwrap_or(comment.len());
+                if let Some(text) = comment[..end].strip_prefix('*') {
+                    kdoc = Some(kdoc_text(text));
+                }
+                rest = comment.get(end + 2..).unwrap_or("");
+                current.push(' ');
+                continue;
+            }
+        }
+        rest = &rest[ch.len_utf8()..];
+        match ch {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' | ';' if depth == 0 => {
+                if !current.trim().is_empty() {
+                    entries.push((std::mem::take(&mut current), kdoc.take()));
+                }
+                current.clear();
+                if ch == ';' {
+                    return entries;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        current.push(ch);
+    }
+
+    if !current.trim().is_empty() {
+        entries.push((current, kdoc));
+    }
+    entries
+}
+
+/// The text of a KDoc comment without its leading `*` decorations
+fn kdoc_text(comment: &str) -> String {
+    comment
+        .lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse a single enum entry such as `JVM_APP("jvm/app", supportedPlatforms = setOf(Platform.JVM))`.
+///
+/// `@SchemaDoc` takes precedence over the entry's KDoc as its description.
+fn parse_enum_entry(text: &str, kdoc: Option<String>) -> Option<EnumEntry> {
+    static ENUM_ENTRY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(&format!(r"(?s)^\s*((?:{ANNOTATION_PATTERN}\s*)*)(\w+)\s*(?:\((.*)\))?\s*$")).unwrap()
+    });
+    let cap = ENUM_ENTRY_REGEX.captures(text)?;
+
+    let annotations_str = &cap[1];
+    let doc = if annotations_str.contains("@SchemaDoc") {
+        Some(extract_doc_string(annotations_str))
+    } else {
+        kdoc
+    };
+    let name = cap[2].to_string();
+    let arguments: Vec<EnumArgument> = cap
+        .get(3)
+        .map(|m| split_arguments(m.as_str()))
+        .unwrap_or_default();
+
+    // The schema value is the leading string literal; entries without one
+    // (like `Platform`) derive it from the entry name.
+    let schema_value = arguments
+        .first()
+        .filter(|arg| arg.name.is_none())
+        .and_then(|arg| arg.value.strip_prefix('"')?.strip_suffix('"').map(str::to_string))
+        .unwrap_or_else(|| to_camel_case(&name));
+
+    let is_outdated = arguments
+        .iter()
+        .any(|arg| arg.name.as_deref() == Some("outdated") && arg.value == "true");
+    let replacement = annotations_str
+        .contains("@Deprecated")
+        .then(|| replace_with(annotations_str))
+        .flatten()
+        .map(|name| name.rsplit('.').next().unwrap_or(&name).to_string());
+
+    Some(EnumEntry {
+        name,
+        schema_value,
+        doc,
+        is_outdated,
+        replacement,
+        arguments,
+    })
+}
+
+/// Split constructor arguments on top-level commas into positional and named arguments
+fn split_arguments(args: &str) -> Vec<EnumArgument> {
+    static NAMED_ARGUMENT_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)^(\w+)\s*=\s*(.+)$").unwrap());
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut start = 0;
+
+    for (i, ch) in args.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&args[start..]);
+
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| match NAMED_ARGUMENT_REGEX.captures(p) {
+            Some(cap) => EnumArgument {
+                name: Some(cap[1].to_string()),
+                value: cap[2].trim().to_string(),
+            },
+            None => EnumArgument {
+                name: None,
+                value: p.to_string(),
+            },
+        })
+        .collect()
+}
+
+/// Convert an enum entry name like `IOS_SIMULATOR_ARM64` to `iosSimulatorArm64`
+fn to_camel_case(name: &str) -> String {
+    let mut result = String::new();
+    for (i, part) in name.split('_').filter(|p| !p.is_empty()).enumerate() {
+        let lower = part.to_lowercase();
+        if i == 0 {
+            result.push_str(&lower);
+        } else {
+            let mut chars = lower.chars();
+            if let Some(first) = chars.next() {
+                result.extend(first.to_uppercase());
+                result.push_str(chars.as_str());
+            }
+        }
+    }
+    result
 }
 
 /// Parse class definitions
-fn parse_classes(content: &str, context: &mut ParsingContext, verbose: bool) -> Result<()> {
+fn parse_classes(content: &str, context: &mut ParsingContext, verbose: bool) -> Result<(), &'static str> {
     // Match class headers - need to handle multiline and various whitespace
-    let class_header_regex = Regex::new(
-        r"(?m)((?:@\w+\([^\)]*\)\s*)*)?\s*(abstract\s+|sealed\s+)?class\s+(\w+)\s*(?:\(\))?\s*:\s*(\w+)\s*\(\)\s*\{"
-    )?;
+    static CLASS_HEADER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(&format!(
+            r"(?m)((?:{ANNOTATION_PATTERN}\s*)*)?\s*(abstract\s+|sealed\s+)?class\s+(\w+)\s*(?:\(\))?\s*:\s*(\w+)\s*\(\)(\s*\{{)?"
+        ))
+        .unwrap()
+    });
 
     let mut matches = Vec::new();
-    for cap in class_header_regex.captures_iter(content) {
+    for cap in CLASS_HEADER_REGEX.captures_iter(content) {
         let start = cap.get(0).unwrap().start();
         
         // Extract annotations from group 1
@@ -135,6 +313,7 @@ fn parse_classes(content: &str, context: &mut ParsingContext, verbose: bool) ->
         let is_sealed = cap.get(2).map(|m| m.as_str().contains("sealed")).unwrap_or(false);
         let name = cap.get(3).map(|m| m.as_str().to_string()).unwrap_or_default();
         let parent = cap.get(4).map(|m| m.as_str().to_string()).unwrap_or_default();
+        let has_body = cap.get(5).is_some();
         
         if name.is_empty() || parent.is_empty() {
             continue;
@@ -145,11 +324,11 @@ fn parse_classes(content: &str, context: &mut ParsingContext, verbose: bool) ->
                      name, parent, is_abstract, is_sealed);
         }
         
-        matches.push((start, doc, is_sealed, name, parent));
+        matches.push((start, doc, is_sealed, name, parent, has_body));
     }
 
     // Extract class bodies by finding matching braces
-    for (start, doc, is_sealed, name, parent) in matches {
+    for (start, doc, is_sealed, name, parent, has_body) in matches {
         // Include Base class and known schema classes
         let valid_parents = [
             "SchemaNode", "Base", "Dependency", "ScopedDependency",
@@ -164,9 +343,13 @@ fn parse_classes(content: &str, context: &mut ParsingContext, verbose: bool) ->
             continue;
         }
 
-        // Find the class body by counting braces
-        let body = extract_class_body(&content[start..])?;
-        let properties = parse_properties(&body, verbose)?;
+        // Find the class body by counting braces; `sealed class X : Y()` may have none
+        let properties = if has_body {
+            let body = extract_class_body(&content[start..])?;
+            parse_properties(&body, verbose)
+        } else {
+            Vec::new()
+        };
         
         if verbose {
             eprintln!("    Parsed {} with {} properties", name, properties.len());
@@ -189,8 +372,8 @@ fn parse_classes(content: &str, context: &mut ParsingContext, verbose: bool) ->
 }
 
 /// Extract class body by matching braces
-fn extract_class_body(text: &str) -> Result<String> {
-    let open_brace = text.find('{').ok_or_else(|| anyhow::anyhow!("No opening brace found"))?;
+fn extract_class_body(text: &str) -> Result<String, &'static str> {
+    let open_brace = text.find('{').ok_or("No opening brace found")?;
     let mut depth = 0;
     let mut end_pos = open_brace;
 
@@ -209,23 +392,26 @@ fn extract_class_body(text: &str) -> Result<String> {
     }
 
     if depth != 0 {
-        return Err(anyhow::anyhow!("Unmatched braces"));
+        return Err("Unmatched braces");
     }
 
     Ok(text[open_brace + 1..end_pos].to_string())
 }
 
 /// Parse property definitions from class body
-fn parse_properties(body: &str, verbose: bool) -> Result<Vec<Property>> {
+fn parse_properties(body: &str, verbose: bool) -> Vec<Property> {
     let mut properties = Vec::new();
 
     // Improved regex to match various property patterns
     // Matches: val name by value<Type>() / val name: Type by nested() / val name by nullableValue<Type>()
-    let prop_regex = Regex::new(
-        r"(?s)((?:@\w+(?:\([^\)]*\))?\s*)*)val\s+(\w+)(?:\s*:\s*(\w+(?:<[^>]*(?:<[^>]*>)?[^>]*>)?))?\s+by\s+(\w+)\s*(?:<([^>]*(?:<[^>]*>)?[^>]*)>)?\s*\("
-    )?;
-
-    for cap in prop_regex.captures_iter(body) {
+    static PROPERTY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(&format!(
+            r"(?s)((?:{ANNOTATION_PATTERN}\s*)*)val\s+(\w+)(?:\s*:\s*(\w+(?:<[^>]*(?:<[^>]*>)?[^>]*>)?))?\s+by\s+(\w+)\s*(?:<([^>]*(?:<[^>]*>)?[^>]*)>)?\s*\("
+        ))
+        .unwrap()
+    });
+
+    for cap in PROPERTY_REGEX.captures_iter(body) {
         let annotations_str = &cap[1];
         let prop_name = cap[2].to_string();
         
@@ -234,7 +420,28 @@ fn parse_properties(body: &str, verbose: bool) -> Result<Vec<Property>> {
         let generic_type = cap.get(5).map(|m| m.as_str().trim());
         let delegate_func = &cap[4]; // value, nullableValue, nested, dependentValue
         
-        let type_str = generic_type.or(explicit_type).unwrap_or("String");
+        // Delegate arguments carry the default value: `value(false)`, `value(default = X)`
+        let call_start = cap.get(0).unwrap().end() - 1;
+        let default_value = match delegate_func {
+            "value" | "nullableValue" => extract_call_args(&body[call_start..])
+                .map(|args| {
+                    let args = args.trim();
+                    args.strip_prefix("default")
+                        .and_then(|rest| rest.trim_start().strip_prefix('='))
+                        .unwrap_or(args)
+                        .trim()
+                })
+                .filter(|args| !args.is_empty())
+                .map(str::to_string),
+            _ => None,
+        };
+
+        // Without an explicit type, infer it from a literal default
+        let inferred_type = default_value.as_deref().and_then(infer_literal_type);
+        let type_str = generic_type
+            .or(explicit_type)
+            .or(inferred_type.as_deref())
+            .unwrap_or("String");
         
         if verbose {
             eprintln!("      Property: {} (type: {}, delegate: {})", prop_name, type_str, delegate_func);
@@ -262,20 +469,62 @@ fn parse_properties(body: &str, verbose: bool) -> Result<Vec<Property>> {
             is_nullable,
             is_list,
             is_map,
-            default_value: None,
+            default_value,
+            // Nested objects are always instantiated, so they are never required
+            is_nested: delegate_func == "nested",
             annotations,
         });
     }
 
-    Ok(properties)
+    properties
+}
+
+/// Extract the text between the parentheses of a call starting at `text`
+fn extract_call_args(text: &str) -> Option<&str> {
+    let mut depth = 0;
+    let mut in_string = false;
+    for (i, ch) in text.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[1..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Infer the Kotlin type of a literal default value.
+///
+/// `ProductType.JVM_APP` is taken as an enum entry; other expressions are unknown.
+fn infer_literal_type(value: &str) -> Option<String> {
+    static ENUM_CONSTANT_REGEX: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^([A-Z]\w*)\.[A-Z][A-Z0-9_]*$").unwrap());
+    if value == "true" || value == "false" {
+        Some("Boolean".to_string())
+    } else if value.parse::<i64>().is_ok() {
+        Some("Int".to_string())
+    } else if value.parse::<f64>().is_ok() {
+        Some("Double".to_string())
+    } else if value.starts_with('"') {
+        Some("String".to_string())
+    } else {
+        ENUM_CONSTANT_REGEX.captures(value).map(|cap| cap[1].to_string())
+    }
 }
 
 /// Parse annotations from annotation string
-fn parse_annotations(annotations_str: &str) -> HashSet<String> {
-    let annotation_regex = Regex::new(r"@(\w+)(?:\([^\)]*\))?").unwrap();
-    annotation_regex
+fn parse_annotations(annotations_str: &str) -> BTreeSet<String> {
+    static ANNOTATION_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(ANNOTATION_PATTERN).unwrap());
+    ANNOTATION_REGEX
         .captures_iter(annotations_str)
-        .map(|cap| cap[0].to_string())
+        .map(|cap| cap[0].trim_start_matches('@').to_string())
         .collect()
 }
 
@@ -307,13 +556,49 @@ fn parse_type_info(type_str: &str) -> (String, bool, bool, bool) {
 }
 
 /// Extract documentation string from @SchemaDoc annotation
+///
+/// Concatenated literals (`"a" + "b"`) are joined and Kotlin escapes are resolved.
 fn extract_doc_string(annotation: &str) -> String {
-    let doc_regex = Regex::new(r#"@SchemaDoc\s*\(\s*"([^"]*)""#).unwrap();
-    if let Some(cap) = doc_regex.captures(annotation) {
-        cap[1].to_string()
-    } else {
-        String::new()
+    let Some(start) = annotation.find("SchemaDoc") else {
+        return String::new();
+    };
+    let mut doc = String::new();
+    for (literal, followed_by_plus) in string_literals(&annotation[start..]) {
+        doc.push_str(&literal);
+        if !followed_by_plus {
+            break;
+        }
+    }
+    doc
+}
+
+/// Collect the string literals of an annotation argument list, each paired with
+/// whether it is followed by a `+` concatenation.
+fn string_literals(text: &str) -> Vec<(String, bool)> {
+    static LITERAL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""((?:[^"\\]|\\.)*)"(\s*\+)?"#).unwrap());
+    LITERAL_REGEX
+        .captures_iter(text)
+        .map(|cap| (unescape_kotlin(&cap[1]), cap.get(2).is_some()))
+        .collect()
+}
+
+/// Resolve the escape sequences of a Kotlin string literal
+fn unescape_kotlin(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
     }
+    result
 }
 
 /// Resolve sealed class hierarchies
@@ -341,3 +626,92 @@ fn resolve_sealed_hierarchies(context: &mut ParsingContext) {
     }
 }
 
+/// `context` with the `Template` and `Project` roots of [`add_builtin_roots`],
+/// copied only if the sources lack one of them
+pub fn with_builtin_roots(context: &ParsingContext) -> Cow<'_, ParsingContext> {
+    if context.classes.contains_key("Template") && context.classes.contains_key("Project") {
+        return Cow::Borrowed(context);
+    }
+    let mut context = context.clone();
+    add_builtin_roots(&mut context);
+    Cow::Owned(context)
+}
+
+/// Add the `Template` and `Project` root classes if no source declares them.
+///
+/// Templates (`*.module-template.yaml`) accept everything a module does except the
+/// product section, so they inherit from `Base` or mirror `Module` without
+/// `product`/`apply`. `project.yaml` is read by Amper outside the schema package,
+/// so its keys are declared here. Parsing doesn't add them, so the model only
+/// holds what the sources declare; schema generation and the commands reading
+/// template and project files apply this step.
+pub fn add_builtin_roots(context: &mut ParsingContext) {
+    if !context.classes.contains_key("Template") {
+        let template = if context.classes.contains_key("Base") {
+            Some(ClassDef {
+                name: "Template".to_string(),
+                doc: None,
+                properties: Vec::new(),
+                is_sealed: false,
+                parent: Some("Base".to_string()),
+                subclasses: Vec::new(),
+            })
+        } else {
+            context.classes.get("Module").map(|module| ClassDef {
+                name: "Template".to_string(),
+                doc: None,
+                properties: module
+                    .properties
+                    .iter()
+                    .filter(|p| p.name != "product" && p.name != "apply")
+                    .cloned()
+                    .collect(),
+                is_sealed: false,
+                parent: module.parent.clone(),
+                subclasses: Vec::new(),
+            })
+        };
+        if let Some(template) = template {
+            context.classes.insert("Template".to_string(), template);
+        }
+    }
+
+    if !context.classes.contains_key("Project") {
+        context.classes.insert(
+            "Project".to_string(),
+            ClassDef {
+                name: "Project".to_string(),
+                doc: Some("Amper project configuration (project.yaml)".to_string()),
+                properties: vec![
+                    builtin_list_property(
+                        "modules",
+                        "Paths to the modules of the project, relative to project.yaml. \
+                         Glob patterns such as `./libs/*` include every matching directory containing a module.yaml",
+                    ),
+                    builtin_list_property(
+                        "plugins",
+                        "Paths to the local plugin modules used by the project, relative to project.yaml",
+                    ),
+                ],
+                is_sealed: false,
+                parent: None,
+                subclasses: Vec::new(),
+            },
+        );
+    }
+}
+
+/// An optional list-of-paths property of a built-in root
+fn builtin_list_property(name: &str, doc: &str) -> Property {
+    Property {
+        name: name.to_string(),
+        type_name: "Path".to_string(),
+        doc: Some(doc.to_string()),
+        is_nullable: true,
+        is_list: true,
+        is_map: false,
+        default_value: None,
+        is_nested: false,
+        annotations: BTreeSet::new(),
+    }
+}
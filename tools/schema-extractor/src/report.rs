@@ -0,0 +1,288 @@
+//! Machine-readable reports of validation, lint and project check results
+//!
+//! The commands print `file:line:column` lines by default. `--format json`
+//! writes the findings in this tool's own format, versioned so consumers
+//! like the extension can rely on it; `--format sarif` writes a SARIF 2.1.0
+//! log for code scanning dashboards.
+
+use crate::cst;
+use crate::yaml::Position;
+use serde_json::{Value, json};
+use std::fmt;
+
+const DOCS: &str = "https://github.com/Tinnci/amper-vscode/blob/main/tools/schema-extractor/README.md";
+
+/// Version of the JSON report; bumped on incompatible changes
+const JSON_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ReportFormat {
+    /// One `file:line:column: message` line per finding
+    Text,
+    /// This tool's JSON report
+    Json,
+    /// SARIF 2.1.0
+    Sarif,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A kind of finding
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub summary: &'static str,
+    /// Anchor of the README section documenting the rule
+    pub docs: &'static str,
+}
+
+impl Rule {
+    pub fn docs_url(&self) -> String {
+        format!("{}#{}", DOCS, self.docs)
+    }
+}
+
+pub const SYNTAX_ERROR: Rule = Rule {
+    id: "syntax-error",
+    severity: Severity::Error,
+    summary: "The file is not valid YAML",
+    docs: "validating-configuration-files",
+};
+
+/// A validation or lint result in a file
+#[derive(Debug)]
+pub struct Finding {
+    pub file: String,
+    pub rule: &'static Rule,
+    pub message: String,
+    pub start: Position,
+    /// End of the offending key or value, when it is known
+    pub end: Option<Position>,
+    /// Dotted path to the offending node, for validation results
+    pub path: Option<String>,
+    pub fixes: Vec<Fix>,
+}
+
+/// A replacement of the text between two positions
+#[derive(Debug)]
+pub struct Fix {
+    pub description: String,
+    pub start: Position,
+    pub end: Position,
+    pub replacement: String,
+}
+
+/// Where the scalar `text` written at `position` in `source` ends, quotes included
+pub fn token_end(source: &str, position: Position, text: &str) -> Option<Position> {
+    let line = source.lines().nth(position.line.checked_sub(1)?)?;
+    let written: String = line.chars().skip(position.column.checked_sub(1)?).collect();
+    let length = match written.chars().next()? {
+        '"' | '\'' => written[..cst::quoted_len(&written)?].chars().count(),
+        _ if written.starts_with(text) => text.chars().count(),
+        _ => return None,
+    };
+    Some(Position { line: position.line, column: position.column + length })
+}
+
+/// The findings of a command in `format`, for `files` checked files.
+/// `text` writes a finding as its line of text output; `rules` are the rules
+/// the command ran, listed in SARIF output together with [`SYNTAX_ERROR`].
+pub fn render(
+    format: ReportFormat,
+    findings: &[Finding],
+    files: usize,
+    rules: &[&'static Rule],
+    text: fn(&Finding) -> String,
+) -> String {
+    match format {
+        ReportFormat::Text => findings.iter().map(|finding| text(finding) + "\n").collect(),
+        ReportFormat::Json => format!("{:#}\n", to_json(findings, files)),
+        ReportFormat::Sarif => {
+            let mut rules = rules.to_vec();
+            if !rules.contains(&&SYNTAX_ERROR) {
+                rules.push(&SYNTAX_ERROR);
+            }
+            format!("{:#}\n", to_sarif(findings, &rules))
+        }
+    }
+}
+
+/// The rules with findings, in the order they first fired
+pub fn fired_rules(findings: &[Finding]) -> Vec<&'static Rule> {
+    let mut fired: Vec<&'static Rule> = Vec::new();
+    for finding in findings {
+        if !fired.contains(&finding.rule) {
+            fired.push(finding.rule);
+        }
+    }
+    fired
+}
+
+/// The findings as this tool's JSON report
+pub fn to_json(findings: &[Finding], files: usize) -> Value {
+    let count = |severity| findings.iter().filter(|finding| finding.rule.severity == severity).count();
+    let diagnostics: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut diagnostic = json!({
+                "file": finding.file,
+                "rule": finding.rule.id,
+                "severity": finding.rule.severity.to_string(),
+                "message": finding.message,
+                "range": json_range(finding.start, finding.end),
+                "fixes": finding.fixes.iter().map(|fix| json!({
+                    "description": fix.description,
+                    "range": json_range(fix.start, Some(fix.end)),
+                    "replacement": fix.replacement,
+                })).collect::<Vec<_>>(),
+            });
+            if let Some(path) = &finding.path {
+                diagnostic["path"] = json!(path);
+            }
+            diagnostic
+        })
+        .collect();
+
+    json!({
+        "version": JSON_VERSION,
+        "diagnostics": diagnostics,
+        "summary": {
+            "files": files,
+            "errors": count(Severity::Error),
+            "warnings": count(Severity::Warning),
+        },
+    })
+}
+
+fn json_range(start: Position, end: Option<Position>) -> Value {
+    let position = |p: Position| json!({ "line": p.line, "column": p.column });
+    match end {
+        Some(end) => json!({ "start": position(start), "end": position(end) }),
+        None => json!({ "start": position(start) }),
+    }
+}
+
+/// The findings as a SARIF 2.1.0 log, listing `rules` as the rules of the run
+pub fn to_sarif(findings: &[Finding], rules: &[&Rule]) -> Value {
+    let rules: Vec<Value> = rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.summary },
+                "helpUri": rule.docs_url(),
+                "defaultConfiguration": { "level": sarif_level(rule.severity) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let uri = artifact_uri(&finding.file);
+            let fixes: Vec<Value> = finding
+                .fixes
+                .iter()
+                .map(|fix| {
+                    json!({
+                        "description": { "text": fix.description },
+                        "artifactChanges": [{
+                            "artifactLocation": { "uri": uri },
+                            "replacements": [{
+                                "deletedRegion": sarif_region(fix.start, Some(fix.end)),
+                                "insertedContent": { "text": fix.replacement },
+                            }],
+                        }],
+                    })
+                })
+                .collect();
+
+            let mut result = json!({
+                "ruleId": finding.rule.id,
+                "level": sarif_level(finding.rule.severity),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri },
+                        "region": sarif_region(finding.start, finding.end),
+                    },
+                }],
+            });
+            if !fixes.is_empty() {
+                result["fixes"] = Value::Array(fixes);
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "extract-schema",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": DOCS,
+                    "rules": rules,
+                },
+            },
+            "results": results,
+            // Columns count characters, not the UTF-16 code units SARIF assumes by default
+            "columnKind": "unicodeCodePoints",
+        }],
+    })
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+fn sarif_region(start: Position, end: Option<Position>) -> Value {
+    let mut region = json!({ "startLine": start.line, "startColumn": start.column });
+    if let Some(end) = end {
+        region["endLine"] = json!(end.line);
+        region["endColumn"] = json!(end.column);
+    }
+    region
+}
+
+/// A relative URI reference for a file path
+pub(crate) fn artifact_uri(path: &str) -> String {
+    let mut uri = String::new();
+    for c in path.replace('\\', "/").chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' | '/' => uri.push(c),
+            _ => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    uri.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+    uri
+}
+
+/// `singular` or `plural`, whichever fits `count`
+pub(crate) fn plural<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
+    if count == 1 { singular } else { plural }
+}
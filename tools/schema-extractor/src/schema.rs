@@ -1,94 +1,260 @@
 //! JSON Schema generation
 
+use crate::error::{Error, Result};
+use crate::mapping::TypeMapping;
+use crate::parser::with_builtin_roots;
 use crate::types::*;
-use anyhow::Result;
 use indexmap::IndexMap;
+use regex::Regex;
 use serde_json::{json, Map, Value};
+use std::sync::LazyLock;
+
+/// Name of the shared definition rejecting explicit `null` values
+const NON_NULL_DEFINITION: &str = "NonNull";
+
+/// Editor the generated annotations are tailored for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SchemaFlavor {
+    /// Red Hat YAML extension keywords: `markdownDescription`, `defaultSnippets`, ...
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "vscode"))]
+    VsCode,
+    /// IntelliJ `x-intellij-*` metadata
+    #[cfg_attr(feature = "cli", value(name = "intellij"))]
+    IntelliJ,
+}
+
+/// JSON Schema draft the output is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum SchemaDialect {
+    /// Draft-07: `definitions`, and `$ref` ignores sibling keywords
+    #[cfg_attr(feature = "cli", value(name = "draft-07"))]
+    Draft07,
+    /// Draft 2019-09: `$defs`
+    #[cfg_attr(feature = "cli", value(name = "2019-09"))]
+    Draft2019_09,
+    /// Draft 2020-12: `$defs`
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "2020-12"))]
+    Draft2020_12,
+}
+
+impl SchemaDialect {
+    /// The `$schema` URI identifying the dialect
+    pub fn schema_uri(self) -> &'static str {
+        match self {
+            SchemaDialect::Draft07 => "http://json-schema.org/draft-07/schema#",
+            SchemaDialect::Draft2019_09 => "https://json-schema.org/draft/2019-09/schema",
+            SchemaDialect::Draft2020_12 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+
+    /// The keyword holding reusable definitions
+    pub fn definitions_key(self) -> &'static str {
+        match self {
+            SchemaDialect::Draft07 => "definitions",
+            SchemaDialect::Draft2019_09 | SchemaDialect::Draft2020_12 => "$defs",
+        }
+    }
+
+    /// JSON pointer reference to a named definition
+    pub fn ref_to(self, name: &str) -> String {
+        format!("#/{}/{}", self.definitions_key(), name)
+    }
+}
+
+/// Options controlling schema generation
+#[derive(Debug, Clone, Default)]
+pub struct SchemaOptions {
+    pub flavor: SchemaFlavor,
+    pub dialect: SchemaDialect,
+    /// Leave `@HiddenFromCompletion` properties out of the schema entirely
+    pub drop_hidden: bool,
+    /// How Kotlin value types map to JSON Schema primitives
+    pub type_mapping: TypeMapping,
+}
 
-/// Generate JSON Schema from parsing context
-pub fn generate_json_schema(context: &ParsingContext, root_type: &str) -> Result<Value> {
-    let mut builder = SchemaBuilder::new(context);
+/// Generate JSON Schema from parsing context.
+/// `Template` and `Project` roots are provided when the sources lack them.
+pub fn generate_json_schema(
+    context: &ParsingContext,
+    root_type: &str,
+    options: &SchemaOptions,
+) -> Result<Value> {
+    let context = with_builtin_roots(context);
+    check_definition_names(&context)?;
+    let mut builder = SchemaBuilder::new(&context, options.clone());
     builder.build(root_type)
 }
 
+/// Root schemas sharing a single definitions document
+#[derive(Debug)]
+pub struct SchemaBundle {
+    /// Document holding the definitions of every root
+    pub definitions: Value,
+    /// Small wrapper schema per root type, referencing `definitions`
+    pub roots: IndexMap<String, Value>,
+}
+
+/// Generate several root schemas in one pass.
+///
+/// `roots` pairs each root type with the file name its wrapper is written to;
+/// wrappers reference their root via `<definitions_file>#/$defs/<Root>`.
+pub fn generate_schema_bundle(
+    context: &ParsingContext,
+    roots: &[(&str, &str)],
+    definitions_file: &str,
+    options: &SchemaOptions,
+) -> Result<SchemaBundle> {
+    let context = with_builtin_roots(context);
+    check_definition_names(&context)?;
+    let mut builder = SchemaBuilder::new(&context, options.clone());
+    for (root_type, _) in roots {
+        builder.build_root_definitions(root_type)?;
+    }
+
+    let dialect = options.dialect;
+    let mut definitions = json!({
+        "$schema": dialect.schema_uri(),
+        "$id": definitions_file,
+        "title": "Amper schema definitions",
+        (dialect.definitions_key()): builder.definitions
+    });
+    if dialect == SchemaDialect::Draft07 {
+        isolate_refs(&mut definitions);
+    }
+
+    let roots = roots
+        .iter()
+        .map(|(root_type, file_name)| {
+            let reference = format!("{}{}", definitions_file, dialect.ref_to(root_type));
+            let wrapper = json!({
+                "$schema": dialect.schema_uri(),
+                "$id": file_name,
+                "title": format!("{} schema", root_type),
+                "type": "object",
+                "allOf": [
+                    { "$ref": reference }
+                ]
+            });
+            (root_type.to_string(), wrapper)
+        })
+        .collect();
+
+    Ok(SchemaBundle { definitions, roots })
+}
+
+/// Fail when two definitions would share a name: enums and classes are both
+/// defined by their simple name, next to the `NonNull` definition
+fn check_definition_names(context: &ParsingContext) -> Result<()> {
+    if let Some(name) = context.enums.keys().find(|name| context.classes.contains_key(*name)) {
+        return Err(Error::DefinitionClash { name: name.clone(), first: "a class", second: "an enum" });
+    }
+    let kind = if context.classes.contains_key(NON_NULL_DEFINITION) {
+        "a class"
+    } else if context.enums.contains_key(NON_NULL_DEFINITION) {
+        "an enum"
+    } else {
+        return Ok(());
+    };
+    Err(Error::DefinitionClash {
+        name: NON_NULL_DEFINITION.to_string(),
+        first: kind,
+        second: "the definition rejecting null values",
+    })
+}
+
+/// What tells the variants of a sealed hierarchy apart, in variant order
+enum Discriminator {
+    /// A shared key with a distinct constant default per variant
+    Constant { key: String, values: Vec<String> },
+    /// A key only that variant declares; `optional` when some variant's key may be left out
+    Keys { keys: Vec<String>, optional: bool },
+}
+
 struct SchemaBuilder<'a> {
     context: &'a ParsingContext,
+    options: SchemaOptions,
     definitions: IndexMap<String, Value>,
 }
 
 impl<'a> SchemaBuilder<'a> {
-    fn new(context: &'a ParsingContext) -> Self {
+    fn new(context: &'a ParsingContext, options: SchemaOptions) -> Self {
         Self {
             context,
+            options,
             definitions: IndexMap::new(),
         }
     }
 
-    fn build(&mut self, root_type: &str) -> Result<Value> {
-        // Build all referenced types
+    fn is_vscode(&self) -> bool {
+        self.options.flavor == SchemaFlavor::VsCode
+    }
+
+    /// Build the definitions of a root type and everything it references
+    fn build_root_definitions(&mut self, root_type: &str) -> Result<()> {
         if let Some(root_class) = self.context.classes.get(root_type) {
             self.build_class_definition(root_class);
+            Ok(())
         } else {
-            anyhow::bail!("Root type '{}' not found", root_type);
+            Err(Error::UnknownType {
+                name: root_type.to_string(),
+                is_enum: self.context.enums.contains_key(root_type),
+            })
         }
+    }
+
+    fn build(&mut self, root_type: &str) -> Result<Value> {
+        // Build all referenced types
+        self.build_root_definitions(root_type)?;
+
+        let dialect = self.options.dialect;
 
         // Create root schema
-        Ok(json!({
-            "$schema": "https://json-schema.org/draft/2020-12/schema",
+        let mut schema = json!({
+            "$schema": dialect.schema_uri(),
             "$id": format!("{}.json", root_type),
             "title": format!("{} schema", root_type),
             "type": "object",
             "allOf": [
-                { "$ref": format!("#/$defs/{}", root_type) }
+                { "$ref": dialect.ref_to(root_type) }
             ],
-            "$defs": self.definitions
-        }))
+            (dialect.definitions_key()): self.definitions
+        });
+
+        if dialect == SchemaDialect::Draft07 {
+            isolate_refs(&mut schema);
+        }
+
+        Ok(schema)
+    }
+
+    fn ref_to(&self, name: &str) -> Value {
+        json!({ "$ref": self.options.dialect.ref_to(name) })
     }
 
     fn build_class_definition(&mut self, class: &ClassDef) {
         let name = &class.name;
 
-        // Skip if already processed
+        // Skip if already processed or in progress
         if self.definitions.contains_key(name) {
             return;
         }
 
+        // Register a placeholder before recursing, so that a class reachable from
+        // its own properties or subclasses resolves to a `$ref` instead of recursing
+        self.definitions.insert(name.clone(), Value::Null);
+
         // Handle sealed classes (variants)
         if class.is_sealed && !class.subclasses.is_empty() {
-            let variants: Vec<Value> = class
-                .subclasses
-                .iter()
-                .filter_map(|subclass_name| {
-                    self.context.classes.get(subclass_name).map(|subclass| {
-                        self.build_class_definition(subclass);
-                        json!({ "$ref": format!("#/$defs/{}", subclass_name) })
-                    })
-                })
-                .collect();
-
-            self.definitions.insert(
-                name.clone(),
-                json!({
-                    "anyOf": variants
-                }),
-            );
+            let definition = self.build_sealed_definition(class);
+            self.definitions.insert(name.clone(), definition);
             return;
         }
 
-        // Collect properties from this class and its parent
-        let mut all_properties = class.properties.clone();
-        
-        // If this class has a parent, merge parent's properties
-        if let Some(parent_name) = &class.parent {
-            if let Some(parent_class) = self.context.classes.get(parent_name) {
-                // Add parent properties that don't conflict
-                for parent_prop in &parent_class.properties {
-                    if !all_properties.iter().any(|p| p.name == parent_prop.name) {
-                        all_properties.push(parent_prop.clone());
-                    }
-                }
-            }
-        }
+        let all_properties = self.context.all_properties(class);
 
         // Build properties
         let mut properties = Map::new();
@@ -96,11 +262,12 @@ impl<'a> SchemaBuilder<'a> {
         let mut required = Vec::new();
 
         for prop in &all_properties {
-            if prop.is_hidden() {
+            if prop.is_hidden() && self.options.drop_hidden {
                 continue;
             }
 
-            let prop_schema = self.build_property_schema(prop);
+            let owner = self.context.declaring_class(class, &prop.name);
+            let prop_schema = self.build_property_schema(&owner.name, prop);
 
             // Handle modifier-aware properties (test-* prefix support)
             if prop.is_modifier_aware() {
@@ -115,11 +282,21 @@ impl<'a> SchemaBuilder<'a> {
             properties.insert(prop.name.clone(), prop_schema);
 
             // Mark as required if not nullable and no default
-            if !prop.is_nullable && prop.default_value.is_none() {
+            if !prop.is_nullable && !prop.has_default() && !prop.is_hidden() {
                 required.push(prop.name.clone());
             }
         }
 
+        // Narrow `platforms` to the Platform enum, per product type
+        let mut conditions = Vec::new();
+        if let Some((platforms_schema, product_conditions)) =
+            self.build_product_platforms(&all_properties)
+        {
+            properties.insert("platforms".to_string(), platforms_schema);
+            required.retain(|name| name != "platforms");
+            conditions = product_conditions;
+        }
+
         let mut schema_obj = json!({
             "type": "object",
             "additionalProperties": false,
@@ -139,16 +316,267 @@ impl<'a> SchemaBuilder<'a> {
                     Value::Array(required.into_iter().map(Value::String).collect()),
                 );
             }
-            if let Some(doc) = &class.doc {
-                obj.insert("title".to_string(), Value::String(doc.clone()));
+            if !conditions.is_empty() {
+                obj.insert("allOf".to_string(), Value::Array(conditions));
             }
         }
 
+        // A @Shorthand property lets the whole object be written as that property's value
+        if let Some(shorthand) = all_properties.iter().find(|p| p.is_shorthand() && !p.is_hidden()) {
+            let shorthand_schema = if shorthand.type_name == "Boolean" && !shorthand.is_list {
+                json!({ "const": "enabled" })
+            } else {
+                let owner = self.context.declaring_class(class, &shorthand.name);
+                self.build_type_schema(&shorthand.type_name, &owner.name, shorthand)
+            };
+            schema_obj = json!({
+                "anyOf": [shorthand_schema, schema_obj]
+            });
+        }
+
+        if let Some(doc) = &class.doc
+            && let Some(obj) = schema_obj.as_object_mut()
+        {
+            obj.insert("title".to_string(), Value::String(doc.clone()));
+        }
+
         self.definitions.insert(name.clone(), schema_obj);
     }
 
-    fn build_property_schema(&mut self, prop: &Property) -> Value {
-        let base_schema = self.build_type_schema(&prop.type_name, prop);
+    /// Collect the concrete (non-sealed) classes of a sealed hierarchy
+    fn concrete_variants(&self, class: &ClassDef, variants: &mut Vec<String>) {
+        for subclass_name in &class.subclasses {
+            let Some(subclass) = self.context.classes.get(subclass_name) else {
+                continue;
+            };
+            if subclass.is_sealed && !subclass.subclasses.is_empty() {
+                if !variants.contains(subclass_name) {
+                    self.concrete_variants(subclass, variants);
+                }
+            } else if !variants.contains(subclass_name) {
+                variants.push(subclass_name.clone());
+            }
+        }
+    }
+
+    /// Build a sealed class as a union of its concrete subclasses.
+    ///
+    /// When every variant can be told apart by a discriminator, objects must carry
+    /// exactly one discriminator (`oneOf`) and are validated against the variant it
+    /// selects (`if`/`then`), so errors and completion refer to that variant alone.
+    /// Non-object values (shorthand forms), and objects omitting a defaulted constant
+    /// or every optional key discriminator, match any variant. Without discriminators the union stays a
+    /// plain `anyOf`.
+    fn build_sealed_definition(&mut self, class: &ClassDef) -> Value {
+        let mut variant_names = Vec::new();
+        self.concrete_variants(class, &mut variant_names);
+
+        let variants: Vec<&ClassDef> = variant_names
+            .iter()
+            .filter_map(|name| self.context.classes.get(name))
+            .collect();
+        for variant in &variants {
+            self.build_class_definition(variant);
+        }
+        let refs: Vec<Value> = variant_names.iter().map(|name| self.ref_to(name)).collect();
+
+        let Some(discriminator) = self.find_discriminator(&variants) else {
+            return json!({ "anyOf": refs });
+        };
+
+        let conditions: Vec<Value> = match &discriminator {
+            Discriminator::Constant { key, values } => values
+                .iter()
+                .map(|value| json!({ "properties": { (key.clone()): { "const": value } }, "required": [key] }))
+                .collect(),
+            Discriminator::Keys { keys, .. } => keys.iter().map(|key| json!({ "required": [key] })).collect(),
+        };
+        let mut selectors = Vec::new();
+        let mut branches = Vec::new();
+        for ((variant, condition), reference) in variants.iter().zip(conditions).zip(&refs) {
+            let mut selector = condition.clone();
+            if let Some(obj) = selector.as_object_mut() {
+                obj.insert("title".to_string(), Value::String(variant.name.clone()));
+            }
+            selectors.push(selector);
+            branches.push(json!({ "if": condition, "then": reference }));
+        }
+        let mut selected = json!({
+            "oneOf": selectors,
+            "allOf": branches
+        });
+        // A constant has a default and an optional key may be left out, so objects
+        // without any discriminator may be any variant
+        let carries_discriminator = match &discriminator {
+            Discriminator::Constant { key, .. } => Some(json!({ "required": [key] })),
+            Discriminator::Keys { keys, optional: true } => {
+                Some(json!({ "anyOf": keys.iter().map(|key| json!({ "required": [key] })).collect::<Vec<_>>() }))
+            }
+            Discriminator::Keys { optional: false, .. } => None,
+        };
+        if let Some(condition) = carries_discriminator {
+            selected = json!({
+                "if": condition,
+                "then": selected,
+                "else": { "anyOf": refs }
+            });
+        }
+
+        let mut definition = json!({
+            "if": { "type": "object" },
+            "then": selected,
+            "else": { "anyOf": refs }
+        });
+        if let Some(doc) = &class.doc
+            && let Some(obj) = definition.as_object_mut()
+        {
+            obj.insert("title".to_string(), Value::String(doc.clone()));
+        }
+        definition
+    }
+
+    /// Find what selects each variant of a sealed hierarchy.
+    ///
+    /// Prefers a property shared by all variants with a distinct string or enum
+    /// literal default in each; otherwise each variant needs a key no sibling
+    /// declares, required keys first. Returns `None` unless every variant can be
+    /// discriminated.
+    fn find_discriminator(&self, variants: &[&ClassDef]) -> Option<Discriminator> {
+        if variants.len() < 2 {
+            return None;
+        }
+        let properties: Vec<Vec<Property>> = variants
+            .iter()
+            .map(|variant| {
+                self.context.all_properties(variant)
+                    .into_iter()
+                    .filter(|p| !p.is_hidden())
+                    .collect()
+            })
+            .collect();
+
+        // Constant-valued discriminator
+        for candidate in &properties[0] {
+            let constants: Vec<String> = properties
+                .iter()
+                .filter_map(|props| {
+                    let prop = props.iter().find(|p| p.name == candidate.name)?;
+                    match self.default_json(prop)? {
+                        Value::String(value) => Some(value),
+                        _ => None,
+                    }
+                })
+                .collect();
+            let mut distinct = constants.clone();
+            distinct.sort();
+            distinct.dedup();
+            if constants.len() == variants.len() && distinct.len() == constants.len() {
+                return Some(Discriminator::Constant { key: candidate.name.clone(), values: constants });
+            }
+        }
+
+        // Unique key per variant
+        properties
+            .iter()
+            .enumerate()
+            .map(|(i, props)| {
+                let unique: Vec<&Property> = props
+                    .iter()
+                    .filter(|p| {
+                        properties
+                            .iter()
+                            .enumerate()
+                            .all(|(j, other)| i == j || !other.iter().any(|o| o.name == p.name))
+                    })
+                    .collect();
+                match unique.iter().find(|p| !p.is_nullable && !p.has_default()) {
+                    Some(key) => Some((key.name.clone(), false)),
+                    None => unique.first().map(|key| (key.name.clone(), true)),
+                }
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|keys| Discriminator::Keys {
+                optional: keys.iter().any(|(_, optional)| *optional),
+                keys: keys.into_iter().map(|(key, _)| key).collect(),
+            })
+    }
+
+    /// Build the `platforms` schema of a product-like class.
+    ///
+    /// Applies when the class has a `platforms` property next to an enum property
+    /// whose entries declare `supportedPlatforms` (Amper's `ProductType`). Returns
+    /// the platforms schema and `if`/`then` conditions restricting the platforms
+    /// allowed for each product type.
+    fn build_product_platforms(&mut self, properties: &[Property]) -> Option<(Value, Vec<Value>)> {
+        if !properties.iter().any(|p| p.name == "platforms") {
+            return None;
+        }
+        let platform_enum = self.context.enums.get("Platform")?;
+        let (type_prop, product_enum) = properties.iter().find_map(|p| {
+            let enum_def = self.context.enums.get(self.options.type_mapping.unwrap_all(&p.type_name))?;
+            enum_def
+                .entries
+                .iter()
+                .any(|e| e.named_argument("supportedPlatforms").is_some())
+                .then_some((p, enum_def))
+        })?;
+
+        // Leaf platforms are the ones a product can actually be built for
+        let has_leaves = platform_enum.entries.iter().any(|e| e.is_leaf());
+        let all_platforms: Vec<String> = platform_enum
+            .entries
+            .iter()
+            .filter(|e| !e.is_outdated && (!has_leaves || e.is_leaf()))
+            .map(|e| e.schema_value.clone())
+            .collect();
+
+        let mut conditions = Vec::new();
+        for entry in product_enum.entries.iter().filter(|e| !e.is_outdated) {
+            let mut then = Map::new();
+            if let Some(supported) = entry.supported_platforms() {
+                let values: Vec<String> = supported
+                    .iter()
+                    .filter_map(|name| platform_enum.entry(name))
+                    .map(|e| e.schema_value.clone())
+                    .collect();
+                then.insert(
+                    "properties".to_string(),
+                    json!({ "platforms": { "items": { "enum": values } } }),
+                );
+            }
+            // Without default platforms the user has to list them explicitly
+            if entry.has_no_default_platforms() {
+                then.insert("required".to_string(), json!(["platforms"]));
+            }
+            if then.is_empty() {
+                continue;
+            }
+            conditions.push(json!({
+                "if": {
+                    "properties": { (type_prop.name.clone()): { "const": entry.schema_value } },
+                    "required": [type_prop.name]
+                },
+                "then": then
+            }));
+        }
+
+        let mut platforms_schema = json!({
+            "type": "array",
+            "items": { "enum": all_platforms },
+            "uniqueItems": true
+        });
+        if let Some(prop) = properties.iter().find(|p| p.name == "platforms")
+            && let Some(doc) = &prop.doc
+        {
+            self.apply_docs(&mut platforms_schema, doc);
+        }
+
+        Some((platforms_schema, conditions))
+    }
+
+    /// Build the schema of a property declared by the class `owner`
+    fn build_property_schema(&mut self, owner: &str, prop: &Property) -> Value {
+        let base_schema = self.build_type_schema(&prop.type_name, owner, prop);
 
         let mut schema = if prop.is_list {
             json!({
@@ -171,19 +599,53 @@ impl<'a> SchemaBuilder<'a> {
             base_schema
         };
 
+        // Nullable properties also accept an explicit YAML null (`key: ~` or `key:`)
+        if prop.is_nullable {
+            schema = allow_null(schema);
+        } else {
+            self.reject_null(&mut schema);
+        }
+
         // Add documentation
         if let Some(doc) = &prop.doc {
-            if let Some(obj) = schema.as_object_mut() {
-                obj.insert("description".to_string(), Value::String(doc.clone()));
-                obj.insert("title".to_string(), Value::String(short_doc(doc)));
-            }
+            self.apply_docs(&mut schema, doc);
         }
 
-        // Add x-intellij-metadata for platform/product specificity
+        if let Some(default) = self.default_json(prop)
+            && let Some(obj) = schema.as_object_mut()
+        {
+            obj.insert("default".to_string(), default);
+        }
+
+        // Add platform/product specificity
         let platforms = prop.get_platform_specific();
         let product_types = prop.get_product_type_specific();
-        
-        if !platforms.is_empty() || !product_types.is_empty() {
+
+        if self.is_vscode() {
+            let mut notes = Vec::new();
+            if !platforms.is_empty() {
+                let platforms = self.resolve_enum_values("Platform", &platforms);
+                notes.push(format!("Only applies to platforms: {}", code_list(&platforms)));
+            }
+            if !product_types.is_empty() {
+                let product_types = self.resolve_enum_values("ProductType", &product_types);
+                notes.push(format!("Only applies to product types: {}", code_list(&product_types)));
+            }
+            if !notes.is_empty()
+                && let Some(obj) = schema.as_object_mut()
+            {
+                let markdown = obj
+                    .get("markdownDescription")
+                    .and_then(Value::as_str)
+                    .map(|doc| format!("{}\n\n", doc))
+                    .unwrap_or_default();
+                let notes = notes.iter().map(|n| format!("_{}_", n)).collect::<Vec<_>>();
+                obj.insert(
+                    "markdownDescription".to_string(),
+                    Value::String(format!("{}{}", markdown, notes.join("\n\n"))),
+                );
+            }
+        } else if !platforms.is_empty() || !product_types.is_empty() {
             let mut metadata = Map::new();
             if !platforms.is_empty() {
                 metadata.insert(
@@ -202,48 +664,200 @@ impl<'a> SchemaBuilder<'a> {
             }
         }
 
+        // Hidden properties stay valid but are never offered in completion
+        if prop.is_hidden()
+            && let Some(obj) = schema.as_object_mut()
+        {
+            obj.insert("doNotSuggest".to_string(), Value::Bool(true));
+        }
+
+        if let Some(message) = prop.deprecation_message()
+            && let Some(obj) = schema.as_object_mut()
+        {
+            if self.is_vscode() {
+                let message = if message.is_empty() {
+                    format!("'{}' is deprecated", prop.name)
+                } else {
+                    message
+                };
+                obj.insert("deprecationMessage".to_string(), Value::String(message));
+                obj.insert("doNotSuggest".to_string(), Value::Bool(true));
+            } else {
+                obj.insert("deprecated".to_string(), Value::Bool(true));
+            }
+        }
+
+        // Offer a snippet that expands object-valued settings
+        if self.is_vscode()
+            && !prop.is_list
+            && !prop.is_map
+            && let Some(class) = self.context.classes.get(&prop.type_name)
+            && let Some(obj) = schema.as_object_mut()
+        {
+            let mut snippet = Map::new();
+            snippet.insert("label".to_string(), Value::String(prop.name.clone()));
+            if let Some(doc) = &prop.doc {
+                snippet.insert("description".to_string(), Value::String(short_doc(doc)));
+            }
+            snippet.insert("body".to_string(), self.snippet_body(class));
+            obj.insert("defaultSnippets".to_string(), json!([snippet]));
+        }
+
         schema
     }
 
-    fn build_type_schema(&mut self, type_name: &str, _prop: &Property) -> Value {
-        // Check if it's an enum
-        if let Some(enum_def) = self.context.enums.get(type_name) {
-            return self.build_enum_schema(enum_def);
+    /// Require a non-null value, through the shared `NonNull` definition
+    fn reject_null(&mut self, schema: &mut Value) {
+        if !self.definitions.contains_key(NON_NULL_DEFINITION) {
+            let mut non_null = json!({
+                "type": ["string", "number", "boolean", "object", "array"]
+            });
+            if self.is_vscode()
+                && let Some(obj) = non_null.as_object_mut()
+            {
+                obj.insert(
+                    "errorMessage".to_string(),
+                    Value::String(
+                        "A value is required here: an empty value or `~` (null) is not allowed."
+                            .to_string(),
+                    ),
+                );
+            }
+            self.definitions.insert(NON_NULL_DEFINITION.to_string(), non_null);
         }
 
-        // Check if it's a class
-        if let Some(class) = self.context.classes.get(type_name) {
-            self.build_class_definition(class);
-            return json!({ "$ref": format!("#/$defs/{}", type_name) });
+        let reference = self.ref_to(NON_NULL_DEFINITION);
+        if let Some(obj) = schema.as_object_mut() {
+            match obj.get_mut("allOf").and_then(Value::as_array_mut) {
+                Some(all_of) => all_of.push(reference),
+                None => {
+                    obj.insert("allOf".to_string(), json!([reference]));
+                }
+            }
         }
+    }
 
-        // Check for TraceableEnum wrapper
-        if type_name.starts_with("TraceableEnum<") {
-            if let Some(inner) = type_name.strip_prefix("TraceableEnum<").and_then(|s| s.strip_suffix('>')) {
-                if let Some(enum_def) = self.context.enums.get(inner) {
-                    return self.build_enum_schema(enum_def);
+    /// The JSON form of a literal or enum entry default (`false`, `"git"`, `DependencyScope.ALL`).
+    /// Computed defaults and instantiated objects have none.
+    fn default_json(&self, prop: &Property) -> Option<Value> {
+        let value = prop.default_value.as_deref()?.trim();
+        if let Ok(flag) = value.parse::<bool>() {
+            return Some(Value::Bool(flag));
+        }
+        if let Ok(number) = value.parse::<i64>() {
+            return Some(Value::from(number));
+        }
+        if let Ok(number) = value.parse::<f64>() {
+            return serde_json::Number::from_f64(number).map(Value::Number);
+        }
+        if let Some(literal) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            // String templates and concatenations are computed at runtime
+            return (!literal.contains(['$', '"'])).then(|| Value::String(literal.to_string()));
+        }
+        let (enum_name, entry_name) = value.rsplit_once('.')?;
+        let enum_def = self.context.enums.get(enum_name)?;
+        enum_def.entry(entry_name).map(|e| Value::String(e.schema_value.clone()))
+    }
+
+    /// Map annotation arguments like `Platform.ANDROID` to their schema values
+    fn resolve_enum_values(&self, enum_name: &str, values: &[String]) -> Vec<String> {
+        values
+            .iter()
+            .map(|value| {
+                let entry_name = value.rsplit('.').next().unwrap_or(value);
+                self.context
+                    .enums
+                    .get(enum_name)
+                    .and_then(|e| e.entry(entry_name))
+                    .map(|e| e.schema_value.clone())
+                    .unwrap_or_else(|| value.clone())
+            })
+            .collect()
+    }
+
+    /// Attach documentation to a schema, as Markdown for VS Code
+    fn apply_docs(&self, schema: &mut Value, doc: &str) {
+        let Some(obj) = schema.as_object_mut() else {
+            return;
+        };
+        if self.is_vscode() {
+            obj.insert("description".to_string(), Value::String(plain_text(doc)));
+            obj.insert("markdownDescription".to_string(), Value::String(doc.to_string()));
+        } else {
+            obj.insert("description".to_string(), Value::String(doc.to_string()));
+        }
+        obj.insert("title".to_string(), Value::String(short_doc(doc)));
+    }
+
+    /// Build a `defaultSnippets` body listing the properties a class requires
+    fn snippet_body(&self, class: &ClassDef) -> Value {
+        let mut body = Map::new();
+        let mut placeholder = 0;
+        for prop in self.context.all_properties(class).iter().filter(|p| !p.is_hidden()) {
+            if prop.is_shorthand() && prop.type_name == "Boolean" {
+                body.insert(prop.name.clone(), Value::Bool(true));
+            } else if !prop.is_nullable && !prop.has_default() && !prop.is_shorthand() {
+                if self.context.classes.contains_key(&prop.type_name) {
+                    body.insert(prop.name.clone(), json!({}));
+                } else if prop.is_list {
+                    placeholder += 1;
+                    body.insert(prop.name.clone(), json!([format!("${}", placeholder)]));
+                } else {
+                    placeholder += 1;
+                    body.insert(prop.name.clone(), Value::String(format!("${}", placeholder)));
                 }
             }
         }
+        Value::Object(body)
+    }
 
-        // Primitive types
-        match type_name {
-            "String" | "TraceableString" => json!({ "type": "string" }),
-            "Int" | "Integer" => json!({ "type": "integer" }),
-            "Boolean" => json!({ "type": "boolean" }),
-            "Path" | "TraceablePath" => json!({ "type": "string" }),
-            "Double" | "Float" => json!({ "type": "number" }),
-            _ => {
-                // Unknown type - treat as string with a warning in the schema
-                json!({
-                    "type": "string",
-                    "description": format!("Type: {}", type_name)
-                })
+    fn build_type_schema(&mut self, type_name: &str, owner: &str, prop: &Property) -> Value {
+        let mut current = type_name;
+        loop {
+            // Check if it's an enum
+            if let Some(enum_def) = self.context.enums.get(current) {
+                return self.build_enum_schema(enum_def);
+            }
+
+            // Check if it's a class
+            if let Some(class) = self.context.classes.get(current) {
+                self.build_class_definition(class);
+                return self.ref_to(current);
+            }
+
+            // Primitive types from the type mapping
+            if let Some(rule) = self.options.type_mapping.rule(current) {
+                let mut schema = rule.to_schema();
+                self.options.type_mapping.constrain(owner, &prop.name, &mut schema);
+                return schema;
+            }
+
+            // Wrapper types (`TraceableEnum<T>`, `TraceableString`) are retried unwrapped
+            match self.options.type_mapping.unwrap(current) {
+                Some(inner) => current = inner,
+                None => break,
             }
         }
+
+        // Unknown type - treat as string with a warning in the schema
+        let mut schema = json!({
+            "type": "string",
+            "description": format!("Type: {}", type_name)
+        });
+        self.options.type_mapping.constrain(owner, &prop.name, &mut schema);
+        schema
     }
 
-    fn build_enum_schema(&self, enum_def: &EnumDef) -> Value {
+    /// Reference an enum, emitting its definition on first use
+    fn build_enum_schema(&mut self, enum_def: &EnumDef) -> Value {
+        if !self.definitions.contains_key(&enum_def.name) {
+            let definition = self.build_enum_definition(enum_def);
+            self.definitions.insert(enum_def.name.clone(), definition);
+        }
+        self.ref_to(&enum_def.name)
+    }
+
+    fn build_enum_definition(&self, enum_def: &EnumDef) -> Value {
         let values: Vec<String> = enum_def
             .entries
             .iter()
@@ -255,29 +869,42 @@ impl<'a> SchemaBuilder<'a> {
             "enum": values
         });
 
-        // Add enum metadata
-        let metadata: Map<String, Value> = enum_def
-            .entries
-            .iter()
-            .filter(|e| !e.is_outdated && e.doc.is_some())
-            .map(|e| {
-                (
-                    e.schema_value.clone(),
-                    Value::String(e.doc.as_ref().unwrap().clone()),
-                )
-            })
-            .collect();
+        let entries = enum_def.entries.iter().filter(|e| !e.is_outdated);
+        if self.is_vscode() {
+            // One description per enum value, in the same order
+            let descriptions: Vec<Value> = entries
+                .clone()
+                .map(|e| Value::String(e.doc.clone().unwrap_or_default()))
+                .collect();
+            if enum_def.entries.iter().any(|e| !e.is_outdated && e.doc.is_some())
+                && let Some(obj) = schema.as_object_mut()
+            {
+                obj.insert("markdownEnumDescriptions".to_string(), Value::Array(descriptions));
+            }
+        } else {
+            // Add enum metadata
+            let metadata: Map<String, Value> = entries
+                .filter_map(|e| Some((e.schema_value.clone(), Value::String(e.doc.clone()?))))
+                .collect();
 
-        if !metadata.is_empty() {
-            if let Some(obj) = schema.as_object_mut() {
+            if !metadata.is_empty()
+                && let Some(obj) = schema.as_object_mut()
+            {
                 obj.insert("x-intellij-enum-metadata".to_string(), Value::Object(metadata));
             }
         }
 
-        if enum_def.is_order_sensitive {
-            if let Some(obj) = schema.as_object_mut() {
-                obj.insert("x-intellij-enum-order-sensitive".to_string(), Value::Bool(true));
-            }
+        if enum_def.is_order_sensitive
+            && let Some(obj) = schema.as_object_mut()
+        {
+            obj.insert("x-intellij-enum-order-sensitive".to_string(), Value::Bool(true));
+        }
+
+        if let Some(doc) = &enum_def.doc {
+            self.apply_docs(&mut schema, doc);
+        }
+        if let Some(obj) = schema.as_object_mut() {
+            obj.insert("title".to_string(), Value::String(enum_def.name.clone()));
         }
 
         schema
@@ -286,9 +913,70 @@ impl<'a> SchemaBuilder<'a> {
 
 /// Extract short form of documentation
 fn short_doc(doc: &str) -> String {
-    doc.replace("[Read more]", "")
-        .replace(|c: char| c == '(' || c == ')', "")
+    plain_text(doc)
+        .replace(['(', ')'], "")
         .trim()
         .trim_end_matches('.')
         .to_string()
 }
+
+/// Strip Markdown links and code spans, keeping their text.
+/// "Read more" links carry no text of their own and are dropped.
+fn plain_text(doc: &str) -> String {
+    static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap());
+    LINK_REGEX
+        .replace_all(doc, |cap: &regex::Captures| match &cap[1] {
+            "Read more" => String::new(),
+            text => text.to_string(),
+        })
+        .replace('`', "")
+        .trim()
+        .to_string()
+}
+
+/// Format values as a comma-separated list of code spans
+fn code_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| format!("`{}`", v))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Extend a schema to also accept `null`
+fn allow_null(mut schema: Value) -> Value {
+    if let Some(obj) = schema.as_object_mut()
+        && !obj.contains_key("enum")
+        && !obj.contains_key("const")
+        && let Some(Value::String(type_name)) = obj.get("type").cloned()
+    {
+        obj.insert("type".to_string(), json!([type_name, "null"]));
+        return schema;
+    }
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+/// Move `$ref` into `allOf` wherever it has sibling keywords.
+///
+/// Draft-07 ignores everything next to `$ref`, which would drop descriptions,
+/// snippets and other annotations attached to references.
+fn isolate_refs(value: &mut Value) {
+    match value {
+        Value::Object(obj) => {
+            if obj.len() > 1
+                && let Some(reference) = obj.remove("$ref")
+            {
+                let reference = json!({ "$ref": reference });
+                match obj.get_mut("allOf").and_then(Value::as_array_mut) {
+                    Some(all_of) => all_of.insert(0, reference),
+                    None => {
+                        obj.insert("allOf".to_string(), json!([reference]));
+                    }
+                }
+            }
+            obj.values_mut().for_each(isolate_refs);
+        }
+        Value::Array(items) => items.iter_mut().for_each(isolate_refs),
+        _ => {}
+    }
+}
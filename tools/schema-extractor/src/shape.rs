@@ -0,0 +1,41 @@
+//! Expected shape of YAML values, from the Kotlin properties declaring them
+
+use crate::mapping::TypeMapping;
+use crate::types::{ClassDef, EnumDef, ParsingContext, Property};
+
+/// What a value is expected to be, from the property declaring it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Shape<'c> {
+    /// Class of the value, or of the items or map values of a collection
+    pub class: Option<&'c ClassDef>,
+    /// Enum of the value, or of the items or map values of a collection
+    pub enum_def: Option<&'c EnumDef>,
+    pub list: bool,
+    pub map: bool,
+}
+
+impl<'c> Shape<'c> {
+    /// The shape of an object of class `name`
+    pub fn class(context: &'c ParsingContext, name: &str) -> Self {
+        Shape { class: context.classes.get(name), ..Shape::default() }
+    }
+
+    /// The shape of the values of `property`
+    pub fn of(property: &Property, context: &'c ParsingContext, mapping: &TypeMapping) -> Self {
+        let type_name = mapping.unwrap_all(&property.type_name);
+        // `platforms` is typed by the schema as a list of `Platform` values
+        let platforms = property.name == "platforms" && context.enums.contains_key("Platform");
+        let enum_name = if platforms { "Platform" } else { type_name };
+        Shape {
+            class: context.classes.get(type_name),
+            enum_def: context.enums.get(enum_name),
+            list: property.is_list || platforms,
+            map: property.is_map,
+        }
+    }
+
+    /// The shape of the items or map values of a collection
+    pub fn element(self) -> Self {
+        Shape { list: false, map: false, ..self }
+    }
+}
@@ -0,0 +1,80 @@
+//! "Did you mean" suggestions for unknown property keys and enum values
+//!
+//! A misspelled name is matched to the closest allowed one by edit distance,
+//! ignoring case. Names that are far from the right one but commonly used for
+//! it (`deps`, `jvm/application`) come from the alias table in `aliases.toml`.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Built-in alias table
+const ALIASES: &str = include_str!("../aliases.toml");
+
+static ALIAS_TABLE: LazyLock<Aliases> =
+    LazyLock::new(|| toml::from_str(ALIASES).expect("built-in alias table is invalid"));
+
+/// Known misnomers mapped to what Amper calls them
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Aliases {
+    #[serde(default)]
+    properties: HashMap<String, String>,
+    #[serde(default)]
+    values: HashMap<String, String>,
+}
+
+/// What kind of name is being corrected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameKind {
+    Property,
+    Value,
+}
+
+/// The allowed name `word` was most likely meant to be, if any
+pub fn closest<'c>(kind: NameKind, word: &str, candidates: &[&'c str]) -> Option<&'c str> {
+    let aliases = match kind {
+        NameKind::Property => &ALIAS_TABLE.properties,
+        NameKind::Value => &ALIAS_TABLE.values,
+    };
+    let alias_target = aliases
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(word))
+        .and_then(|(_, target)| candidates.iter().find(|c| **c == target.as_str()));
+    if let Some(target) = alias_target {
+        return Some(target);
+    }
+
+    // Allow one edit per three characters, so short names need a case-only difference
+    let word = word.to_lowercase();
+    let limit = word.chars().count() / 3;
+    candidates
+        .iter()
+        .map(|c| (edit_distance(&word, &c.to_lowercase()), *c))
+        .filter(|(distance, candidate)| *distance <= limit && *distance < candidate.chars().count())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and transpositions of adjacent characters each count as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
@@ -1,18 +1,89 @@
 //! Type definitions for schema extraction
 
 use indexmap::IndexMap;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 /// Parsing context that holds all discovered types
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ParsingContext {
     pub classes: IndexMap<String, ClassDef>,
     pub enums: IndexMap<String, EnumDef>,
+    #[serde(skip)]
     pub verbose: bool,
 }
 
+impl ParsingContext {
+    /// Collect the properties of a class and all of its ancestors.
+    /// Properties declared closer to the class win over inherited ones.
+    pub fn all_properties(&self, class: &ClassDef) -> Vec<Property> {
+        let mut all_properties = class.properties.clone();
+        let mut visited = vec![class.name.clone()];
+        let mut parent = class.parent.as_ref();
+
+        while let Some(parent_name) = parent {
+            let Some(parent_class) = self.classes.get(parent_name) else {
+                break;
+            };
+            if visited.contains(&parent_class.name) {
+                break;
+            }
+            visited.push(parent_class.name.clone());
+
+            // Add parent properties that don't conflict
+            for parent_prop in &parent_class.properties {
+                if !all_properties.iter().any(|p| p.name == parent_prop.name) {
+                    all_properties.push(parent_prop.clone());
+                }
+            }
+            parent = parent_class.parent.as_ref();
+        }
+
+        all_properties
+    }
+
+    /// The class or ancestor of `class` that declares `property_name`
+    pub fn declaring_class<'c>(&'c self, class: &'c ClassDef, property_name: &str) -> &'c ClassDef {
+        let mut current = class;
+        let mut visited = vec![class.name.as_str()];
+        while !current.properties.iter().any(|p| p.name == property_name) {
+            let Some(parent) = current.parent.as_ref().and_then(|name| self.classes.get(name)) else {
+                return class;
+            };
+            if visited.contains(&parent.name.as_str()) {
+                return class;
+            }
+            visited.push(&parent.name);
+            current = parent;
+        }
+        current
+    }
+
+    /// For a sealed class, the first concrete variant declaring every one of
+    /// `keys`, or the class itself when none does
+    pub fn variant<'c>(&'c self, class: &'c ClassDef, keys: &[&str]) -> &'c ClassDef {
+        if !class.is_sealed {
+            return class;
+        }
+        let mut pending: Vec<&str> = class.subclasses.iter().map(String::as_str).collect();
+        while !pending.is_empty() {
+            let name = pending.remove(0);
+            let Some(subclass) = self.classes.get(name) else { continue };
+            if subclass.is_sealed {
+                pending.extend(subclass.subclasses.iter().map(String::as_str));
+                continue;
+            }
+            let properties = self.all_properties(subclass);
+            if keys.iter().all(|key| properties.iter().any(|p| p.name == *key)) {
+                return subclass;
+            }
+        }
+        class
+    }
+}
+
 /// A Kotlin class definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClassDef {
     pub name: String,
     pub doc: Option<String>,
@@ -23,7 +94,7 @@ pub struct ClassDef {
 }
 
 /// A property in a Kotlin class
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Property {
     pub name: String,
     pub type_name: String,
@@ -32,7 +103,9 @@ pub struct Property {
     pub is_list: bool,
     pub is_map: bool,
     pub default_value: Option<String>,
-    pub annotations: HashSet<String>,
+    /// Declared `by nested()`: always instantiated, with no default to show
+    pub is_nested: bool,
+    pub annotations: BTreeSet<String>,
 }
 
 impl Property {
@@ -46,12 +119,38 @@ impl Property {
         self.has_annotation("HiddenFromCompletion")
     }
 
+    /// Check if this property can be omitted because it has a value without one
+    pub fn has_default(&self) -> bool {
+        self.default_value.is_some() || self.is_nested
+    }
+
     /// Check if this property is a shorthand
-    #[allow(dead_code)]
     pub fn is_shorthand(&self) -> bool {
         self.has_annotation("Shorthand")
     }
 
+    /// Get the message of a `@Deprecated("...")` annotation, if present
+    pub fn deprecation_message(&self) -> Option<String> {
+        let annotation = self.deprecation()?;
+        let message = annotation
+            .split_once('"')
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(message, _)| message.to_string())
+            .unwrap_or_default();
+        Some(message)
+    }
+
+    /// Get the property named by `@Deprecated("...", ReplaceWith("name"))`
+    pub fn replacement(&self) -> Option<String> {
+        replace_with(self.deprecation()?)
+    }
+
+    fn deprecation(&self) -> Option<&String> {
+        self.annotations
+            .iter()
+            .find(|a| *a == "Deprecated" || a.starts_with("Deprecated("))
+    }
+
     /// Check if this property is modifier-aware
     pub fn is_modifier_aware(&self) -> bool {
         self.has_annotation("ModifierAware")
@@ -77,37 +176,122 @@ impl Property {
 
     /// Extract values from annotation string like "Annotation(VAL1, VAL2)"
     fn extract_annotation_values(annotation: &str) -> Vec<String> {
-        if let Some(start) = annotation.find('(') {
-            if let Some(end) = annotation.rfind(')') {
-                let content = &annotation[start + 1..end];
-                return content
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect();
-            }
+        if let Some(start) = annotation.find('(')
+            && let Some(end) = annotation.rfind(')')
+        {
+            let content = &annotation[start + 1..end];
+            return content
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect();
         }
         vec![]
     }
 }
 
 /// A Kotlin enum definition
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnumDef {
-    #[allow(dead_code)]
     pub name: String,
-    #[allow(dead_code)]
     pub doc: Option<String>,
     pub entries: Vec<EnumEntry>,
     pub is_order_sensitive: bool,
+    /// `@EnumOrderSensitive(reverse = true)`: lists go from the last entry to the first
+    pub is_order_reversed: bool,
+}
+
+impl EnumDef {
+    /// Find an entry by its Kotlin name
+    pub fn entry(&self, name: &str) -> Option<&EnumEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Find an entry by the value written in YAML
+    pub fn entry_by_value(&self, value: &str) -> Option<&EnumEntry> {
+        self.entries.iter().find(|e| e.schema_value == value)
+    }
 }
 
 /// An enum entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnumEntry {
-    #[allow(dead_code)]
     pub name: String,
     pub schema_value: String,
     pub doc: Option<String>,
     pub is_outdated: bool,
+    /// The entry named by `@Deprecated("...", ReplaceWith("OTHER"))`
+    pub replacement: Option<String>,
+    pub arguments: Vec<EnumArgument>,
+}
+
+impl EnumEntry {
+    /// Get the raw value of a named constructor argument
+    pub fn named_argument(&self, name: &str) -> Option<&str> {
+        self.arguments
+            .iter()
+            .find(|arg| arg.name.as_deref() == Some(name))
+            .map(|arg| arg.value.as_str())
+    }
+
+    /// Get the platforms listed in `supportedPlatforms = setOf(Platform.X, ...)`.
+    ///
+    /// Returns `None` when the entry doesn't declare them or uses a computed
+    /// set (like `Platform.leafPlatforms`), meaning any platform is allowed.
+    pub fn supported_platforms(&self) -> Option<Vec<String>> {
+        Self::extract_platform_set(self.named_argument("supportedPlatforms")?)
+    }
+
+    /// Get the platforms listed in `defaultPlatforms = setOf(Platform.X, ...)`
+    pub fn default_platforms(&self) -> Option<Vec<String>> {
+        Self::extract_platform_set(self.named_argument("defaultPlatforms")?)
+    }
+
+    /// Check whether `defaultPlatforms` is explicitly `null`
+    pub fn has_no_default_platforms(&self) -> bool {
+        self.named_argument("defaultPlatforms") == Some("null")
+    }
+
+    /// Check if this entry is declared with `isLeaf = true`
+    pub fn is_leaf(&self) -> bool {
+        self.named_argument("isLeaf") == Some("true")
+    }
+
+    /// Get the entry named by the `parent` argument, given by name or as the
+    /// first positional argument: `IOS(APPLE)`, `IOS(parent = Platform.APPLE)`
+    pub fn parent_entry(&self) -> Option<&str> {
+        let value = self
+            .named_argument("parent")
+            .or_else(|| self.arguments.first().filter(|arg| arg.name.is_none()).map(|arg| arg.value.as_str()))?;
+        let name = value.rsplit('.').next().unwrap_or(value);
+        (name != "null").then_some(name)
+    }
+
+    /// Extract entry names from an expression like `setOf(Platform.JVM, Platform.ANDROID)`
+    fn extract_platform_set(value: &str) -> Option<Vec<String>> {
+        let content = value.strip_prefix("setOf(")?.strip_suffix(')')?;
+        Some(
+            content
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.rsplit('.').next().unwrap_or(s).to_string())
+                .collect(),
+        )
+    }
+}
+
+/// Extract the expression of `ReplaceWith("...")` from a `@Deprecated` annotation
+pub fn replace_with(annotation: &str) -> Option<String> {
+    let (_, rest) = annotation.split_once("ReplaceWith(")?;
+    let (_, rest) = rest.split_once('"')?;
+    let (expression, _) = rest.split_once('"')?;
+    Some(expression.to_string())
+}
+
+/// A constructor argument of an enum entry, e.g. `isLeaf = true`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnumArgument {
+    pub name: Option<String>,
+    pub value: String,
 }
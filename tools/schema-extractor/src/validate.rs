@@ -0,0 +1,867 @@
+//! Validation of YAML documents against the generated schema
+//!
+//! Implements the JSON Schema keywords the generator emits (`$ref`, `type`,
+//! `enum`, `const`, `pattern`, `format: uri`, object and array keywords,
+//! combinators and `if`/`then`/`else`) over positioned YAML nodes, so every
+//! violation can be reported with its line and column. The VS Code
+//! `errorMessage` keyword replaces the errors of the schema it annotates.
+//! Unknown keys and enum values come with a "did you mean" suggestion.
+
+use crate::error::{Error, Result};
+use crate::report::{self, Finding, Fix, Rule, Severity, plural};
+use crate::suggest::{self, NameKind};
+use crate::types::{ClassDef, ParsingContext};
+use crate::workspace;
+use crate::yaml::{self, Node, NodeValue, Position};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Most allowed values listed in an `enum` error
+const ENUM_VALUES_SHOWN: usize = 10;
+
+/// `patternProperties` key the generator emits for `@ModifierAware` properties
+static MODIFIER_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\^(\(test-\)\?)?([\w-]+)\(@\.\+\)\?\$$").unwrap());
+
+pub const INVALID_TYPE: Rule = schema_rule("invalid-type", "A value of the wrong type");
+pub const INVALID_VALUE: Rule = schema_rule("invalid-value", "A value the schema doesn't allow");
+pub const INVALID_FORMAT: Rule = schema_rule("invalid-format", "A string not in the expected format");
+pub const UNKNOWN_PROPERTY: Rule = schema_rule("unknown-property", "A key the schema doesn't define");
+pub const MISSING_PROPERTY: Rule = schema_rule("missing-property", "A required key is missing");
+pub const DUPLICATE_ITEM: Rule = schema_rule("duplicate-item", "A list item repeated in a list of unique items");
+pub const INVALID_ALTERNATIVE: Rule =
+    schema_rule("invalid-alternative", "A value matching none, or several, of the accepted forms");
+pub const UNRESOLVED_REFERENCE: Rule = schema_rule("unresolved-reference", "A `$ref` the schema can't resolve");
+pub const NO_SCHEMA: Rule = schema_rule("no-schema", "No schema is available for the kind of file");
+
+pub const RULES: &[&Rule] = &[
+    &INVALID_TYPE,
+    &INVALID_VALUE,
+    &INVALID_FORMAT,
+    &UNKNOWN_PROPERTY,
+    &MISSING_PROPERTY,
+    &DUPLICATE_ITEM,
+    &INVALID_ALTERNATIVE,
+    &UNRESOLVED_REFERENCE,
+    &NO_SCHEMA,
+];
+
+const fn schema_rule(id: &'static str, summary: &'static str) -> Rule {
+    Rule { id, severity: Severity::Error, summary, docs: "validating-configuration-files" }
+}
+
+/// A schema violation at a position in a YAML document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: &'static Rule,
+    pub position: Position,
+    /// Dotted path to the offending node: `settings.jvm.release`, `dependencies[0]`
+    pub path: String,
+    pub message: String,
+    pub suggestion: Option<Suggestion>,
+}
+
+/// A "did you mean" replacement for the key or value at a diagnostic's position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// The key or value as read
+    pub original: String,
+    pub replacement: String,
+}
+
+impl Diagnostic {
+    /// The diagnostic as a finding in `file`, whose text is `source`, its suggestion as a fix
+    pub fn into_finding(self, file: &str, source: &str) -> Finding {
+        let suggestion = self.suggestion.as_ref();
+        let end = suggestion.and_then(|s| report::token_end(source, self.position, &s.original));
+        let fixes = match (suggestion, end) {
+            (Some(suggestion), Some(end)) => vec![Fix {
+                description: format!("Replace `{}` with `{}`", suggestion.original, suggestion.replacement),
+                start: self.position,
+                end,
+                replacement: suggestion.replacement.clone(),
+            }],
+            _ => Vec::new(),
+        };
+        Finding {
+            file: file.to_string(),
+            rule: self.rule,
+            message: self.message,
+            start: self.position,
+            end,
+            path: Some(self.path),
+            fixes,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.position.line, self.position.column, self.path, self.message
+        )
+    }
+}
+
+/// Schema documents addressable by `$ref`, keyed by file name
+#[derive(Debug, Default)]
+pub struct SchemaStore {
+    documents: HashMap<String, Value>,
+    /// The model the schemas were generated from, if known
+    context: Option<ParsingContext>,
+}
+
+impl SchemaStore {
+    pub fn insert(&mut self, name: &str, document: Value) {
+        self.documents.insert(name.to_string(), document);
+    }
+
+    /// Draw "did you mean" suggestions for the definitions of classes and
+    /// enums from `context` rather than from the schemas
+    pub fn set_context(&mut self, context: ParsingContext) {
+        self.context = Some(context);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.documents.contains_key(name)
+    }
+
+    /// Load a schema file together with the files its `$ref`s point to,
+    /// which are looked up next to it. Returns the name of the loaded document.
+    pub fn load(&mut self, path: &Path) -> Result<String> {
+        let name = file_name(path);
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut pending = vec![name.clone()];
+
+        while let Some(next) = pending.pop() {
+            if self.contains(&next) {
+                continue;
+            }
+            let file = dir.join(&next);
+            let content = std::fs::read_to_string(&file).map_err(Error::io(&file))?;
+            let document: Value =
+                serde_json::from_str(&content).map_err(|source| Error::Schema { path: file.clone(), source })?;
+
+            let mut references = Vec::new();
+            collect_external_refs(&document, &mut references);
+            pending.extend(references);
+            self.insert(&next, document);
+        }
+
+        Ok(name)
+    }
+
+    /// Name of the definition `schema` is, or is an `anyOf` branch of
+    fn definition_of(&self, schema: &Map<String, Value>) -> Option<&str> {
+        let is_schema = |value: &Value| value.as_object().is_some_and(|obj| std::ptr::eq(obj, schema));
+        self.documents
+            .values()
+            .filter_map(|document| document.get("$defs").or_else(|| document.get("definitions"))?.as_object())
+            .flatten()
+            .find(|(_, definition)| {
+                is_schema(definition)
+                    || definition.get("anyOf").and_then(Value::as_array).is_some_and(|branches| branches.iter().any(is_schema))
+            })
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// The class whose definition is `schema`, when the model is known
+    fn class_of(&self, schema: &Map<String, Value>) -> Option<(&ParsingContext, &ClassDef)> {
+        let context = self.context.as_ref()?;
+        Some((context, context.classes.get(self.definition_of(schema)?)?))
+    }
+
+    /// Resolve a `$ref` found in document `current`
+    fn resolve<'a>(&'a self, current: &'a str, reference: &'a str) -> Option<(&'a str, &'a Value)> {
+        let (document, pointer) = reference.split_once('#').unwrap_or((reference, ""));
+        let (name, document) = if document.is_empty() {
+            (current, self.documents.get(current)?)
+        } else {
+            let name = document.rsplit('/').next().unwrap_or(document);
+            self.documents.get_key_value(name).map(|(k, v)| (k.as_str(), v))?
+        };
+        let target = if pointer.is_empty() { document } else { document.pointer(pointer)? };
+        Some((name, target))
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(String::new, |n| n.to_string_lossy().into_owned())
+}
+
+/// File names referenced by `file.json#/...` style `$ref`s
+fn collect_external_refs(value: &Value, references: &mut Vec<String>) {
+    match value {
+        Value::Object(obj) => {
+            if let Some(Value::String(reference)) = obj.get("$ref")
+                && let Some((document, _)) = reference.split_once('#')
+                && !document.is_empty()
+                && !document.contains(':')
+            {
+                references.push(document.rsplit('/').next().unwrap_or(document).to_string());
+            }
+            obj.values().for_each(|v| collect_external_refs(v, references));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_external_refs(v, references)),
+        _ => {}
+    }
+}
+
+/// Validate configuration files against the root schema of their kind in
+/// `store`, named per kind by `roots`. Files of a kind without a schema get a
+/// [`NO_SCHEMA`] finding, and files that don't parse a [`report::SYNTAX_ERROR`].
+pub fn validate_files(
+    files: &[PathBuf],
+    store: &SchemaStore,
+    roots: &HashMap<&str, String>,
+    verbose: bool,
+) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for file in files {
+        let start = findings.len();
+        let display = file.display().to_string();
+        // Files given by name that don't follow Amper's naming are module files
+        let kind = workspace::config_kind(file).unwrap_or("module");
+        let Some(root) = roots.get(kind) else {
+            findings.push(Finding {
+                file: display,
+                rule: &NO_SCHEMA,
+                message: format!("no {} schema available", kind),
+                start: Position { line: 1, column: 1 },
+                end: None,
+                path: None,
+                fixes: Vec::new(),
+            });
+            continue;
+        };
+
+        let content = std::fs::read_to_string(file).map_err(Error::io(file))?;
+        match yaml::parse(&content) {
+            Ok(document) => {
+                for diagnostic in validate(store, root, &document) {
+                    findings.push(diagnostic.into_finding(&display, &content));
+                }
+            }
+            Err(error) => findings.push(Finding {
+                file: display,
+                rule: &report::SYNTAX_ERROR,
+                message: error.message,
+                start: error.position,
+                end: None,
+                path: None,
+                fixes: Vec::new(),
+            }),
+        }
+
+        if verbose {
+            eprintln!("Validated {} ({} problems)", file.display(), findings.len() - start);
+        }
+    }
+    Ok(findings)
+}
+
+/// Totals of a validation run, printed after its findings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub files: usize,
+    pub problems: usize,
+    /// Files with at least one problem
+    pub invalid_files: usize,
+}
+
+impl Summary {
+    pub fn new(findings: &[Finding], files: usize) -> Self {
+        let invalid_files = findings.iter().map(|finding| &finding.file).collect::<HashSet<_>>().len();
+        Summary { files, problems: findings.len(), invalid_files }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.problems == 0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "{} {} valid", self.files, plural(self.files, "file is", "files are"));
+        }
+        write!(
+            f,
+            "{} {} in {} of {} {}",
+            self.problems,
+            plural(self.problems, "problem", "problems"),
+            self.invalid_files,
+            self.files,
+            plural(self.files, "file", "files")
+        )
+    }
+}
+
+/// A finding as a line of text output: `file:line:column: path: message`
+pub fn text_line(finding: &Finding) -> String {
+    let start = finding.start;
+    match &finding.path {
+        Some(path) => format!("{}:{}:{}: {}: {}", finding.file, start.line, start.column, path, finding.message),
+        None if finding.rule == &NO_SCHEMA => format!("{}: {}", finding.file, finding.message),
+        None if finding.rule == &report::SYNTAX_ERROR => {
+            format!("{}:{}:{}: {}: syntax error", finding.file, start.line, start.column, finding.message)
+        }
+        None => format!("{}:{}:{}: {}", finding.file, start.line, start.column, finding.message),
+    }
+}
+
+/// Validate a YAML document against the root schema stored as `root`
+pub fn validate(store: &SchemaStore, root: &str, node: &Node) -> Vec<Diagnostic> {
+    let Some(schema) = store.documents.get(root) else {
+        return Vec::new();
+    };
+    let validator = Validator { store, patterns: RefCell::new(HashMap::new()) };
+    let mut failures = Vec::new();
+    validator.check(schema, root, node, &[], &mut failures);
+
+    // A schema author's message explains a value better than a sibling type check
+    let explained: Vec<Vec<Segment>> = failures.iter().filter(|f| f.custom).map(|f| f.path.clone()).collect();
+    failures.retain(|f| !(f.type_mismatch && explained.contains(&f.path)));
+
+    let mut diagnostics: Vec<Diagnostic> = failures
+        .into_iter()
+        .map(|f| Diagnostic {
+            rule: f.rule,
+            position: f.position,
+            path: render_path(&f.path),
+            message: f.message,
+            suggestion: f.suggestion,
+        })
+        .collect();
+    diagnostics.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.message.cmp(&b.message)));
+    diagnostics.dedup();
+    diagnostics
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn render_path(path: &[Segment]) -> String {
+    if path.is_empty() {
+        return "<root>".to_string();
+    }
+    let mut rendered = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !rendered.is_empty() {
+                    rendered.push('.');
+                }
+                rendered.push_str(key);
+            }
+            Segment::Index(index) => rendered.push_str(&format!("[{}]", index)),
+        }
+    }
+    rendered
+}
+
+#[derive(Debug, Clone)]
+struct Failure {
+    rule: &'static Rule,
+    position: Position,
+    path: Vec<Segment>,
+    message: String,
+    /// The value has the wrong type, rather than a wrong content
+    type_mismatch: bool,
+    /// The message comes from an `errorMessage`
+    custom: bool,
+    suggestion: Option<Suggestion>,
+}
+
+struct Validator<'a> {
+    store: &'a SchemaStore,
+    /// Compiled `pattern`s; `None` for patterns the regex crate can't handle
+    patterns: RefCell<HashMap<String, Option<Regex>>>,
+}
+
+impl<'a> Validator<'a> {
+    fn check(&self, schema: &'a Value, doc: &'a str, node: &Node, path: &[Segment], out: &mut Vec<Failure>) {
+        let obj = match schema {
+            Value::Object(obj) => obj,
+            Value::Bool(false) => {
+                out.push(failure(&INVALID_VALUE, node.position, path, "No value is allowed here".to_string()));
+                return;
+            }
+            _ => return,
+        };
+        let start = out.len();
+
+        if let Some(Value::String(reference)) = obj.get("$ref") {
+            match self.store.resolve(doc, reference) {
+                Some((target_doc, target)) => self.check(target, target_doc, node, path, out),
+                None => out.push(failure(
+                    &UNRESOLVED_REFERENCE,
+                    node.position,
+                    path,
+                    format!("Unresolved schema reference `{}`", reference),
+                )),
+            }
+        }
+
+        if let Some(expected) = obj.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !types.is_empty() && !types.iter().any(|t| type_matches(node, t)) {
+                out.push(Failure {
+                    type_mismatch: true,
+                    ..failure(
+                        &INVALID_TYPE,
+                        node.position,
+                        path,
+                        format!("Incorrect type: expected {}, found {}", types.join(" or "), node.kind()),
+                    )
+                });
+                apply_error_message(obj, node, path, start, out);
+                return;
+            }
+        }
+
+        if let Some(Value::Array(values)) = obj.get("enum")
+            && !values.iter().any(|v| value_matches(node, v))
+        {
+            let mut shown: Vec<String> = values.iter().take(ENUM_VALUES_SHOWN).map(display_value).collect();
+            if values.len() > ENUM_VALUES_SHOWN {
+                shown.push("…".to_string());
+            }
+            let mut allowed: Vec<&str> = values.iter().filter_map(Value::as_str).collect();
+            // Values replaced by others aren't suggested
+            if let Some(context) = &self.store.context
+                && let Some(enum_def) = self.store.definition_of(obj).and_then(|name| context.enums.get(name))
+            {
+                allowed.retain(|value| {
+                    enum_def.entries.iter().any(|e| e.schema_value == *value && e.replacement.is_none())
+                });
+            }
+            let suggestion = node.scalar_text().and_then(|text| {
+                let value = suggest::closest(NameKind::Value, text, &allowed)?;
+                Some(Suggestion { original: text.to_string(), replacement: value.to_string() })
+            });
+            let hint = suggestion
+                .as_ref()
+                .map(|suggestion| format!(" Did you mean `{}`?", suggestion.replacement))
+                .unwrap_or_default();
+            out.push(Failure {
+                suggestion,
+                ..failure(
+                    &INVALID_VALUE,
+                    node.position,
+                    path,
+                    format!(
+                        "Value {} is not allowed.{} Expected one of: {}",
+                        display_node(node),
+                        hint,
+                        shown.join(", ")
+                    ),
+                )
+            });
+        }
+
+        if let Some(expected) = obj.get("const")
+            && !value_matches(node, expected)
+        {
+            out.push(failure(&INVALID_VALUE, node.position, path, format!("Expected {}", display_value(expected))));
+        }
+
+        if let Some(text) = node.scalar_text() {
+            if let Some(Value::String(pattern)) = obj.get("pattern")
+                && let Some(false) = self.pattern_matches(pattern, text)
+            {
+                out.push(failure(
+                    &INVALID_FORMAT,
+                    node.position,
+                    path,
+                    format!("Value `{}` does not match the expected format `{}`", text, pattern),
+                ));
+            }
+            if obj.get("format").and_then(Value::as_str) == Some("uri") && !is_uri(text) {
+                out.push(failure(&INVALID_FORMAT, node.position, path, format!("Value `{}` is not a valid URI", text)));
+            }
+        }
+
+        if let NodeValue::Mapping(entries) = &node.value {
+            self.check_object(obj, doc, node, entries, path, out);
+        }
+
+        if let NodeValue::Sequence(items) = &node.value {
+            if let Some(item_schema) = obj.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    self.check(item_schema, doc, item, &child(path, Segment::Index(index)), out);
+                }
+            }
+            if obj.get("uniqueItems") == Some(&Value::Bool(true)) {
+                let mut seen = Vec::new();
+                for (index, item) in items.iter().enumerate() {
+                    let value = item.to_json();
+                    if seen.contains(&value) {
+                        let item_path = child(path, Segment::Index(index));
+                        out.push(failure(&DUPLICATE_ITEM, item.position, &item_path, "Duplicate item".to_string()));
+                    } else {
+                        seen.push(value);
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::Array(parts)) = obj.get("allOf") {
+            for part in parts {
+                self.check(part, doc, node, path, out);
+            }
+        }
+
+        if let Some(Value::Array(branches)) = obj.get("anyOf") {
+            let results = self.check_branches(branches, doc, node, path);
+            if !results.iter().any(Vec::is_empty) {
+                self.report_alternatives(branches, doc, results, node, path, out);
+            }
+        }
+
+        if let Some(Value::Array(branches)) = obj.get("oneOf") {
+            let results = self.check_branches(branches, doc, node, path);
+            let matching: Vec<usize> = (0..branches.len()).filter(|i| results[*i].is_empty()).collect();
+            if matching.is_empty() {
+                self.report_alternatives(branches, doc, results, node, path, out);
+            } else if matching.len() > 1 {
+                let labels: Vec<String> = matching.iter().map(|i| self.label(&branches[*i], doc)).collect();
+                out.push(failure(
+                    &INVALID_ALTERNATIVE,
+                    node.position,
+                    path,
+                    format!("Ambiguous value: it matches each of {}", labels.join(", ")),
+                ));
+            }
+        }
+
+        if let Some(condition) = obj.get("if") {
+            let mut condition_failures = Vec::new();
+            self.check(condition, doc, node, path, &mut condition_failures);
+            let branch = if condition_failures.is_empty() { obj.get("then") } else { obj.get("else") };
+            if let Some(branch) = branch {
+                self.check(branch, doc, node, path, out);
+            }
+        }
+
+        apply_error_message(obj, node, path, start, out);
+    }
+
+    fn check_object(
+        &self,
+        obj: &'a Map<String, Value>,
+        doc: &'a str,
+        node: &Node,
+        entries: &[crate::yaml::Entry],
+        path: &[Segment],
+        out: &mut Vec<Failure>,
+    ) {
+        let properties = obj.get("properties").and_then(Value::as_object);
+        let pattern_properties = obj.get("patternProperties").and_then(Value::as_object);
+
+        for entry in entries {
+            let entry_path = child(path, Segment::Key(entry.key.clone()));
+            let mut matched = false;
+
+            if let Some(schema) = properties.and_then(|p| p.get(&entry.key)) {
+                matched = true;
+                self.check(schema, doc, &entry.value, &entry_path, out);
+            }
+            for (pattern, schema) in pattern_properties.into_iter().flatten() {
+                if self.pattern_matches(pattern, &entry.key) == Some(true) {
+                    matched = true;
+                    self.check(schema, doc, &entry.value, &entry_path, out);
+                }
+            }
+
+            if !matched {
+                match obj.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        let suggestion = self
+                            .suggest_property(obj, &entry.key)
+                            .map(|key| Suggestion { original: entry.key.clone(), replacement: key });
+                        let hint = suggestion
+                            .as_ref()
+                            .map(|suggestion| format!(". Did you mean `{}`?", suggestion.replacement))
+                            .unwrap_or_default();
+                        out.push(Failure {
+                            suggestion,
+                            ..failure(
+                                &UNKNOWN_PROPERTY,
+                                entry.key_position,
+                                &entry_path,
+                                format!("Property `{}` is not allowed{}", entry.key, hint),
+                            )
+                        })
+                    }
+                    Some(schema @ Value::Object(_)) => self.check(schema, doc, &entry.value, &entry_path, out),
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(Value::Array(required)) = obj.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !entries.iter().any(|e| e.key == key) {
+                    out.push(failure(
+                        &MISSING_PROPERTY,
+                        node.position,
+                        path,
+                        format!("Missing required property `{}`", key),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_branches(&self, branches: &'a [Value], doc: &'a str, node: &Node, path: &[Segment]) -> Vec<Vec<Failure>> {
+        branches
+            .iter()
+            .map(|branch| {
+                let mut failures = Vec::new();
+                self.check(branch, doc, node, path, &mut failures);
+                failures
+            })
+            .collect()
+    }
+
+    /// Report a value matching none of several alternatives: the errors of the
+    /// alternative that got furthest, or the list of accepted alternatives when
+    /// none fits even the shape of the value
+    fn report_alternatives(
+        &self,
+        branches: &'a [Value],
+        doc: &'a str,
+        results: Vec<Vec<Failure>>,
+        node: &Node,
+        path: &[Segment],
+        out: &mut Vec<Failure>,
+    ) {
+        let is_null_branch = |branch: &Value| branch.get("type").and_then(Value::as_str) == Some("null");
+        let candidates: Vec<(usize, Vec<Failure>)> = results
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| matches!(node.value, NodeValue::Null) || !is_null_branch(&branches[*i]))
+            .collect();
+
+        if candidates.len() == 1 {
+            out.extend(candidates.into_iter().next().unwrap().1);
+            return;
+        }
+
+        // The only alternative accepting this kind of value explains best what's wrong with it
+        let fitting: Vec<&Vec<Failure>> = candidates
+            .iter()
+            .map(|(_, failures)| failures)
+            .filter(|failures| !failures.iter().any(|f| f.type_mismatch && f.path.len() == path.len()))
+            .collect();
+        if fitting.len() == 1 {
+            out.extend(fitting[0].iter().cloned());
+            return;
+        }
+
+        let depth = |failures: &Vec<Failure>| failures.iter().map(|f| f.path.len()).max().unwrap_or(0);
+        let best = candidates
+            .iter()
+            .max_by(|(_, a), (_, b)| depth(a).cmp(&depth(b)).then_with(|| b.len().cmp(&a.len())));
+        if let Some((_, failures)) = best
+            && depth(failures) > path.len()
+        {
+            out.extend(failures.iter().cloned());
+            return;
+        }
+
+        let mut labels: Vec<String> = Vec::new();
+        for (index, _) in &candidates {
+            let label = self.label(&branches[*index], doc);
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        out.push(failure(&INVALID_ALTERNATIVE, node.position, path, format!("Expected one of: {}", labels.join(", "))));
+    }
+
+    /// Short name of an alternative: its title, referenced definition, constant or type
+    fn label(&self, schema: &'a Value, doc: &'a str) -> String {
+        if let Some(title) = schema.get("title").and_then(Value::as_str) {
+            return title.to_string();
+        }
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            if let Some((target_doc, target)) = self.store.resolve(doc, reference)
+                && target.get("title").is_some()
+            {
+                return self.label(target, target_doc);
+            }
+            return reference.rsplit('/').next().unwrap_or(reference).to_string();
+        }
+        if let Some(value) = schema.get("const") {
+            return display_value(value);
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties")
+            && properties.len() == 1
+            && let Some((key, property)) = properties.iter().next()
+            && let Some(value) = property.get("const")
+        {
+            return format!("{}: {}", key, display_value(value));
+        }
+        match schema.get("type") {
+            Some(Value::String(t)) => t.clone(),
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(" or "),
+            _ => "a different value".to_string(),
+        }
+    }
+
+    /// The allowed key closest to an unknown `key`. Qualified keys (`name@platform`)
+    /// are matched against the modifier-aware properties and keep their qualifier.
+    fn suggest_property(&self, obj: &Map<String, Value>, key: &str) -> Option<String> {
+        let (name, qualifier) = match key.split_once('@') {
+            Some((name, qualifier)) => (name, Some(qualifier)),
+            None => (key, None),
+        };
+
+        let mut candidates = match self.store.class_of(obj) {
+            Some((context, class)) => class_keys(context, class, qualifier.is_some()),
+            None => schema_keys(obj, qualifier.is_some()),
+        };
+        // Keep the first occurrence, so declaration order still breaks ties
+        let mut seen = HashSet::new();
+        candidates.retain(|candidate| seen.insert(candidate.clone()));
+
+        let candidates: Vec<&str> = candidates.iter().map(String::as_str).collect();
+        let suggestion = suggest::closest(NameKind::Property, name, &candidates)?;
+        Some(match qualifier {
+            Some(qualifier) => format!("{}@{}", suggestion, qualifier),
+            None => suggestion.to_string(),
+        })
+    }
+
+    fn pattern_matches(&self, pattern: &str, text: &str) -> Option<bool> {
+        let mut patterns = self.patterns.borrow_mut();
+        let regex = patterns.entry(pattern.to_string()).or_insert_with(|| Regex::new(pattern).ok());
+        regex.as_ref().map(|r| r.is_match(text))
+    }
+}
+
+/// Keys of a class's properties, leaving out hidden and deprecated ones as completion does.
+/// `qualified` keys only take the modifier-aware properties.
+fn class_keys(context: &ParsingContext, class: &ClassDef, qualified: bool) -> Vec<String> {
+    let properties: Vec<_> = context
+        .all_properties(class)
+        .into_iter()
+        .filter(|p| !p.is_hidden() && p.deprecation_message().is_none())
+        .collect();
+
+    let mut keys: Vec<String> = Vec::new();
+    if !qualified {
+        keys.extend(properties.iter().map(|p| p.name.clone()));
+    }
+    for property in properties.iter().filter(|p| p.is_modifier_aware()) {
+        if !property.name.starts_with("test-") {
+            keys.push(format!("test-{}", property.name));
+        }
+        keys.push(property.name.clone());
+    }
+    keys
+}
+
+/// Keys a schema allows, for schemas generated from an unknown model
+fn schema_keys(obj: &Map<String, Value>, qualified: bool) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    if !qualified && let Some(properties) = obj.get("properties").and_then(Value::as_object) {
+        keys.extend(properties.keys().cloned());
+    }
+    for pattern in obj.get("patternProperties").and_then(Value::as_object).into_iter().flat_map(Map::keys) {
+        if let Some(captures) = MODIFIER_PATTERN.captures(pattern) {
+            let property = &captures[2];
+            if captures.get(1).is_some() {
+                keys.push(format!("test-{}", property));
+            }
+            keys.push(property.to_string());
+        }
+    }
+    keys
+}
+
+fn failure(rule: &'static Rule, position: Position, path: &[Segment], message: String) -> Failure {
+    Failure { rule, position, path: path.to_vec(), message, type_mismatch: false, custom: false, suggestion: None }
+}
+
+fn child(path: &[Segment], segment: Segment) -> Vec<Segment> {
+    let mut path = path.to_vec();
+    path.push(segment);
+    path
+}
+
+/// Replace the failures produced by a schema with its `errorMessage`
+fn apply_error_message(obj: &Map<String, Value>, node: &Node, path: &[Segment], start: usize, out: &mut Vec<Failure>) {
+    if out.len() > start
+        && let Some(Value::String(message)) = obj.get("errorMessage")
+    {
+        // The message stands for the first failure it replaces
+        let rule = out[start].rule;
+        out.truncate(start);
+        out.push(Failure { custom: true, ..failure(rule, node.position, path, message.clone()) });
+    }
+}
+
+fn type_matches(node: &Node, expected: &str) -> bool {
+    match (expected, &node.value) {
+        ("null", NodeValue::Null) => true,
+        ("boolean", NodeValue::Bool(_)) => true,
+        ("integer", NodeValue::Int(_)) => true,
+        ("integer", NodeValue::Float(f)) => f.fract() == 0.0,
+        ("number", NodeValue::Int(_) | NodeValue::Float(_)) => true,
+        // Unquoted numbers and booleans are read as strings where strings are expected
+        ("string", NodeValue::String(_)) => true,
+        ("string", NodeValue::Bool(_) | NodeValue::Int(_) | NodeValue::Float(_)) => node.plain.is_some(),
+        ("array", NodeValue::Sequence(_)) => true,
+        ("object", NodeValue::Mapping(_)) => true,
+        _ => false,
+    }
+}
+
+fn value_matches(node: &Node, expected: &Value) -> bool {
+    match expected {
+        Value::String(s) => node.scalar_text() == Some(s.as_str()),
+        other => node.to_json() == *other,
+    }
+}
+
+fn is_uri(text: &str) -> bool {
+    let Some((scheme, rest)) = text.split_once(':') else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !rest.is_empty()
+        && !text.contains(char::is_whitespace)
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("`{}`", s),
+        other => format!("`{}`", other),
+    }
+}
+
+fn display_node(node: &Node) -> String {
+    match node.scalar_text() {
+        Some(text) => format!("`{}`", text),
+        None => node.kind().to_string(),
+    }
+}
@@ -0,0 +1,404 @@
+//! Cross-file checks of an Amper project
+//!
+//! Finds the modules of a workspace the way the extension's
+//! `FileSystemProjectRepository` does (every directory with a `module.yaml`,
+//! skipping build output) and checks what schema validation can't see:
+//! local dependencies and applied templates that point nowhere, `modules`
+//! entries of project.yaml that match no module, and modules sharing a name.
+
+use crate::error::{Error, Result};
+use crate::report::{self, Finding, Rule, Severity, plural};
+use crate::yaml::{self, Node, NodeValue, Position};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Directories never searched for configuration files
+pub const SKIPPED_DIRS: &[&str] = &["build", "node_modules", "target"];
+
+const MODULE_FILE: &str = "module.yaml";
+const PROJECT_FILE: &str = "project.yaml";
+const TEMPLATE_SUFFIX: &str = ".module-template.yaml";
+
+/// Schema type of an Amper configuration file, by its name
+pub fn config_kind(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    match name {
+        MODULE_FILE => Some("module"),
+        PROJECT_FILE => Some("project"),
+        _ if name.ends_with(TEMPLATE_SUFFIX) => Some("template"),
+        _ => None,
+    }
+}
+
+/// Expand directories into the Amper configuration files below them
+pub fn collect_config_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let walker = walkdir::WalkDir::new(path).sort_by_file_name().into_iter().filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !(name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref()))
+        });
+        for entry in walker.filter_map(|e| e.ok()) {
+            if entry.file_type().is_file() && config_kind(entry.path()).is_some() {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    files
+}
+
+pub const TEMPLATES: Rule =
+    project_rule("templates", "An `apply:` entry names a missing file or one that isn't a module template");
+pub const DEPENDENCIES: Rule =
+    project_rule("dependencies", "A local dependency points to a missing directory or one without module.yaml");
+pub const PROJECT_MODULES: Rule =
+    project_rule("project-modules", "A project.yaml `modules` entry names no module, or is a glob matching none");
+pub const MODULE_NAMES: Rule = project_rule("module-names", "Two modules of the project have the same directory name");
+
+pub const RULES: &[&Rule] = &[&TEMPLATES, &DEPENDENCIES, &PROJECT_MODULES, &MODULE_NAMES];
+
+const fn project_rule(id: &'static str, summary: &'static str) -> Rule {
+    Rule { id, severity: Severity::Error, summary, docs: "checking-a-project" }
+}
+
+/// A cross-file problem, located in the file that contains the mistake
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub file: PathBuf,
+    pub rule: &'static Rule,
+    pub position: Position,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn to_finding(&self) -> Finding {
+        Finding {
+            file: self.file.display().to_string(),
+            rule: self.rule,
+            message: self.message.clone(),
+            start: self.position,
+            end: None,
+            path: None,
+            fixes: Vec::new(),
+        }
+    }
+}
+
+/// A finding as a line of text output: `file:line:column: message`
+pub fn text_line(finding: &Finding) -> String {
+    let start = finding.start;
+    if finding.rule == &report::SYNTAX_ERROR {
+        format!("{}:{}:{}: {}: syntax error", finding.file, start.line, start.column, finding.message)
+    } else {
+        format!("{}:{}:{}: {}", finding.file, start.line, start.column, finding.message)
+    }
+}
+
+/// Result of checking a workspace
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Configuration files read
+    pub files: usize,
+    /// Directories containing a `module.yaml`
+    pub modules: Vec<PathBuf>,
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    pub fn findings(&self) -> Vec<Finding> {
+        self.problems.iter().map(Problem::to_finding).collect()
+    }
+
+    /// The totals printed after the problems
+    pub fn summary(&self) -> String {
+        let modules = format!("{} {}", self.modules.len(), plural(self.modules.len(), "module", "modules"));
+        match self.problems.len() {
+            0 => format!("No problems found in {}", modules),
+            problems => format!("{} {} in {}", problems, plural(problems, "problem", "problems"), modules),
+        }
+    }
+}
+
+/// Check the project rooted at `root`
+pub fn check(root: &Path) -> Result<Report> {
+    if !root.is_dir() {
+        return Err(Error::NotFound(format!("Project directory not found: {}", root.display())));
+    }
+
+    let files = collect_config_files(&[root.to_path_buf()]);
+    let mut report = Report {
+        files: files.len(),
+        modules: files
+            .iter()
+            .filter(|file| config_kind(file) == Some("module"))
+            .filter_map(|file| file.parent().map(Path::to_path_buf))
+            .collect(),
+        problems: Vec::new(),
+    };
+
+    // Modules project.yaml files include; every module when there are none
+    let mut included: Option<Vec<PathBuf>> = None;
+    for file in &files {
+        let content = std::fs::read_to_string(file).map_err(Error::io(file))?;
+        let document = match yaml::parse(&content) {
+            Ok(document) => document,
+            Err(error) => {
+                report.problems.push(Problem {
+                    file: file.clone(),
+                    rule: &report::SYNTAX_ERROR,
+                    position: error.position,
+                    message: error.message,
+                });
+                continue;
+            }
+        };
+        let base = file.parent().unwrap_or(root);
+        match config_kind(file) {
+            Some("project") => {
+                check_project_modules(root, &report.modules, file, &document, &mut report.problems);
+                included.get_or_insert_with(Vec::new).extend(project_modules(&report.modules, file, &document));
+            }
+            _ => {
+                check_templates(base, file, &document, &mut report.problems);
+                check_dependencies(base, file, &document, &mut report.problems);
+            }
+        }
+    }
+
+    let mut included = included.unwrap_or_else(|| report.modules.clone());
+    included.sort();
+    included.dedup();
+    check_module_names(root, &included, &mut report.problems);
+    report.problems.sort_by(|a, b| a.file.cmp(&b.file).then(a.position.cmp(&b.position)));
+    Ok(report)
+}
+
+/// Entries of the top-level mapping whose key, without a `@platform`
+/// qualifier, is one of `names`
+pub fn sections<'n>(document: &'n Node, names: &[&str]) -> impl Iterator<Item = &'n Node> {
+    let entries = match &document.value {
+        NodeValue::Mapping(entries) => entries.as_slice(),
+        _ => &[],
+    };
+    entries
+        .iter()
+        .filter(|entry| names.contains(&entry.key.split('@').next().unwrap_or_default()))
+        .map(|entry| &entry.value)
+}
+
+/// The value of the top-level `key` of a document, matched exactly
+pub fn top_level<'n>(document: &'n Node, key: &str) -> Option<&'n Node> {
+    match &document.value {
+        NodeValue::Mapping(entries) => entries.iter().find(|entry| entry.key == key).map(|entry| &entry.value),
+        _ => None,
+    }
+}
+
+/// The templates a module applies (`apply:`), as written, with their positions
+pub fn applied_templates(module: &Node) -> Vec<(&str, Position)> {
+    top_level(module, "apply")
+        .map(items)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|item| item.scalar_text().map(|text| (text, item.position)))
+        .collect()
+}
+
+/// The aliases a module can qualify keys with (`aliases: [jvmAndAndroid: [jvm, android]]`)
+/// and their platforms: its own and those of the templates it applies, which are
+/// resolved against `base`, the module's directory, when it is known
+pub fn aliases(module: &Node, base: Option<&Path>) -> Vec<(String, Vec<String>)> {
+    let templates: Vec<Node> = base
+        .map(|base| {
+            applied_templates(module)
+                .into_iter()
+                .filter_map(|(reference, _)| std::fs::read_to_string(base.join(reference)).ok())
+                .filter_map(|content| yaml::parse(&content).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut aliases = Vec::new();
+    for document in templates.iter().chain([module]) {
+        for item in top_level(document, "aliases").map(items).unwrap_or_default() {
+            let NodeValue::Mapping(entries) = &item.value else { continue };
+            for entry in entries {
+                let members = items(&entry.value).iter().filter_map(Node::scalar_text).map(str::to_string).collect();
+                aliases.push((entry.key.clone(), members));
+            }
+        }
+    }
+    aliases
+}
+
+fn items(node: &Node) -> &[Node] {
+    match &node.value {
+        NodeValue::Sequence(items) => items,
+        _ => &[],
+    }
+}
+
+/// `apply:` entries must name existing `*.module-template.yaml` files
+fn check_templates(base: &Path, file: &Path, document: &Node, problems: &mut Vec<Problem>) {
+    for item in sections(document, &["apply"]).flat_map(items) {
+        let Some(reference) = item.scalar_text() else { continue };
+        let target = base.join(reference);
+        let message = if !target.is_file() {
+            format!("Template `{}` does not exist", reference)
+        } else if !reference.ends_with(TEMPLATE_SUFFIX) {
+            format!("`{}` is not a module template: its name must end with `{}`", reference, TEMPLATE_SUFFIX)
+        } else {
+            continue;
+        };
+        problems.push(Problem { file: file.to_path_buf(), rule: &TEMPLATES, position: item.position, message });
+    }
+}
+
+/// Local dependencies (`./shared`, `path: ../lib`) must point to module directories
+fn check_dependencies(base: &Path, file: &Path, document: &Node, problems: &mut Vec<Problem>) {
+    for item in sections(document, &["dependencies", "test-dependencies"]).flat_map(items) {
+        let Some((reference, position)) = dependency_notation(item) else { continue };
+        if !is_local_path(reference) {
+            continue;
+        }
+        let target = base.join(reference);
+        let message = if !target.is_dir() {
+            format!("Dependency `{}` does not exist", reference)
+        } else if !target.join(MODULE_FILE).is_file() {
+            format!("Dependency `{}` is not a module: it has no {}", reference, MODULE_FILE)
+        } else {
+            continue;
+        };
+        problems.push(Problem { file: file.to_path_buf(), rule: &DEPENDENCIES, position, message });
+    }
+}
+
+/// Keys naming what a dependency given as an object refers to
+const DEPENDENCY_KEYS: &[&str] = &["coordinates", "path", "catalogKey", "bom"];
+
+/// What a dependency item refers to: `- ./lib`, `- ./lib: exported`,
+/// `- path: ./lib` or `- coordinates: group:name:version`
+pub fn dependency_notation(item: &Node) -> Option<(&str, Position)> {
+    match &item.value {
+        NodeValue::Mapping(entries) => match entries.iter().find(|entry| DEPENDENCY_KEYS.contains(&entry.key.as_str())) {
+            Some(entry) => entry.value.scalar_text().map(|text| (text, entry.value.position)),
+            None if entries.len() == 1 => Some((entries[0].key.as_str(), entries[0].key_position)),
+            None => None,
+        },
+        _ => item.scalar_text().map(|text| (text, item.position)),
+    }
+}
+
+/// Whether a dependency or template reference is a path relative to the file
+pub fn is_local_path(reference: &str) -> bool {
+    reference == "." || reference == ".." || reference.starts_with("./") || reference.starts_with("../")
+}
+
+/// Every project.yaml `modules` entry must name a module directory or be a
+/// glob matching at least one
+fn check_project_modules(root: &Path, modules: &[PathBuf], file: &Path, document: &Node, problems: &mut Vec<Problem>) {
+    let base = file.parent().unwrap_or(root);
+    for item in sections(document, &["modules"]).flat_map(items) {
+        let Some(entry) = item.scalar_text() else { continue };
+        let message = if entry.contains(['*', '?']) {
+            if !included_by(base, modules, entry).is_empty() {
+                continue;
+            }
+            format!("Pattern `{}` matches no module", entry)
+        } else {
+            let target = base.join(entry);
+            if !target.is_dir() {
+                format!("Module `{}` does not exist", entry)
+            } else if !target.join(MODULE_FILE).is_file() {
+                format!("Module `{}` has no {}", entry, MODULE_FILE)
+            } else {
+                continue;
+            }
+        };
+        problems.push(Problem { file: file.to_path_buf(), rule: &PROJECT_MODULES, position: item.position, message });
+    }
+}
+
+/// The modules a project.yaml includes: the one in its directory, if any,
+/// and those its `modules` entries name
+fn project_modules(modules: &[PathBuf], file: &Path, document: &Node) -> Vec<PathBuf> {
+    let Some(base) = file.parent() else { return Vec::new() };
+    let mut included: Vec<PathBuf> = modules.iter().filter(|module| *module == base).cloned().collect();
+    for item in sections(document, &["modules"]).flat_map(items) {
+        let Some(entry) = item.scalar_text() else { continue };
+        included.extend(included_by(base, modules, entry).into_iter().cloned());
+    }
+    included
+}
+
+/// The modules a `modules` entry names: those a glob matches, or the directory it names
+fn included_by<'m>(base: &Path, modules: &'m [PathBuf], entry: &str) -> Vec<&'m PathBuf> {
+    if entry.contains(['*', '?']) {
+        let pattern = glob_regex(entry);
+        modules
+            .iter()
+            .filter(|module| module.strip_prefix(base).is_ok_and(|relative| pattern.is_match(&slash_path(relative))))
+            .collect()
+    } else {
+        let target = base.join(entry);
+        modules.iter().filter(|module| **module == target).collect()
+    }
+}
+
+/// Regex for a `modules` glob: `*` and `?` match within one path segment
+fn glob_regex(glob: &str) -> Regex {
+    let glob = glob.trim_start_matches("./").trim_end_matches('/');
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).expect("escaped glob is a valid regex")
+}
+
+/// A relative path with `/` separators on every platform
+fn slash_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Module names are directory names and must be unique among the modules of the project
+fn check_module_names(root: &Path, modules: &[PathBuf], problems: &mut Vec<Problem>) {
+    let mut by_name: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+    for module in modules {
+        let name = module.file_name().map_or_else(|| "<root>".into(), |name| name.to_string_lossy());
+        by_name.entry(name.into_owned()).or_default().push(module);
+    }
+
+    for (name, dirs) in by_name {
+        let Some((first, others)) = dirs.split_first() else { continue };
+        for dir in others {
+            problems.push(Problem {
+                file: dir.join(MODULE_FILE),
+                rule: &MODULE_NAMES,
+                position: Position { line: 1, column: 1 },
+                message: format!(
+                    "Module name `{}` is already used by `{}`",
+                    name,
+                    slash_path(first.join(MODULE_FILE).strip_prefix(root).unwrap_or(first))
+                ),
+            });
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! YAML documents with source positions
+//!
+//! Amper configuration files are loaded into a small tree that remembers
+//! where every node and mapping key starts, so diagnostics can point at
+//! `file:line:column`.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+/// A 1-based line and column in the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl From<Marker> for Position {
+    fn from(marker: Marker) -> Self {
+        // The scanner counts lines from 1 but columns from 0
+        Position { line: marker.line(), column: marker.col() + 1 }
+    }
+}
+
+/// A YAML node and where it starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub value: NodeValue,
+    pub position: Position,
+    /// Source text of an unquoted scalar
+    pub plain: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Sequence(Vec<Node>),
+    Mapping(Vec<Entry>),
+}
+
+/// A mapping entry; keys are always read as strings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub key_position: Position,
+    pub value: Node,
+}
+
+/// A syntax error in a YAML document
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub position: Position,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.position.line, self.position.column, self.message)
+    }
+}
+
+impl Node {
+    /// The text of a non-null scalar as written. Unquoted `17`, `true` or `1.10`
+    /// resolve to numbers and booleans, but Amper reads them as strings where a
+    /// string is expected.
+    pub fn scalar_text(&self) -> Option<&str> {
+        match &self.value {
+            NodeValue::Null | NodeValue::Sequence(_) | NodeValue::Mapping(_) => None,
+            NodeValue::String(value) => Some(value),
+            _ => self.plain.as_deref(),
+        }
+    }
+
+    /// Name of the node's kind in JSON Schema terms
+    pub fn kind(&self) -> &'static str {
+        match &self.value {
+            NodeValue::Null => "null",
+            NodeValue::Bool(_) => "boolean",
+            NodeValue::Int(_) => "integer",
+            NodeValue::Float(_) => "number",
+            NodeValue::String(_) => "string",
+            NodeValue::Sequence(_) => "array",
+            NodeValue::Mapping(_) => "object",
+        }
+    }
+
+    /// The node as JSON, dropping positions
+    pub fn to_json(&self) -> Value {
+        match &self.value {
+            NodeValue::Null => Value::Null,
+            NodeValue::Bool(b) => Value::Bool(*b),
+            NodeValue::Int(i) => Value::from(*i),
+            NodeValue::Float(f) => serde_json::Number::from_f64(*f).map_or(Value::Null, Value::Number),
+            NodeValue::String(value) => Value::String(value.clone()),
+            NodeValue::Sequence(items) => Value::Array(items.iter().map(Node::to_json).collect()),
+            NodeValue::Mapping(entries) => Value::Object(
+                entries.iter().map(|e| (e.key.clone(), e.value.to_json())).collect(),
+            ),
+        }
+    }
+}
+
+/// Parse the first document of a YAML text; an empty text is a `null` document
+pub fn parse(source: &str) -> Result<Node, SyntaxError> {
+    let mut builder = TreeBuilder::default();
+    let mut parser = Parser::new_from_str(source);
+    parser.load(&mut builder, false).map_err(|e| SyntaxError {
+        position: Position::from(*e.marker()),
+        message: e.info().to_string(),
+    })?;
+
+    Ok(builder.root.unwrap_or(Node {
+        value: NodeValue::Null,
+        position: Position { line: 1, column: 1 },
+        plain: None,
+    }))
+}
+
+/// Whether `text` can be written unquoted and still read back as the same string
+pub fn is_plain_string(text: &str) -> bool {
+    !text.contains(['\n', '#'])
+        && text.trim() == text
+        && matches!(parse(text), Ok(Node { value: NodeValue::String(parsed), .. }) if parsed == text)
+}
+
+/// Resolve an unquoted scalar with the YAML 1.2 core schema
+fn resolve_plain(text: &str) -> NodeValue {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => NodeValue::Null,
+        "true" | "True" | "TRUE" => NodeValue::Bool(true),
+        "false" | "False" | "FALSE" => NodeValue::Bool(false),
+        _ => {
+            if let Ok(i) = text.parse::<i64>() {
+                NodeValue::Int(i)
+            } else if text.bytes().any(|b| b.is_ascii_digit())
+                && !text.contains(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E')
+                && let Ok(f) = text.parse::<f64>()
+            {
+                NodeValue::Float(f)
+            } else {
+                NodeValue::String(text.to_string())
+            }
+        }
+    }
+}
+
+/// A collection being filled, with the key waiting for its value
+enum Frame {
+    Sequence { position: Position, anchor: usize, items: Vec<Node> },
+    Mapping { position: Position, anchor: usize, entries: Vec<Entry>, key: Option<(String, Position)> },
+}
+
+#[derive(Default)]
+struct TreeBuilder {
+    stack: Vec<Frame>,
+    anchors: HashMap<usize, Node>,
+    root: Option<Node>,
+}
+
+impl TreeBuilder {
+    fn push_node(&mut self, mut node: Node, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        match self.stack.last_mut() {
+            None => {
+                if self.root.is_none() {
+                    self.root = Some(node);
+                }
+            }
+            Some(Frame::Sequence { items, .. }) => items.push(node),
+            Some(Frame::Mapping { entries, key, .. }) => match key.take() {
+                Some((key, key_position)) => {
+                    // An empty value (`key:`) has no text of its own to point at
+                    if node.plain.as_deref() == Some("") {
+                        node.position = key_position;
+                    }
+                    entries.push(Entry { key, key_position, value: node })
+                }
+                None => {
+                    let text = node.scalar_text().map_or_else(|| node.to_json().to_string(), str::to_string);
+                    *key = Some((text, node.position));
+                }
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for TreeBuilder {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let position = Position::from(marker);
+        match event {
+            Event::Scalar(text, style, anchor, _) => {
+                let node = match style {
+                    TScalarStyle::Plain => Node { value: resolve_plain(&text), position, plain: Some(text) },
+                    _ => Node { value: NodeValue::String(text), position, plain: None },
+                };
+                self.push_node(node, anchor);
+            }
+            Event::Alias(anchor) => {
+                let node = match self.anchors.get(&anchor) {
+                    Some(anchored) => Node { position, ..anchored.clone() },
+                    None => Node { value: NodeValue::Null, position, plain: None },
+                };
+                self.push_node(node, 0);
+            }
+            Event::SequenceStart(anchor, _) => {
+                self.stack.push(Frame::Sequence { position, anchor, items: Vec::new() });
+            }
+            Event::MappingStart(anchor, _) => {
+                self.stack.push(Frame::Mapping { position, anchor, entries: Vec::new(), key: None });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (node, anchor) = match self.stack.pop() {
+                    Some(Frame::Sequence { position, anchor, items }) => {
+                        (Node { value: NodeValue::Sequence(items), position, plain: None }, anchor)
+                    }
+                    Some(Frame::Mapping { position, anchor, entries, .. }) => {
+                        (Node { value: NodeValue::Mapping(entries), position, plain: None }, anchor)
+                    }
+                    None => return,
+                };
+                self.push_node(node, anchor);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! `--out-dir` writes every root schema around one shared definitions file
+
+mod common;
+
+use common::{amper_sources, collect_refs, run_extractor, temp_path};
+use jsonschema::Resource;
+use serde_json::{Value, json};
+use std::path::Path;
+
+const ROOT_FILES: [&str; 3] = ["module-schema.json", "template-schema.json", "project-schema.json"];
+
+fn read_schema(dir: &Path, name: &str) -> Value {
+    let content = std::fs::read_to_string(dir.join(name)).expect("schema file was not written");
+    serde_json::from_str(&content).expect("schema is not valid JSON")
+}
+
+fn generate_bundle(name: &str, extra_args: &[&str]) -> std::path::PathBuf {
+    let dir = temp_path(name);
+    let sources = amper_sources();
+    let mut args = vec!["--source", sources.to_str().unwrap(), "--out-dir", dir.to_str().unwrap()];
+    args.extend_from_slice(extra_args);
+
+    let result = run_extractor(&args);
+    assert!(
+        result.status.success(),
+        "extract-schema failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    dir
+}
+
+#[test]
+fn roots_are_wrappers_around_shared_definitions() {
+    let dir = generate_bundle("bundle", &[]);
+    let definitions = read_schema(&dir, "amper-definitions.json");
+    let defs = definitions["$defs"].as_object().unwrap();
+    for root in ["Module", "Template", "Project", "Settings", "Dependency"] {
+        assert!(defs.contains_key(root), "missing shared definition {}", root);
+    }
+
+    for (file, root) in ROOT_FILES.iter().zip(["Module", "Template", "Project"]) {
+        let schema = read_schema(&dir, file);
+        assert!(schema.get("$defs").is_none(), "{} should not embed definitions", file);
+
+        let mut refs = Vec::new();
+        collect_refs(&schema, &mut refs);
+        assert_eq!(refs, [format!("amper-definitions.json#/$defs/{}", root)]);
+    }
+}
+
+#[test]
+fn wrappers_validate_through_the_definitions_file() {
+    let dir = generate_bundle("bundle-validate", &[]);
+    let definitions = Resource::from_contents(read_schema(&dir, "amper-definitions.json")).unwrap();
+    let validator_for = |file: &str| {
+        jsonschema::options()
+            .with_resource("json-schema:///amper-definitions.json", definitions.clone())
+            .build(&read_schema(&dir, file))
+            .expect("schema does not compile")
+    };
+
+    let module = validator_for("module-schema.json");
+    assert!(module.is_valid(&json!({ "product": "jvm/app", "settings": { "compose": "enabled" } })));
+    assert!(!module.is_valid(&json!({ "product": "no-such-product" })));
+
+    let template = validator_for("template-schema.json");
+    assert!(template.is_valid(&json!({ "settings": { "compose": "enabled" } })));
+    assert!(!template.is_valid(&json!({ "product": "jvm/app" })));
+
+    let project = validator_for("project-schema.json");
+    assert!(project.is_valid(&json!({ "modules": ["app", "libs/*"] })));
+    assert!(!project.is_valid(&json!({ "modules": "app" })));
+}
+
+#[test]
+fn draft07_bundle_uses_definitions() {
+    let dir = generate_bundle("bundle-draft07", &["--dialect", "draft-07"]);
+    let definitions = read_schema(&dir, "amper-definitions.json");
+    assert!(definitions["definitions"]["Module"].is_object());
+
+    let mut refs = Vec::new();
+    collect_refs(&read_schema(&dir, "module-schema.json"), &mut refs);
+    assert_eq!(refs, ["amper-definitions.json#/definitions/Module"]);
+}
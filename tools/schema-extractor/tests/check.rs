@@ -0,0 +1,133 @@
+//! `check` compares a committed schema with a freshly generated one
+
+mod common;
+
+use common::{fixture, generate_schema, run_extractor, temp_path};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Output;
+
+fn run_check(sources: &Path, expected: &Path) -> Output {
+    run_extractor(&[
+        "check",
+        "--source",
+        sources.to_str().unwrap(),
+        "--expected",
+        expected.to_str().unwrap(),
+    ])
+}
+
+/// Write a schema with a different key order and formatting than the generator
+fn write_compact_reversed(schema: &Value, path: &Path) {
+    fn reversed(value: &Value) -> String {
+        match value {
+            Value::Object(obj) => {
+                let entries: Vec<String> = obj
+                    .iter()
+                    .rev()
+                    .map(|(k, v)| format!("{}:{}", Value::String(k.clone()), reversed(v)))
+                    .collect();
+                format!("{{{}}}", entries.join(","))
+            }
+            Value::Array(items) => format!("[{}]", items.iter().map(reversed).collect::<Vec<_>>().join(",")),
+            other => other.to_string(),
+        }
+    }
+    std::fs::write(path, reversed(schema)).unwrap();
+}
+
+#[test]
+fn up_to_date_schema_passes_regardless_of_formatting() {
+    let sources = fixture("diff/old/sources");
+    let schema = generate_schema(&sources, "check-current.json", &[]);
+    let expected = temp_path("check-expected.json");
+    write_compact_reversed(&schema, &expected);
+
+    let result = run_check(&sources, &expected);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+    assert!(String::from_utf8_lossy(&result.stdout).contains("Schema is up to date"));
+}
+
+#[test]
+fn drift_fails_with_a_summary() {
+    let schema = generate_schema(&fixture("diff/old/sources"), "check-stale.json", &[]);
+    let expected = temp_path("check-stale-expected.json");
+    std::fs::write(&expected, serde_json::to_string_pretty(&schema).unwrap()).unwrap();
+
+    let result = run_check(&fixture("diff/new/sources"), &expected);
+    assert_eq!(result.status.code(), Some(1));
+
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("Schema is out of date"));
+    assert!(stderr.contains("- `Settings.kotlinVersion` → `Settings.languageVersion`"));
+    assert!(stderr.contains("~ /$defs/Settings/properties/threads/type"));
+    assert!(stderr.contains("+ /$defs/ComposeSettings"));
+    // The committed file is left untouched
+    assert_eq!(std::fs::read_to_string(&expected).unwrap(), serde_json::to_string_pretty(&schema).unwrap());
+}
+
+#[test]
+fn generation_options_must_match() {
+    let sources = fixture("diff/old/sources");
+    let schema = generate_schema(&sources, "check-draft07.json", &["--dialect", "draft-07"]);
+    let expected = temp_path("check-draft07-expected.json");
+    std::fs::write(&expected, serde_json::to_string(&schema).unwrap()).unwrap();
+
+    assert!(!run_check(&sources, &expected).status.success());
+
+    let result = run_extractor(&[
+        "check",
+        "--source",
+        sources.to_str().unwrap(),
+        "--expected",
+        expected.to_str().unwrap(),
+        "--dialect",
+        "draft-07",
+    ]);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+}
+
+#[test]
+fn out_dir_bundle_is_compared_file_by_file() {
+    let out_dir = temp_path("check-bundle");
+    let _ = std::fs::remove_dir_all(&out_dir);
+    let written = run_extractor(&[
+        "--source",
+        fixture("diff/old/sources").to_str().unwrap(),
+        "--out-dir",
+        out_dir.to_str().unwrap(),
+    ]);
+    assert!(written.status.success(), "{}", String::from_utf8_lossy(&written.stderr));
+
+    let run_bundle_check = |sources: &Path| {
+        run_extractor(&[
+            "check",
+            "--source",
+            sources.to_str().unwrap(),
+            "--out-dir",
+            out_dir.to_str().unwrap(),
+        ])
+    };
+
+    let result = run_bundle_check(&fixture("diff/old/sources"));
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    for file in ["amper-definitions.json", "module-schema.json", "template-schema.json", "project-schema.json"] {
+        assert!(stdout.contains(&format!("Schema is up to date: {}", out_dir.join(file).display())), "{}", stdout);
+    }
+
+    // Definitions drift while the wrappers stay the same
+    let result = run_bundle_check(&fixture("diff/new/sources"));
+    assert_eq!(result.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains(&format!("Schema is out of date: {}", out_dir.join("amper-definitions.json").display())));
+    assert!(stderr.contains("+ /$defs/ComposeSettings"));
+    assert!(!stderr.contains("module-schema.json"), "{}", stderr);
+    assert!(stderr.contains(&format!("--out-dir {}", out_dir.display())));
+
+    std::fs::remove_file(out_dir.join("project-schema.json")).unwrap();
+    let result = run_bundle_check(&fixture("diff/old/sources"));
+    assert_eq!(result.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains(&format!("Schema is missing: {}", out_dir.join("project-schema.json").display())));
+}
@@ -0,0 +1,106 @@
+//! `check-project` reports broken references between the files of a project
+
+mod common;
+
+use common::{fixture, run_extractor, temp_path};
+use std::path::Path;
+
+fn run_check_project(root: &Path) -> std::process::Output {
+    run_extractor(&["check-project", root.to_str().unwrap()])
+}
+
+#[test]
+fn consistent_project_passes() {
+    let result = run_check_project(&fixture("projects/valid"));
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stdout));
+    assert!(String::from_utf8_lossy(&result.stderr).contains("No problems found in 2 modules"));
+}
+
+#[test]
+fn broken_references_are_reported_where_they_are_written() {
+    let root = fixture("projects/broken");
+    let result = run_check_project(&root);
+    assert_eq!(result.status.code(), Some(1));
+
+    let prefix = format!("{}/", root.display());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let problems: Vec<&str> = stdout.lines().map(|line| line.strip_prefix(&prefix).unwrap_or(line)).collect();
+    assert_eq!(
+        problems,
+        [
+            "app/module.yaml:4:5: Template `../common.module-template.yaml` does not exist",
+            "app/module.yaml:5:5: `../templates/base.yaml` is not a module template: its name must end with `.module-template.yaml`",
+            "app/module.yaml:8:5: Dependency `./shared` does not exist",
+            "app/module.yaml:10:11: Dependency `../shared` is not a module: it has no module.yaml",
+            "app/module.yaml:14:5: Dependency `../util` does not exist",
+            "project.yaml:5:5: Pattern `libs/*` matches no module",
+            "project.yaml:6:5: Module `shared` has no module.yaml",
+            "project.yaml:7:5: Module `missing` does not exist",
+            "tools/core/module.yaml:1:1: Module name `core` is already used by `core/module.yaml`",
+        ]
+    );
+    assert!(String::from_utf8_lossy(&result.stderr).contains("9 problems in 3 modules"));
+}
+
+#[test]
+fn build_output_and_syntax_errors() {
+    let root = temp_path("check-project");
+    std::fs::create_dir_all(root.join("app/build/app")).unwrap();
+    std::fs::write(root.join("app/module.yaml"), "product: [jvm/app\n").unwrap();
+    // Copies in build output are neither modules nor duplicates
+    std::fs::write(root.join("app/build/app/module.yaml"), "product: jvm/app\n").unwrap();
+
+    let result = run_check_project(&root);
+    assert_eq!(result.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    assert!(stdout.trim_end().ends_with(": syntax error"), "{}", stdout);
+    assert!(String::from_utf8_lossy(&result.stderr).contains("1 problem in 1 module"));
+}
+
+#[test]
+fn problems_carry_the_id_of_their_check() {
+    let root = fixture("projects/broken");
+    let result = run_extractor(&["check-project", root.to_str().unwrap(), "--format", "sarif"]);
+    assert_eq!(result.status.code(), Some(1));
+
+    let log: serde_json::Value = serde_json::from_slice(&result.stdout).expect("the report is not JSON");
+    let rules: Vec<&str> =
+        log["runs"][0]["results"].as_array().unwrap().iter().map(|r| r["ruleId"].as_str().unwrap()).collect();
+    assert_eq!(
+        rules,
+        [
+            "templates",
+            "templates",
+            "dependencies",
+            "dependencies",
+            "dependencies",
+            "project-modules",
+            "project-modules",
+            "project-modules",
+            "module-names",
+        ]
+    );
+
+    let result = run_extractor(&["check-project", root.to_str().unwrap(), "--format", "json"]);
+    let report: serde_json::Value = serde_json::from_slice(&result.stdout).expect("the report is not JSON");
+    assert_eq!(report["summary"]["errors"], 9);
+}
+
+#[test]
+fn only_modules_of_the_project_need_unique_names() {
+    let root = temp_path("check-project-names");
+    for module in ["app", "libs/core", "examples/core", "samples/app"] {
+        std::fs::create_dir_all(root.join(module)).unwrap();
+        std::fs::write(root.join(module).join("module.yaml"), "product: jvm/app\n").unwrap();
+    }
+    // `examples/core` isn't part of the project; `samples/app` is
+    std::fs::write(root.join("project.yaml"), "modules:\n  - ./app\n  - libs/*\n  - samples/app\n").unwrap();
+
+    let result = run_check_project(&root);
+    assert_eq!(result.status.code(), Some(1));
+    let prefix = format!("{}/", root.display());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let problems: Vec<&str> = stdout.lines().map(|line| line.strip_prefix(&prefix).unwrap_or(line)).collect();
+    assert_eq!(problems, ["samples/app/module.yaml:1:1: Module name `app` is already used by `app/module.yaml`"]);
+}
@@ -0,0 +1,70 @@
+//! Shared helpers for the integration tests
+
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Path to a directory under `tests/fixtures`
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// Amper-like Kotlin sources used as extractor input
+pub fn amper_sources() -> PathBuf {
+    fixture("amper/sources")
+}
+
+/// A fresh path for test output inside Cargo's temporary directory.
+/// Names are made unique so tests running in parallel never share a file.
+pub fn temp_path(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-{}-{}", std::process::id(), id, name))
+}
+
+/// Run the `extract-schema` binary with the given arguments
+pub fn run_extractor(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_extract-schema"))
+        .args(args)
+        .output()
+        .expect("failed to run extract-schema")
+}
+
+/// Generate a schema from `sources` and return the parsed JSON
+pub fn generate_schema(sources: &Path, output_name: &str, extra_args: &[&str]) -> Value {
+    let output = temp_path(output_name);
+    let mut args = vec![
+        "--source",
+        sources.to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+    ];
+    args.extend_from_slice(extra_args);
+
+    let result = run_extractor(&args);
+    assert!(
+        result.status.success(),
+        "extract-schema failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let content = std::fs::read_to_string(&output).expect("schema file was not written");
+    serde_json::from_str(&content).expect("schema is not valid JSON")
+}
+
+/// Collect every `$ref` value in a schema
+pub fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Object(obj) => {
+            if let Some(Value::String(reference)) = obj.get("$ref") {
+                refs.push(reference.clone());
+            }
+            obj.values().for_each(|v| collect_refs(v, refs));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
@@ -0,0 +1,72 @@
+//! Well-known string values get `pattern`/`format` constraints by type and property name
+
+mod common;
+
+use common::{amper_sources, generate_schema};
+use serde_json::{Value, json};
+
+fn module(settings: Value, dependencies: Value) -> Value {
+    json!({
+        "product": "jvm/app",
+        "settings": settings,
+        "dependencies": dependencies
+    })
+}
+
+#[test]
+fn maven_coordinates_are_checked() {
+    let schema = generate_schema(&amper_sources(), "constraints-maven.json", &[]);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    for coordinates in ["io.ktor:ktor-client-core:2.3.0", "org.jetbrains:annotations", "a:b:1.0:sources"] {
+        let valid = module(json!({}), json!([{ "coordinates": coordinates }]));
+        assert!(validator.is_valid(&valid), "{} should be accepted", coordinates);
+    }
+    for coordinates in ["ktor-client-core", "io.ktor:ktor client:2.3.0", "a:b:c:d:e"] {
+        let invalid = module(json!({}), json!([{ "coordinates": coordinates }]));
+        assert!(!validator.is_valid(&invalid), "{} should be rejected", coordinates);
+    }
+
+    // A BOM always pins a version
+    assert!(!validator.is_valid(&module(json!({}), json!([{ "bom": "io.ktor:ktor-bom" }]))));
+    assert!(validator.is_valid(&module(json!({}), json!([{ "bom": "io.ktor:ktor-bom:2.3.0" }]))));
+}
+
+#[test]
+fn identifiers_and_versions_are_checked() {
+    let schema = generate_schema(&amper_sources(), "constraints-ids.json", &[]);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    let valid = json!({
+        "android": { "applicationId": "com.example.app", "namespace": "com.example" },
+        "jvm": { "mainClass": "com.example.MainKt" },
+        "compose": { "enabled": true, "version": "1.7.0-beta01" }
+    });
+    assert!(validator.is_valid(&module(valid, json!([]))));
+
+    for invalid in [
+        json!({ "android": { "applicationId": "app" } }),
+        json!({ "android": { "namespace": "com.example-app" } }),
+        json!({ "jvm": { "mainClass": "com.example.Main Kt" } }),
+        json!({ "compose": { "enabled": true, "version": "latest" } }),
+    ] {
+        assert!(!validator.is_valid(&module(invalid.clone(), json!([]))), "{} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn paths_and_urls_are_constrained() {
+    let schema = generate_schema(&amper_sources(), "constraints-paths.json", &[]);
+    assert_eq!(schema["$defs"]["Repository"]["properties"]["url"]["format"], "uri");
+
+    let validator = jsonschema::options()
+        .should_validate_formats(true)
+        .build(&schema)
+        .expect("schema does not compile");
+    let with_repository = |url: &str| json!({ "product": "jvm/app", "repositories": [{ "url": url }] });
+    assert!(validator.is_valid(&with_repository("https://repo.example.com/maven")));
+    assert!(!validator.is_valid(&with_repository("not a url")));
+
+    assert!(validator.is_valid(&json!({ "product": "jvm/app", "apply": ["../common.module-template.yaml"] })));
+    assert!(!validator.is_valid(&json!({ "product": "jvm/app", "apply": [""] })));
+}
@@ -0,0 +1,95 @@
+//! Generated schemas conform to the meta-schema of the requested dialect
+
+mod common;
+
+use common::{amper_sources, collect_refs, generate_schema};
+use serde_json::{Value, json};
+
+fn generate(dialect: &str) -> Value {
+    generate_schema(
+        &amper_sources(),
+        &format!("dialect-{}.json", dialect),
+        &["--dialect", dialect],
+    )
+}
+
+/// Every `$ref` must point into the dialect's definitions keyword
+fn assert_refs_resolve(schema: &Value, definitions_key: &str) {
+    let definitions = schema[definitions_key]
+        .as_object()
+        .unwrap_or_else(|| panic!("missing '{}'", definitions_key));
+
+    let mut refs = Vec::new();
+    collect_refs(schema, &mut refs);
+    assert!(!refs.is_empty());
+
+    let prefix = format!("#/{}/", definitions_key);
+    for reference in refs {
+        let name = reference
+            .strip_prefix(&prefix)
+            .unwrap_or_else(|| panic!("unexpected reference '{}'", reference));
+        assert!(definitions.contains_key(name), "dangling reference '{}'", reference);
+    }
+}
+
+/// No schema object may combine `$ref` with other keywords
+fn assert_refs_isolated(value: &Value) {
+    match value {
+        Value::Object(obj) => {
+            if obj.contains_key("$ref") {
+                assert_eq!(obj.len(), 1, "$ref with siblings: {}", value);
+            }
+            obj.values().for_each(assert_refs_isolated);
+        }
+        Value::Array(items) => items.iter().for_each(assert_refs_isolated),
+        _ => {}
+    }
+}
+
+#[test]
+fn draft_07_is_valid_against_its_meta_schema() {
+    let schema = generate("draft-07");
+
+    assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+    assert!(schema.get("$defs").is_none());
+    jsonschema::draft7::meta::validate(&schema).expect("invalid draft-07 schema");
+    assert_refs_resolve(&schema, "definitions");
+    assert_refs_isolated(&schema);
+}
+
+#[test]
+fn draft_2019_09_is_valid_against_its_meta_schema() {
+    let schema = generate("2019-09");
+
+    assert_eq!(schema["$schema"], "https://json-schema.org/draft/2019-09/schema");
+    jsonschema::draft201909::meta::validate(&schema).expect("invalid 2019-09 schema");
+    assert_refs_resolve(&schema, "$defs");
+}
+
+#[test]
+fn draft_2020_12_is_valid_against_its_meta_schema() {
+    let schema = generate("2020-12");
+
+    assert_eq!(schema["$schema"], "https://json-schema.org/draft/2020-12/schema");
+    jsonschema::draft202012::meta::validate(&schema).expect("invalid 2020-12 schema");
+    assert_refs_resolve(&schema, "$defs");
+}
+
+#[test]
+fn every_dialect_accepts_the_same_module() {
+    let module = json!({
+        "product": { "type": "jvm/app", "platforms": ["jvm"] },
+        "settings": { "compose": "enabled" }
+    });
+    let invalid = json!({
+        "product": { "type": "jvm/app", "platforms": ["android"] }
+    });
+
+    for dialect in ["draft-07", "2019-09", "2020-12"] {
+        let schema = generate(dialect);
+        let validator = jsonschema::validator_for(&schema)
+            .unwrap_or_else(|e| panic!("{} schema does not compile: {}", dialect, e));
+        assert!(validator.is_valid(&module), "{} rejects a valid module", dialect);
+        assert!(!validator.is_valid(&invalid), "{} accepts an invalid module", dialect);
+    }
+}
@@ -0,0 +1,105 @@
+//! `diff` reports configuration language changes between two versions
+
+mod common;
+
+use common::{fixture, generate_schema, run_extractor, temp_path};
+use serde_json::{Value, json};
+
+fn run_diff(old: &str, new: &str, extra_args: &[&str]) -> String {
+    let mut args = vec!["diff", "--old", old, "--new", new];
+    args.extend_from_slice(extra_args);
+    let result = run_extractor(&args);
+    assert!(
+        result.status.success(),
+        "diff failed: {}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    String::from_utf8(result.stdout).unwrap()
+}
+
+fn source_paths() -> (String, String) {
+    let old = fixture("diff/old/sources");
+    let new = fixture("diff/new/sources");
+    (old.to_str().unwrap().to_owned(), new.to_str().unwrap().to_owned())
+}
+
+#[test]
+fn markdown_report_lists_changes_by_section() {
+    let (old, new) = source_paths();
+    let report = run_diff(&old, &new, &[]);
+
+    let expected = [
+        "## Configuration changes",
+        "### Added",
+        "- Type `ComposeSettings`",
+        "- `Module.compose` (`ComposeSettings?`)",
+        "- `Platform` value `wasm`",
+        "### Removed",
+        "- `Platform` value `ios`",
+        "### Renamed",
+        "- `Settings.kotlinVersion` → `Settings.languageVersion`",
+        "### Changed",
+        "- `Settings.allWarningsAsErrors`: default `false` → `true`",
+        "- `Settings.threads`: type `string` → `integer`",
+        "### Documentation",
+        "- `Settings.legacyFrontend`: Enables the K1 compiler frontend",
+    ];
+    for line in expected {
+        assert!(report.lines().any(|l| l == line), "missing `{}` in:\n{}", line, report);
+    }
+}
+
+#[test]
+fn json_report_is_machine_readable() {
+    let (old, new) = source_paths();
+    let report: Value = serde_json::from_str(&run_diff(&old, &new, &["--format", "json"])).unwrap();
+    let changes = report["changes"].as_array().unwrap();
+
+    assert!(changes.contains(&json!({
+        "kind": "property-renamed",
+        "owner": "Settings",
+        "from": "kotlinVersion",
+        "to": "languageVersion"
+    })));
+    assert!(changes.contains(&json!({
+        "kind": "default-changed",
+        "owner": "Settings",
+        "property": "allWarningsAsErrors",
+        "old": false,
+        "new": true
+    })));
+    assert!(changes.contains(&json!({
+        "kind": "type-changed",
+        "owner": "Settings",
+        "property": "threads",
+        "old": "string",
+        "new": "integer"
+    })));
+    // The rename is not also reported as a removal and an addition
+    assert!(!changes.iter().any(|c| c["property"] == "kotlinVersion"));
+}
+
+#[test]
+fn schema_files_and_directories_can_be_compared() {
+    let old_schema = temp_path("diff-old.json");
+    let old_sources = fixture("diff/old/sources");
+    let new_sources = fixture("diff/new/sources");
+    let generated = generate_schema(&old_sources, "diff-old-module.json", &[]);
+    std::fs::write(&old_schema, serde_json::to_string(&generated).unwrap()).unwrap();
+
+    let new_dir = temp_path("diff-new");
+    let result = run_extractor(&[
+        "--source",
+        new_sources.to_str().unwrap(),
+        "--out-dir",
+        new_dir.to_str().unwrap(),
+    ]);
+    assert!(result.status.success());
+
+    let report = run_diff(old_schema.to_str().unwrap(), new_dir.to_str().unwrap(), &[]);
+    assert!(report.contains("- `Platform` value `wasm`"));
+    assert!(report.contains("- `Settings.threads`: type `string` → `integer`"));
+
+    let unchanged = run_diff(old_schema.to_str().unwrap(), old_schema.to_str().unwrap(), &[]);
+    assert_eq!(unchanged, "## Configuration changes\n\nNo changes.\n");
+}
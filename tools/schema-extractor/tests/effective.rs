@@ -0,0 +1,79 @@
+//! `effective` merges a module with its templates for one platform
+
+mod common;
+
+use common::{amper_sources, fixture, run_extractor};
+use serde_json::{Value, json};
+use std::process::Output;
+
+fn run_effective(extra_args: &[&str]) -> Output {
+    let module = fixture("projects/effective/app/module.yaml");
+    let sources = amper_sources();
+    let mut args = vec!["effective", module.to_str().unwrap(), "--source", sources.to_str().unwrap()];
+    args.extend_from_slice(extra_args);
+    run_extractor(&args)
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn qualified_and_test_sections_override_by_precedence() {
+    let yaml = stdout(&run_effective(&["--platform", "jvm", "--test"]));
+    assert_eq!(
+        yaml,
+        "\
+# Effective test configuration for jvm
+# Merged from: ../shared.module-template.yaml, module.yaml
+settings:
+  jvm:
+    release: 21  # module.yaml:32:14
+    target: 11  # ../shared.module-template.yaml:7:13
+    mainClass: com.example.MainKt  # module.yaml:28:16
+  compose: enabled  # module.yaml:24:12
+dependencies:
+  - io.ktor:ktor-client-core:2.3.0: exported  # module.yaml:13:37
+  - ../core: exported  # module.yaml:12:14
+  - io.ktor:ktor-client-okhttp:2.3.0  # module.yaml:16:5
+  - org.jetbrains.kotlin:kotlin-test:2.0.0  # ../shared.module-template.yaml:14:5
+"
+    );
+}
+
+#[test]
+fn parent_platform_sections_apply_to_leaf_platforms() {
+    let output = stdout(&run_effective(&["--platform", "iosArm64", "--format", "json"]));
+    let effective: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(effective["platform"], "iosArm64");
+    // `settings@ios` of the template replaces the module's `compose: enabled` shorthand
+    assert_eq!(
+        effective["settings"]["compose"],
+        json!({ "enabled": { "value": false, "origin": "../shared.module-template.yaml:11:14" } })
+    );
+    assert_eq!(effective["settings"]["jvm"]["release"]["value"], 17);
+    assert!(effective["settings"]["jvm"].get("mainClass").is_none());
+
+    let dependencies = effective["dependencies"].as_array().unwrap();
+    assert_eq!(dependencies.len(), 3);
+    assert_eq!(
+        dependencies[2],
+        json!({ "value": "io.ktor:ktor-client-darwin:2.3.0", "origin": "module.yaml:19:5" })
+    );
+}
+
+#[test]
+fn platforms_must_exist_and_be_targeted() {
+    let result = run_effective(&["--platform", "wasm"]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("Unknown platform `wasm`. Expected one of: common, jvm"));
+
+    let result = run_effective(&["--platform", "linuxX64"]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("The module doesn't target `linuxX64`"));
+
+    // Intermediate platforms of a target are fine
+    stdout(&run_effective(&["--platform", "apple"]));
+}
@@ -0,0 +1,88 @@
+//! Enums are emitted once as documented definitions that properties reference
+
+mod common;
+
+use amper_schema_extractor::{Error, SchemaOptions, generate_json_schema, parse_sources};
+use common::{amper_sources, collect_refs, fixture, generate_schema};
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// Every `enum` array in `value`
+fn collect_enums<'v>(value: &'v Value, enums: &mut Vec<&'v Value>) {
+    match value {
+        Value::Object(obj) => {
+            if let Some(values) = obj.get("enum") {
+                enums.push(values);
+            }
+            obj.values().for_each(|v| collect_enums(v, enums));
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_enums(v, enums)),
+        _ => {}
+    }
+}
+
+#[test]
+fn enums_are_defined_once_and_referenced() {
+    let schema = generate_schema(&amper_sources(), "enums.json", &[]);
+    let java_version = &schema["$defs"]["JavaVersion"];
+
+    assert_eq!(java_version["enum"], json!(["8", "11", "17", "21"]));
+    assert_eq!(java_version["title"], "JavaVersion");
+    assert_eq!(java_version["description"], "A Java release version.");
+
+    let mut enums = Vec::new();
+    collect_enums(&schema, &mut enums);
+    assert_eq!(enums.iter().filter(|values| ***values == java_version["enum"]).count(), 1);
+
+    let jvm = &schema["$defs"]["JvmSettings"]["properties"];
+    for property in ["release", "target"] {
+        let mut refs = Vec::new();
+        collect_refs(&jvm[property], &mut refs);
+        assert_eq!(refs, ["#/$defs/JavaVersion"], "{property}");
+    }
+}
+
+#[test]
+fn order_sensitivity_is_kept() {
+    let schema = generate_schema(&amper_sources(), "enums-order.json", &[]);
+
+    assert_eq!(schema["$defs"]["JavaVersion"]["x-intellij-enum-order-sensitive"], true);
+    assert_eq!(schema["$defs"]["ProductType"]["x-intellij-enum-order-sensitive"], true);
+    assert!(schema["$defs"]["Platform"].get("x-intellij-enum-order-sensitive").is_none());
+}
+
+#[test]
+fn commented_entries_are_kept() {
+    let schema = generate_schema(&fixture("comments/sources"), "enums-comments.json", &[]);
+    let build_mode = &schema["$defs"]["BuildMode"];
+
+    assert_eq!(build_mode["enum"], json!(["fast", "slow", "safe"]));
+    assert_eq!(
+        build_mode["markdownEnumDescriptions"],
+        json!(["Skips optimizations, for local builds", "", "Adds runtime checks"])
+    );
+}
+
+#[test]
+fn an_enum_named_like_a_class_is_an_error() {
+    // Both are defined by their simple name, so one would replace the other
+    let settings = r#"
+        class Channel : SchemaNode() {
+            val name by value<String>()
+        }
+    "#;
+    let module = r#"
+        enum class Channel(override val schemaValue: String) : SchemaEnum {
+            Stable("stable"),
+        }
+
+        class Module : SchemaNode() {
+            val channel by value<Channel>(Channel.Stable)
+        }
+    "#;
+    let context =
+        parse_sources([(Path::new("settings.kt"), settings), (Path::new("module.kt"), module)], false).unwrap();
+
+    let error = generate_json_schema(&context, "Module", &SchemaOptions::default()).unwrap_err();
+    assert!(matches!(&error, Error::DefinitionClash { name, .. } if name == "Channel"), "{error}");
+}
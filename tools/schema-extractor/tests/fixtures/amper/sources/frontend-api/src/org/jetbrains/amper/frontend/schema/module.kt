@@ -0,0 +1,34 @@
+package org.jetbrains.amper.frontend.schema
+
+abstract class Base : SchemaNode() {
+
+    @SchemaDoc("The list of repositories used to look up and download the module dependencies")
+    val repositories by nullableValue<List<Repository>>()
+
+    @ModifierAware
+    @SchemaDoc("The list of modules and libraries necessary to build the Module")
+    val dependencies by nullableValue<List<Dependency>>()
+
+    @ModifierAware
+    @SchemaDoc("Configures the toolchains used in the build process")
+    val settings by nested<Settings>()
+}
+
+class Module : Base() {
+    @SchemaDoc("Defines what should be produced out of the module. [Read more](https://github.com/JetBrains/amper/blob/main/docs/Documentation.md#product-types)")
+    val product by value<ModuleProduct>()
+
+    @SchemaDoc("Lists the templates applied to the module")
+    val apply by nullableValue<List<Path>>()
+
+    @HiddenFromCompletion
+    val internalFlag by nullableValue<Boolean>()
+}
+
+class Repository : SchemaNode() {
+    @SchemaDoc("The url of the repository")
+    val url by value<String>()
+
+    @SchemaDoc("The ID of the repository")
+    val id by nullableValue<String>()
+}
@@ -0,0 +1,21 @@
+package org.jetbrains.amper.frontend.schema
+
+@SchemaDoc("Platforms supported by Amper")
+enum class Platform(
+    val parent: Platform? = null,
+    val isLeaf: Boolean = false,
+) : SchemaEnum {
+    COMMON,
+    JVM(COMMON, isLeaf = true),
+    ANDROID(COMMON, isLeaf = true),
+    NATIVE(COMMON),
+    APPLE(NATIVE),
+    IOS(APPLE),
+    IOS_ARM64(IOS, isLeaf = true),
+    IOS_SIMULATOR_ARM64(IOS, isLeaf = true),
+    IOS_X64(IOS, isLeaf = true),
+    LINUX(NATIVE),
+    LINUX_X64(LINUX, isLeaf = true);
+
+    override val schemaValue: String = name.doCamelCase()
+}
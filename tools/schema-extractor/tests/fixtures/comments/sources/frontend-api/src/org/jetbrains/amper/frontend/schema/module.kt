@@ -0,0 +1,18 @@
+package org.jetbrains.amper.frontend.schema
+
+class Module : SchemaNode() {
+    @SchemaDoc("How the module is built")
+    val mode by value<BuildMode>(BuildMode.FAST)
+}
+
+enum class BuildMode(override val schemaValue: String) : SchemaEnum {
+    /**
+     * Skips optimizations, for local builds
+     */
+    FAST("fast"),
+    // Optimizes, which takes a while, so CI uses it
+    SLOW("slow"),
+    /* Adds runtime checks, as in "fast, but safe" */
+    @SchemaDoc("Adds runtime checks")
+    SAFE("safe"), // the default on release branches
+}
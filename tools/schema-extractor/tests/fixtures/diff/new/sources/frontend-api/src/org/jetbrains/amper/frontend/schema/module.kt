@@ -0,0 +1,41 @@
+package org.jetbrains.amper.frontend.schema
+
+import org.jetbrains.amper.frontend.api.SchemaDoc
+import org.jetbrains.amper.frontend.api.SchemaEnum
+import org.jetbrains.amper.frontend.api.SchemaNode
+
+enum class Platform(override val schemaValue: String, override val outdated: Boolean = false) : SchemaEnum {
+    JVM("jvm"),
+    ANDROID("android"),
+    WASM("wasm"),
+}
+
+class Module : SchemaNode() {
+    @SchemaDoc("Toolchain settings")
+    val settings by nested<Settings>()
+
+    @SchemaDoc("The platforms the module is built for")
+    val platforms by nullableValue<List<Platform>>()
+
+    @SchemaDoc("Compose Multiplatform settings")
+    val compose by nullableValue<ComposeSettings>()
+}
+
+class Settings : SchemaNode() {
+    @SchemaDoc("Kotlin compiler version")
+    val languageVersion by nullableValue<String>()
+
+    @SchemaDoc("Treat warnings as errors")
+    val allWarningsAsErrors by value(true)
+
+    @SchemaDoc("Number of compiler threads")
+    val threads by value<Int>()
+
+    @SchemaDoc("Enables the K1 compiler frontend")
+    val legacyFrontend by nullableValue<Boolean>()
+}
+
+class ComposeSettings : SchemaNode() {
+    @SchemaDoc("Enables Compose")
+    val enabled by value(false)
+}
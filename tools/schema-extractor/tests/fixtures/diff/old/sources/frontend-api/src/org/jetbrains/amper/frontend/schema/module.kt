@@ -0,0 +1,33 @@
+package org.jetbrains.amper.frontend.schema
+
+import org.jetbrains.amper.frontend.api.SchemaDoc
+import org.jetbrains.amper.frontend.api.SchemaEnum
+import org.jetbrains.amper.frontend.api.SchemaNode
+
+enum class Platform(override val schemaValue: String, override val outdated: Boolean = false) : SchemaEnum {
+    JVM("jvm"),
+    ANDROID("android"),
+    IOS("ios"),
+}
+
+class Module : SchemaNode() {
+    @SchemaDoc("Toolchain settings")
+    val settings by nested<Settings>()
+
+    @SchemaDoc("The platforms the module is built for")
+    val platforms by nullableValue<List<Platform>>()
+}
+
+class Settings : SchemaNode() {
+    @SchemaDoc("Kotlin compiler version")
+    val kotlinVersion by nullableValue<String>()
+
+    @SchemaDoc("Treat warnings as errors")
+    val allWarningsAsErrors by value(false)
+
+    @SchemaDoc("Number of compiler threads")
+    val threads by value<String>()
+
+    @SchemaDoc("Enables the legacy compiler frontend")
+    val legacyFrontend by nullableValue<Boolean>()
+}
@@ -0,0 +1,40 @@
+package org.jetbrains.amper.frontend.schema
+
+sealed class Dependency : SchemaNode()
+
+sealed class ScopedDependency : Dependency() {
+    @SchemaDoc("Which classpaths the dependency is added to")
+    val scope by value(DependencyScope.ALL)
+
+    @SchemaDoc("Whether the dependency is exported to dependent modules")
+    val exported by value(false)
+}
+
+class ExternalMavenDependency : ScopedDependency() {
+    @SchemaDoc("Dependency on a Maven library")
+    val coordinates by value<String>()
+}
+
+class InternalDependency : ScopedDependency() {
+    @SchemaDoc("Dependency on another module in the codebase")
+    val path by nullableValue<Path>()
+}
+
+class CatalogDependency : ScopedDependency() {
+    @SchemaDoc("Dependency from a dependency catalog")
+    val catalogKey by value<String>()
+}
+
+class BomDependency : Dependency() {
+    @SchemaDoc("Dependency on a BOM")
+    val bom by value<String>()
+}
+
+enum class DependencyScope(override val schemaValue: String, override val outdated: Boolean = false) : SchemaEnum {
+    @SchemaDoc("The dependency is available at compile time and runtime")
+    ALL("all"),
+    @SchemaDoc("The dependency is available at compile time only")
+    COMPILE_ONLY("compile-only"),
+    @SchemaDoc("The dependency is available at runtime only")
+    RUNTIME_ONLY("runtime-only"),
+}
@@ -0,0 +1,58 @@
+package org.jetbrains.amper.frontend.schema
+
+@EnumOrderSensitive(reverse = true)
+enum class ProductType(
+    override val schemaValue: String,
+    val supportedPlatforms: Set<Platform>,
+    val defaultPlatforms: Set<Platform>?,
+    override val outdated: Boolean = false,
+) : SchemaEnum {
+    @SchemaDoc("A reusable multiplatform library")
+    LIB(
+        "lib",
+        supportedPlatforms = Platform.leafPlatforms,
+        defaultPlatforms = null
+    ),
+
+    @SchemaDoc("A JVM console or desktop application")
+    JVM_APP(
+        "jvm/app",
+        supportedPlatforms = setOf(Platform.JVM),
+        defaultPlatforms = setOf(Platform.JVM)
+    ),
+
+    @SchemaDoc("An Android VM application")
+    ANDROID_APP(
+        "android/app",
+        supportedPlatforms = setOf(Platform.ANDROID),
+        defaultPlatforms = setOf(Platform.ANDROID)
+    ),
+
+    @SchemaDoc("An iOS application")
+    IOS_APP(
+        "ios/app",
+        supportedPlatforms = setOf(Platform.IOS_ARM64, Platform.IOS_SIMULATOR_ARM64, Platform.IOS_X64),
+        defaultPlatforms = setOf(Platform.IOS_ARM64, Platform.IOS_SIMULATOR_ARM64, Platform.IOS_X64)
+    ),
+
+    @Deprecated("Use `jvm/app` instead", ReplaceWith("JVM_APP"))
+    LEGACY_APP(
+        "legacy/app",
+        supportedPlatforms = setOf(Platform.JVM),
+        defaultPlatforms = setOf(Platform.JVM),
+        outdated = true
+    );
+
+    override fun toString() = schemaValue
+}
+
+@SchemaDoc("Defines what should be produced out of the module")
+class ModuleProduct : SchemaNode() {
+
+    @Shorthand
+    @SchemaDoc("What type of product to generate")
+    val type by value<ProductType>()
+
+    @SchemaDoc("What platforms to generate the product for")
+    val platforms by dependentValue(::type) { it.defaultPlatforms?.toList() ?: emptyList() }
+}
@@ -0,0 +1,61 @@
+package org.jetbrains.amper.frontend.schema
+
+// The shared Amper fixture, plus enum lists for the `enum-order` rule
+
+class Settings : SchemaNode() {
+    @SchemaDoc("JVM platform-specific settings")
+    val jvm: JvmSettings by nested()
+
+    @PlatformSpecific(Platform.ANDROID)
+    @SchemaDoc("Android toolchain and platform settings")
+    val android: AndroidSettings by nested()
+
+    @SchemaDoc("Compose settings")
+    val compose: ComposeSettings by nested()
+}
+
+class JvmSettings : SchemaNode() {
+    @SchemaDoc("The minimum JVM release version")
+    val release by nullableValue<JavaVersion>()
+
+    @SchemaDoc("The fully-qualified name of the class used to run the application (e.g. `com.example.MainKt`). " +
+            "[Read more](https://github.com/JetBrains/amper/blob/main/docs/Documentation.md#jvm)")
+    val mainClass by nullableValue<String>()
+
+    @SchemaDoc("The JVM releases to run the tests on")
+    val testReleases by nullableValue<List<JavaVersion>>()
+
+    @SchemaDoc("The product types the tests are packaged as")
+    val testProducts by nullableValue<List<ProductType>>()
+
+    @Deprecated("Use 'release' instead", ReplaceWith("release"))
+    @SchemaDoc("The JVM target")
+    val target by nullableValue<JavaVersion>()
+}
+
+class AndroidSettings : SchemaNode() {
+    @SchemaDoc("The ID for the application on a device and in the Google Play Store")
+    val applicationId by nullableValue<String>()
+
+    @SchemaDoc("A Kotlin or Java package name for the generated `R` and `BuildConfig` classes")
+    val namespace by nullableValue<String>()
+}
+
+class ComposeSettings : SchemaNode() {
+    @Shorthand
+    @SchemaDoc("Enable Compose runtime, dependencies and the compiler plugins")
+    val enabled by value<Boolean>()
+
+    @SchemaDoc("The Compose plugin version")
+    val version by nullableValue<String>()
+}
+
+@SchemaDoc("A Java release version. [Read more](https://openjdk.org/projects/jdk/)")
+@EnumOrderSensitive
+enum class JavaVersion(override val schemaValue: String, override val outdated: Boolean = false) : SchemaEnum {
+    @SchemaDoc("Java 8 (legacy)")
+    VERSION_8("8"),
+    VERSION_11("11"),
+    VERSION_17("17"),
+    VERSION_21("21");
+}
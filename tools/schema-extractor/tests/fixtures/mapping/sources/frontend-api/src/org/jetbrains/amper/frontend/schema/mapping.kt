@@ -0,0 +1,28 @@
+package org.jetbrains.amper.frontend.schema
+
+import org.jetbrains.amper.frontend.api.SchemaDoc
+import org.jetbrains.amper.frontend.api.SchemaEnum
+import org.jetbrains.amper.frontend.api.SchemaNode
+
+enum class Channel(override val schemaValue: String, override val outdated: Boolean = false) : SchemaEnum {
+    STABLE("stable"),
+    EAP("eap"),
+}
+
+class Module : SchemaNode() {
+
+    @SchemaDoc("Version of the toolchain")
+    val version by value<TraceableVersion>()
+
+    @SchemaDoc("Where the toolchain is downloaded from")
+    val url by nullableValue<Url>()
+
+    @SchemaDoc("Release channel")
+    val channel by value<TraceableEnum<Channel>>()
+
+    @SchemaDoc("Output directory")
+    val output by value<TraceablePath>()
+
+    @SchemaDoc("Number of parallel jobs")
+    val jobs by value<Long>()
+}
@@ -0,0 +1,41 @@
+package org.jetbrains.amper.frontend.schema
+
+class Module : SchemaNode() {
+    @SchemaDoc("Custom tasks of the module")
+    val tasks by nullableValue<Map<String, TaskSettings>>()
+
+    @SchemaDoc("Plugin configuration")
+    val plugin by nested<PluginSettings>()
+
+    @SchemaDoc("A tree of nodes")
+    val tree by nullableValue<NodeSettings>()
+}
+
+// Self-referential
+class TaskSettings : SchemaNode() {
+    @SchemaDoc("Tasks that must run before this one")
+    val dependsOn by nullableValue<List<TaskSettings>>()
+}
+
+// Mutually recursive
+class PluginSettings : SchemaNode() {
+    @SchemaDoc("Options of the plugin")
+    val options by nullableValue<PluginOptionsSettings>()
+}
+
+class PluginOptionsSettings : SchemaNode() {
+    @SchemaDoc("Settings of a nested plugin")
+    val nested by nullableValue<PluginSettings>()
+}
+
+// Recursive through a sealed hierarchy
+sealed class NodeSettings : SchemaNode() {
+}
+
+class LeafSettings : NodeSettings() {
+    val value by value<String>()
+}
+
+class BranchSettings : NodeSettings() {
+    val children by value<List<NodeSettings>>()
+}
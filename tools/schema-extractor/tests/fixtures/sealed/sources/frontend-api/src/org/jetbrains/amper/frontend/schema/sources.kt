@@ -0,0 +1,36 @@
+package org.jetbrains.amper.frontend.schema
+
+class Module : SchemaNode() {
+    @SchemaDoc("Where the sources come from")
+    val source by nullableValue<SourceSettings>()
+
+    val cache by nullableValue<CacheSettings>()
+}
+
+// Variants share every key, but `kind` has a different constant in each
+sealed class SourceSettings : SchemaNode() {
+}
+
+class GitSourceSettings : SourceSettings() {
+    val kind by value("git")
+    val location by value<String>()
+}
+
+class LocalSourceSettings : SourceSettings() {
+    val kind by value("local")
+    val location by value<String>()
+}
+
+// `mode` has a distinct default in each variant, but an instantiated object is no constant
+sealed class CacheSettings : SchemaNode() {
+}
+
+class LocalCacheSettings : CacheSettings() {
+    val mode by value(CacheMode())
+    val directory by value<String>()
+}
+
+class RemoteCacheSettings : CacheSettings() {
+    val mode by value(RemoteCacheMode())
+    val url by value<String>()
+}
@@ -0,0 +1,79 @@
+//! The `vscode` flavor adds the Red Hat YAML extension's keywords; `intellij` leaves them out
+
+mod common;
+
+use amper_schema_extractor::{SchemaOptions, generate_json_schema, parse_sources};
+use common::{amper_sources, generate_schema};
+use serde_json::{Value, json};
+use std::path::Path;
+
+const VSCODE_KEYWORDS: &[&str] =
+    &["markdownDescription", "markdownEnumDescriptions", "deprecationMessage", "defaultSnippets"];
+
+/// Whether `keyword` appears anywhere in `value`
+fn contains_keyword(value: &Value, keyword: &str) -> bool {
+    match value {
+        Value::Object(obj) => obj.contains_key(keyword) || obj.values().any(|v| contains_keyword(v, keyword)),
+        Value::Array(items) => items.iter().any(|v| contains_keyword(v, keyword)),
+        _ => false,
+    }
+}
+
+#[test]
+fn vscode_flavor_adds_markdown_deprecations_and_snippets() {
+    let schema = generate_schema(&amper_sources(), "flavor-vscode.json", &["--flavor", "vscode"]);
+    let defs = &schema["$defs"];
+
+    // Markdown is kept for the hover, plain text goes to `description`
+    let main_class = &defs["JvmSettings"]["properties"]["mainClass"];
+    assert!(main_class["markdownDescription"].as_str().unwrap().contains("`com.example.MainKt`"));
+    assert!(!main_class["description"].as_str().unwrap().contains('`'));
+
+    // One description per value, in the order of `enum`
+    assert_eq!(defs["JavaVersion"]["enum"][0], "8");
+    assert_eq!(defs["JavaVersion"]["markdownEnumDescriptions"][0], "Java 8 (legacy)");
+    assert_eq!(defs["JavaVersion"]["markdownEnumDescriptions"][1], "");
+
+    let target = &defs["JvmSettings"]["properties"]["target"];
+    assert_eq!(target["deprecationMessage"], "Use 'release' instead");
+    assert_eq!(target["doNotSuggest"], true);
+
+    let snippets = &defs["Settings"]["properties"]["jvm"]["defaultSnippets"];
+    assert_eq!(snippets[0]["label"], "jvm");
+    assert!(snippets[0]["body"].is_object(), "{}", snippets);
+}
+
+#[test]
+fn intellij_flavor_omits_the_vscode_keywords() {
+    let schema = generate_schema(&amper_sources(), "flavor-intellij.json", &["--flavor", "intellij"]);
+
+    for keyword in VSCODE_KEYWORDS {
+        assert!(!contains_keyword(&schema, keyword), "`{}` in the intellij flavor", keyword);
+    }
+    let defs = &schema["$defs"];
+    assert_eq!(defs["JvmSettings"]["properties"]["target"]["deprecated"], true);
+    assert_eq!(defs["JavaVersion"]["x-intellij-enum-metadata"]["8"], "Java 8 (legacy)");
+}
+
+#[test]
+fn snippets_include_inherited_required_properties() {
+    let source = r#"
+        class Module : SchemaNode() {
+            val publishing by nested<MavenPublishing>()
+        }
+
+        abstract class PublishingSettings : SchemaNode() {
+            val group by value<String>()
+            val enabled by value(false)
+        }
+
+        class MavenPublishing : PublishingSettings() {
+            val repository by value<String>()
+        }
+    "#;
+    let context = parse_sources([(Path::new("module.kt"), source)], false).unwrap();
+    let schema = generate_json_schema(&context, "Module", &SchemaOptions::default()).unwrap();
+
+    let snippets = &schema["$defs"]["Module"]["properties"]["publishing"]["defaultSnippets"];
+    assert_eq!(snippets[0]["body"], json!({ "repository": "$1", "group": "$2" }));
+}
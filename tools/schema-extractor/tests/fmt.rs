@@ -0,0 +1,110 @@
+//! `fmt` rewrites configuration files in canonical form
+
+mod common;
+
+use common::{amper_sources, run_extractor, temp_path};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+const MESSY: &str = "\
+# Application module
+
+settings@jvm:
+  jvm:
+    release: '21'
+test-settings:
+  jvm: {release: 17}
+dependencies:
+    - '../core'   # local module
+    - io.ktor:ktor-client-core:2.3.0: exported
+
+# Keep these last
+settings:
+  compose: enabled
+product:
+  platforms: [ 'android', jvm ]
+  type: \"jvm/app\"
+";
+
+fn write_module(name: &str, content: &str) -> std::path::PathBuf {
+    let dir = temp_path(name);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("module.yaml");
+    fs::write(&file, content).unwrap();
+    file
+}
+
+fn run_fmt(path: &Path, extra_args: &[&str]) -> Output {
+    let sources = amper_sources();
+    let mut args = vec!["fmt", path.to_str().unwrap(), "--source", sources.to_str().unwrap()];
+    args.extend_from_slice(extra_args);
+    run_extractor(&args)
+}
+
+#[test]
+fn keys_follow_declaration_order_and_comments_are_kept() {
+    let file = write_module("fmt-messy", MESSY);
+    let output = run_fmt(&file, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "\
+# Application module
+
+product:
+  type: jvm/app
+  platforms: [android, jvm]
+dependencies:
+  - ../core # local module
+  - io.ktor:ktor-client-core:2.3.0: exported
+
+# Keep these last
+settings:
+  compose: enabled
+settings@jvm:
+  jvm:
+    release: \"21\"
+test-settings:
+  jvm: {release: 17}
+"
+    );
+
+    // A second run has nothing left to change
+    let output = run_fmt(&file, &["--check"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn check_mode_reports_without_writing() {
+    let file = write_module("fmt-check", MESSY);
+    let output = run_fmt(&file, &["--check"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would reformat"));
+    assert_eq!(fs::read_to_string(&file).unwrap(), MESSY);
+}
+
+#[test]
+fn unsupported_yaml_is_left_unchanged() {
+    let content = "settings: &base\n  compose: enabled\n";
+    let file = write_module("fmt-anchor", content);
+    let output = run_fmt(&file, &[]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 1: anchors, aliases and tags are not supported"), "{stderr}");
+    assert_eq!(fs::read_to_string(&file).unwrap(), content);
+}
+
+#[test]
+fn comments_after_a_dash_before_a_mapping_are_kept() {
+    let content = "product: jvm/app\ndependencies:\n  - # the core library\n    ./core: exported\n";
+    let file = write_module("fmt-dash-comment", content);
+    let output = run_fmt(&file, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(fs::read_to_string(&file).unwrap(), content);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Formatted"));
+}
@@ -0,0 +1,27 @@
+//! `@HiddenFromCompletion` properties stay valid but are not suggested, unless dropped
+
+mod common;
+
+use common::{amper_sources, generate_schema};
+use serde_json::json;
+
+#[test]
+fn hidden_properties_are_kept_as_do_not_suggest() {
+    let schema = generate_schema(&amper_sources(), "hidden-kept.json", &[]);
+
+    let flag = &schema["$defs"]["Module"]["properties"]["internalFlag"];
+    assert_eq!(flag["doNotSuggest"], true, "{}", flag);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+    assert!(validator.is_valid(&json!({ "product": "jvm/app", "internalFlag": true })));
+}
+
+#[test]
+fn drop_hidden_removes_them() {
+    let schema = generate_schema(&amper_sources(), "hidden-dropped.json", &["--drop-hidden"]);
+
+    let properties = schema["$defs"]["Module"]["properties"].as_object().unwrap();
+    assert!(!properties.contains_key("internalFlag"));
+    assert!(properties.contains_key("product"));
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+    assert!(!validator.is_valid(&json!({ "product": "jvm/app", "internalFlag": true })));
+}
@@ -0,0 +1,128 @@
+//! The extractor embedded as a library: parsing, the serializable model and typed errors
+
+mod common;
+
+use amper_schema_extractor::mapping::TypeMapping;
+use amper_schema_extractor::{
+    Error, Output, ParsingContext, SchemaOptions, check, extract, generate_json_schema, lint, load_context,
+    parse_sources, resolve_root_type, write_schema,
+};
+use common::{amper_sources, fixture, temp_path};
+use std::path::Path;
+
+#[test]
+fn parsed_model_round_trips_through_json() {
+    let context = load_context(&amper_sources(), false).expect("failed to parse the sources");
+    let json = serde_json::to_string(&context).unwrap();
+    let restored: ParsingContext = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.classes.keys().collect::<Vec<_>>(), context.classes.keys().collect::<Vec<_>>());
+    assert_eq!(restored.enums.keys().collect::<Vec<_>>(), context.enums.keys().collect::<Vec<_>>());
+    let options = SchemaOptions::default();
+    assert_eq!(
+        generate_json_schema(&restored, "Module", &options).unwrap(),
+        generate_json_schema(&context, "Module", &options).unwrap(),
+    );
+}
+
+#[test]
+fn sources_can_be_parsed_from_memory() {
+    let source = r#"
+        enum class Channel(override val schemaValue: String) : SchemaEnum {
+            Stable("stable"),
+            Beta("beta"),
+        }
+
+        class Module : SchemaNode() {
+            @SchemaDoc("Release channel")
+            val channel by value<Channel>(Channel.Stable)
+        }
+    "#;
+    let context = parse_sources([(Path::new("module.kt"), source)], false).unwrap();
+
+    let schema = generate_json_schema(&context, "Module", &SchemaOptions::default()).unwrap();
+    assert_eq!(schema["$defs"]["Module"]["properties"]["channel"]["$ref"], "#/$defs/Channel");
+    assert_eq!(schema["$defs"]["Channel"]["enum"], serde_json::json!(["stable", "beta"]));
+}
+
+#[test]
+fn failures_are_typed() {
+    let error = load_context(&fixture("mapping"), false).unwrap_err();
+    assert!(matches!(error, Error::InvalidSources { .. }), "{error}");
+
+    let broken = "class Module : SchemaNode() {\n    val name by value<String>()\n";
+    let error = parse_sources([(Path::new("broken.kt"), broken)], false).unwrap_err();
+    assert!(matches!(&error, Error::Kotlin { path, .. } if path == Path::new("broken.kt")), "{error}");
+
+    let context = load_context(&amper_sources(), false).unwrap();
+    let error = generate_json_schema(&context, "Nothing", &SchemaOptions::default()).unwrap_err();
+    assert!(matches!(&error, Error::UnknownType { name, is_enum: false } if name == "Nothing"), "{error}");
+    let error = resolve_root_type(&context, "Platform").unwrap_err();
+    assert!(matches!(error, Error::UnknownType { is_enum: true, .. }), "{error}");
+    let error = lint::enabled_rules(&["no-such-rule".to_string()]).unwrap_err();
+    assert!(matches!(&error, Error::UnknownRule(id) if id == "no-such-rule"), "{error}");
+
+    let mapping = temp_path("invalid-mapping.toml");
+    std::fs::write(&mapping, "[types.LocalDate]\ntype = \"date\"\n").unwrap();
+    let error = TypeMapping::load(&mapping).unwrap_err();
+    assert!(matches!(error, Error::TypeMapping { .. }), "{error}");
+    assert!(error.to_string().contains("unsupported type 'date'"));
+
+    let error = TypeMapping::load(&temp_path("missing.toml")).unwrap_err();
+    assert!(matches!(&error, Error::Io { path: Some(_), .. }), "{error}");
+    let io_error = std::fs::read(temp_path("missing.toml")).unwrap_err();
+    assert!(error.to_string().ends_with(&format!(": {}", io_error)), "{error}");
+}
+
+#[test]
+fn nested_objects_have_no_default_but_are_optional() {
+    let context = load_context(&amper_sources(), false).unwrap();
+    let jvm = context.classes["Settings"].properties.iter().find(|p| p.name == "jvm").unwrap();
+    assert!(jvm.is_nested);
+    assert_eq!(jvm.default_value, None);
+
+    let schema = generate_json_schema(&context, "Module", &SchemaOptions::default()).unwrap();
+    let settings = &schema["$defs"]["Settings"];
+    assert!(settings["properties"]["jvm"].get("default").is_none());
+    let required = settings["required"].as_array().cloned().unwrap_or_default();
+    assert!(!required.contains(&serde_json::json!("jvm")), "{required:?}");
+}
+
+#[test]
+fn committed_schemas_are_checked_against_the_sources() {
+    let context = load_context(&amper_sources(), false).unwrap();
+    let options = SchemaOptions::default();
+    let expected = temp_path("library-check.json");
+    let _ = std::fs::remove_file(&expected);
+
+    let missing = check::check_schema(&context, "module", &expected, &options).unwrap();
+    assert!(matches!(missing.status, check::Status::Missing), "{missing}");
+
+    let schema = generate_json_schema(&context, "Module", &options).unwrap();
+    write_schema(&expected, &schema).unwrap();
+    assert!(check::check_schema(&context, "module", &expected, &options).unwrap().is_up_to_date());
+
+    let mut stale = schema.clone();
+    stale["$defs"].as_object_mut().unwrap().remove("Settings");
+    write_schema(&expected, &stale).unwrap();
+    let drifted = check::check_schema(&context, "module", &expected, &options).unwrap();
+    let check::Status::OutOfDate { changes, paths } = &drifted.status else { panic!("{drifted}") };
+    assert!(!changes.is_empty());
+    assert!(paths.iter().any(|path| path.contains("Settings")), "{paths:?}");
+}
+
+#[test]
+fn written_bundles_pass_the_check() {
+    let context = load_context(&amper_sources(), false).unwrap();
+    let options = SchemaOptions::default();
+    let out_dir = temp_path("library-bundle");
+
+    let written = extract(&context, &Output::Bundle(&out_dir), &options, false).unwrap();
+    assert_eq!(written.len(), 4, "{written:?}");
+
+    let target = check::Target::Bundle(&out_dir);
+    let checks = check::run(&context, &target, &options).unwrap();
+    assert_eq!(checks.len(), written.len());
+    assert!(checks.iter().all(check::FileCheck::is_up_to_date));
+    assert_eq!(target.regenerate_args(), format!("--out-dir {}", out_dir.display()));
+}
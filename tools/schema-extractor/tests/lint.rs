@@ -0,0 +1,76 @@
+//! `lint` reports mistakes that are valid per the schema
+
+mod common;
+
+use common::{fixture, run_extractor};
+use std::process::Output;
+
+fn run_lint(extra_args: &[&str]) -> Output {
+    let project = fixture("projects/lint");
+    let sources = fixture("lint/sources");
+    let mut args = vec!["lint", project.to_str().unwrap(), "--source", sources.to_str().unwrap()];
+    args.extend_from_slice(extra_args);
+    run_extractor(&args)
+}
+
+/// Diagnostics with the fixture directory stripped from their paths
+fn diagnostics(output: &Output) -> Vec<String> {
+    let prefix = format!("{}/", fixture("projects/lint").display());
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.replace(&prefix, ""))
+        .collect()
+}
+
+#[test]
+fn rules_report_with_id_and_severity() {
+    let output = run_lint(&[]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        diagnostics(&output),
+        [
+            "app/module.yaml:4:3: error[platform-specific-setting]: `android` only applies to android, but `settings` applies to jvm",
+            "app/module.yaml:7:24: warning[enum-order]: `11` is listed after `21`; `JavaVersion` values go in declaration order",
+            "app/module.yaml:8:29: warning[enum-order]: `android/app` is listed after `jvm/app`; `ProductType` values go in reverse declaration order",
+            "app/module.yaml:10:1: warning[untargeted-qualifier]: `settings@ios` applies to none of the module's platforms: jvm",
+            // `server` is an alias declared by the template app/module.yaml applies
+            "app/module.yaml:16:3: error[platform-specific-setting]: `android` only applies to android, but `settings@server` applies to jvm",
+            "lib/module.yaml:9:3: error[platform-specific-setting]: `android` only applies to android, but `settings@jvm` applies to jvm",
+        ]
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("README.md#enum-order"), "{stderr}");
+    assert!(stderr.contains("3 errors and 3 warnings in 3 files"), "{stderr}");
+}
+
+#[test]
+fn disabled_rules_are_skipped() {
+    let output = run_lint(&["--disable", "platform-specific-setting,enum-order"]);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        diagnostics(&output),
+        ["app/module.yaml:10:1: warning[untargeted-qualifier]: `settings@ios` applies to none of the module's platforms: jvm"]
+    );
+
+    let output = run_lint(&["--disable", "platform-specific-setting", "--deny-warnings"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = run_lint(&["--disable", "no-such-rule"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown lint rule `no-such-rule`"));
+}
+
+#[test]
+fn reversed_enums_are_checked_against_reverse_declaration_order() {
+    let output = run_lint(&["--disable", "platform-specific-setting,untargeted-qualifier"]);
+
+    // `ProductType` is `@EnumOrderSensitive(reverse = true)`: lib/module.yaml lists
+    // `android/app, jvm/app, lib` in reverse declaration order and passes
+    let reversed: Vec<String> =
+        diagnostics(&output).into_iter().filter(|line| line.contains("`ProductType`")).collect();
+    assert_eq!(
+        reversed,
+        ["app/module.yaml:8:29: warning[enum-order]: `android/app` is listed after `jvm/app`; `ProductType` values go in reverse declaration order"]
+    );
+}
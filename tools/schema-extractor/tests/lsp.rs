@@ -0,0 +1,319 @@
+//! The `amper-lsp` language server, driven in-process by a minimal client
+
+mod common;
+
+use amper_schema_extractor::lsp::{Server, read_message, run, write_message};
+use amper_schema_extractor::{load_context, schema::SchemaOptions};
+use common::{amper_sources, fixture};
+use serde_json::{Value, json};
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A client holding the server, collecting the notifications it sends
+struct Client {
+    server: Server,
+    next_id: i64,
+    notifications: Vec<Value>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let context = load_context(&amper_sources(), false).expect("failed to parse the sources");
+        let server = Server::new(context, SchemaOptions::default()).expect("failed to start the server");
+        let mut client = Client { server, next_id: 0, notifications: Vec::new() };
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(result["serverInfo"]["name"], "amper-lsp");
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    /// Send a request and return its response
+    fn send(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        let mut response = None;
+        for reply in self.server.handle(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })) {
+            if reply["id"] == id {
+                response = Some(reply);
+            } else {
+                self.notifications.push(reply);
+            }
+        }
+        response.unwrap_or_else(|| panic!("no response to {}", method))
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let response = self.send(method, params);
+        assert!(response.get("error").is_none(), "{} failed: {}", method, response);
+        response["result"].clone()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        let replies = self.server.handle(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+        self.notifications.extend(replies);
+    }
+
+    /// Open `text` as the file at `path`, returning the diagnostics published for it
+    fn open(&mut self, path: &Path, text: &str) -> (String, Vec<Value>) {
+        let uri = format!("file://{}", path.display());
+        let document = json!({ "uri": uri, "languageId": "yaml", "version": 1, "text": text });
+        self.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        let diagnostics = self.diagnostics(&uri);
+        (uri, diagnostics)
+    }
+
+    fn change(&mut self, uri: &str, version: i64, text: &str) -> Vec<Value> {
+        let params = json!({ "textDocument": { "uri": uri, "version": version }, "contentChanges": [{ "text": text }] });
+        self.notify("textDocument/didChange", params);
+        self.diagnostics(uri)
+    }
+
+    /// The last diagnostics published for `uri`
+    fn diagnostics(&self, uri: &str) -> Vec<Value> {
+        let published = self
+            .notifications
+            .iter()
+            .rev()
+            .find(|n| n["method"] == "textDocument/publishDiagnostics" && n["params"]["uri"] == uri)
+            .expect("no diagnostics were published");
+        published["params"]["diagnostics"].as_array().unwrap().clone()
+    }
+
+    fn at(&mut self, method: &str, uri: &str, line: u64, character: u64) -> Value {
+        let params = json!({ "textDocument": { "uri": uri }, "position": { "line": line, "character": character } });
+        self.request(method, params)
+    }
+
+    /// Labels of the completion items at a position
+    fn complete(&mut self, uri: &str, line: u64, character: u64) -> Vec<String> {
+        let result = self.at("textDocument/completion", uri, line, character);
+        result["items"].as_array().unwrap().iter().map(|item| item["label"].as_str().unwrap().to_string()).collect()
+    }
+}
+
+fn module(dir: &str) -> std::path::PathBuf {
+    fixture("projects/lsp").join(dir).join("module.yaml")
+}
+
+#[test]
+fn diagnostics_follow_edits_and_fixes_become_code_actions() {
+    let mut client = Client::start();
+    let text = "product: jvm/app\n\nsettings:\n  android:\n    namespace: com.example\n  jvm:\n    relase: 17\n";
+    let (uri, diagnostics) = client.open(&module("app"), text);
+
+    let codes: Vec<&str> = diagnostics.iter().map(|d| d["code"].as_str().unwrap()).collect();
+    assert_eq!(codes, ["platform-specific-setting", "unknown-property"]);
+    assert_eq!(diagnostics[0]["range"], json!({ "start": { "line": 3, "character": 2 }, "end": { "line": 3, "character": 9 } }));
+    assert!(diagnostics[0]["codeDescription"]["href"].as_str().unwrap().ends_with("README.md#platform-specific-setting"));
+    let key = &diagnostics[1];
+    assert_eq!(key["severity"], 1);
+    assert_eq!(key["range"], json!({ "start": { "line": 6, "character": 4 }, "end": { "line": 6, "character": 10 } }));
+
+    // The client sends the diagnostic back to get its fix
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "range": key["range"],
+        "context": { "diagnostics": [key] },
+    });
+    let actions = client.request("textDocument/codeAction", params);
+    assert_eq!(actions[0]["kind"], "quickfix");
+    assert_eq!(actions[0]["title"], "Replace `relase` with `release`");
+    assert_eq!(actions[0]["edit"]["changes"][&uri], json!([{ "range": key["range"], "newText": "release" }]));
+
+    let diagnostics = client.change(&uri, 2, "product: jvm/app\n");
+    assert_eq!(diagnostics, Vec::<Value>::new());
+    let diagnostics = client.change(&uri, 3, "product: [jvm\n");
+    assert_eq!(diagnostics[0]["code"], "syntax-error");
+}
+
+#[test]
+fn deprecated_names_are_migrated_by_a_source_action() {
+    let mut client = Client::start();
+    let text = "product: jvm/app\n\nsettings:\n  jvm:\n    target: 17 # LTS\n";
+    let (uri, _) = client.open(&module("app"), text);
+
+    let params = json!({
+        "textDocument": { "uri": uri },
+        "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+        "context": { "diagnostics": [] },
+    });
+    let actions = client.request("textDocument/codeAction", params);
+    let migration = actions.as_array().unwrap().iter().find(|a| a["kind"] == "source.fixAll").expect("no migration");
+    assert_eq!(migration["title"], "Migrate 1 deprecated name");
+    assert_eq!(
+        migration["edit"]["changes"][&uri][0]["newText"],
+        "product: jvm/app\n\nsettings:\n  jvm:\n    release: 17 # LTS\n"
+    );
+}
+
+#[test]
+fn completes_keys_values_and_qualifiers() {
+    let mut client = Client::start();
+    let text = "product: \n\nsettings:\n  jvm:\n    rel\n    release: \n  compose: \nsettings@\n\n";
+    let (uri, _) = client.open(&module("app"), text);
+
+    let root = client.complete(&uri, 8, 0);
+    assert!(root.contains(&"settings".to_string()) && root.contains(&"product".to_string()), "{:?}", root);
+    assert!(!root.contains(&"internalFlag".to_string()), "hidden properties are offered: {:?}", root);
+
+    let jvm = client.at("textDocument/completion", &uri, 4, 7);
+    let release = jvm["items"].as_array().unwrap().iter().find(|item| item["label"] == "release").expect("no release");
+    assert_eq!(release["detail"], "JavaVersion?");
+    assert_eq!(release["documentation"]["value"], "The minimum JVM release version");
+    assert_eq!(
+        release["textEdit"],
+        json!({
+            "range": { "start": { "line": 4, "character": 4 }, "end": { "line": 4, "character": 7 } },
+            "newText": "release: "
+        })
+    );
+    let target = jvm["items"].as_array().unwrap().iter().find(|item| item["label"] == "target").unwrap();
+    assert_eq!(target["tags"], json!([1]));
+
+    // Product types through the `@Shorthand` type of `product`, enum values, `@Shorthand` booleans
+    let products = client.complete(&uri, 0, 9);
+    assert!(products.contains(&"jvm/app".to_string()) && products.contains(&"lib".to_string()), "{:?}", products);
+    assert_eq!(client.complete(&uri, 5, 13), ["8", "11", "17", "21"]);
+    assert_eq!(client.complete(&uri, 6, 11), ["enabled"]);
+
+    let qualified = client.complete(&uri, 7, 9);
+    assert!(qualified.contains(&"settings@jvm".to_string()) && qualified.contains(&"settings@iosArm64".to_string()), "{:?}", qualified);
+}
+
+#[test]
+fn completes_platforms_aliases_and_flow_lists() {
+    let mut client = Client::start();
+    let text = "product:\n  type: lib\n  platforms: [jvm, ]\n\naliases:\n  - jvmAndAndroid: [jvm, android]\n\ndependencies@\n";
+    let (uri, _) = client.open(&module("lib"), text);
+
+    let platforms = client.complete(&uri, 2, 18);
+    assert!(platforms.contains(&"android".to_string()) && platforms.contains(&"linuxX64".to_string()), "{:?}", platforms);
+    let qualified = client.complete(&uri, 7, 13);
+    assert!(qualified.contains(&"dependencies@jvmAndAndroid".to_string()), "{:?}", qualified);
+}
+
+#[test]
+fn completes_local_modules_catalog_references_and_templates() {
+    let mut client = Client::start();
+    let text = "product: jvm/app\n\napply:\n  - \n\ndependencies:\n  - \n  - path: \n";
+    let (uri, _) = client.open(&module("app"), text);
+
+    let items = client.complete(&uri, 6, 4);
+    for expected in ["../lib", "$libs.ktor.client.core", "$libs.kotlinx.coroutines", "coordinates"] {
+        assert!(items.contains(&expected.to_string()), "{} missing from {:?}", expected, items);
+    }
+    assert!(!items.contains(&"../app".to_string()), "the module itself is offered: {:?}", items);
+    assert_eq!(client.complete(&uri, 7, 10), ["../lib"]);
+    assert_eq!(client.complete(&uri, 3, 4), ["../common.module-template.yaml"]);
+
+    let catalog = client.at("textDocument/completion", &uri, 6, 4);
+    let ktor = catalog["items"].as_array().unwrap().iter().find(|i| i["label"] == "$libs.ktor.client.core").unwrap();
+    assert_eq!(ktor["detail"], "io.ktor:ktor-client-core");
+}
+
+#[test]
+fn hover_shows_schema_docs() {
+    let mut client = Client::start();
+    let text = "product: jvm/app\n\nsettings:\n  jvm:\n    release: 8\n";
+    let (uri, _) = client.open(&module("app"), text);
+
+    let key = client.at("textDocument/hover", &uri, 4, 6);
+    assert_eq!(key["contents"]["value"], "**release**: `JavaVersion?`\n\nThe minimum JVM release version");
+    assert_eq!(key["range"], json!({ "start": { "line": 4, "character": 4 }, "end": { "line": 4, "character": 11 } }));
+    let value = client.at("textDocument/hover", &uri, 4, 13);
+    assert_eq!(value["contents"]["value"], "**8**: `JavaVersion`\n\nJava 8 (legacy)");
+    let product = client.at("textDocument/hover", &uri, 0, 11);
+    assert_eq!(product["contents"]["value"], "**jvm/app**: `ProductType`\n\nA JVM console or desktop application");
+    assert_eq!(client.at("textDocument/hover", &uri, 1, 0), Value::Null);
+}
+
+#[test]
+fn local_dependencies_go_to_their_module() {
+    let mut client = Client::start();
+    let text = std::fs::read_to_string(module("app")).unwrap();
+    let (uri, _) = client.open(&module("app"), &text);
+
+    let location = client.at("textDocument/definition", &uri, 3, 6);
+    assert!(location["uri"].as_str().unwrap().ends_with("projects/lsp/lib/module.yaml"), "{}", location);
+    assert_eq!(client.at("textDocument/definition", &uri, 4, 6), Value::Null);
+}
+
+#[test]
+fn follows_the_protocol_lifecycle() {
+    let context = load_context(&amper_sources(), false).unwrap();
+    let mut server = Server::new(context, SchemaOptions::default()).unwrap();
+    let request = |id: i64, method: &str| json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": {} });
+
+    assert_eq!(server.handle(&request(1, "textDocument/hover"))[0]["error"]["code"], -32002);
+    assert!(server.handle(&request(2, "initialize"))[0]["result"]["capabilities"]["hoverProvider"].as_bool().unwrap());
+    assert_eq!(server.handle(&request(3, "workspace/symbol"))[0]["error"]["code"], -32601);
+    assert_eq!(server.handle(&request(4, "shutdown"))[0]["result"], Value::Null);
+    assert_eq!(server.exited(), None);
+    server.handle(&json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exited(), Some(true));
+}
+
+#[test]
+fn malformed_messages_get_a_parse_error_and_are_skipped() {
+    let context = load_context(&amper_sources(), false).unwrap();
+    let mut server = Server::new(context, SchemaOptions::default()).unwrap();
+
+    let mut input = Vec::new();
+    input.extend_from_slice(b"Content-Length: 9\r\n\r\nnot json!");
+    // The body of a message with a bad length runs into the next header
+    input.extend_from_slice(b"Content-Length: many\r\n\r\n{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"shutdown\"}");
+    for message in [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "capabilities": {} } }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ] {
+        write_message(&mut input, &message).unwrap();
+    }
+    let mut output = Vec::new();
+    assert!(run(&mut server, &mut input.as_slice(), &mut output).unwrap());
+
+    let mut reader = BufReader::new(output.as_slice());
+    let mut replies = Vec::new();
+    while let Some(reply) = read_message(&mut reader).unwrap() {
+        replies.push(reply);
+    }
+    assert_eq!(replies.len(), 4, "{replies:#?}");
+    for (reply, message) in replies.iter().zip(["Message is not JSON", "Invalid Content-Length header: many"]) {
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], -32700);
+        assert!(reply["error"]["message"].as_str().unwrap().starts_with(message), "{reply}");
+    }
+    assert!(replies[2]["result"]["capabilities"].is_object());
+    assert_eq!(replies[3], json!({ "jsonrpc": "2.0", "id": 2, "result": null }));
+}
+
+#[test]
+fn binary_speaks_lsp_over_stdio() {
+    let sources = amper_sources();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_amper-lsp"))
+        .args(["--source", sources.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run amper-lsp");
+
+    let mut input = Vec::new();
+    for message in [
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "capabilities": {} } }),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ] {
+        write_message(&mut input, &message).unwrap();
+    }
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let mut reader = BufReader::new(output.stdout.as_slice());
+    let initialize = read_message(&mut reader).unwrap().expect("no initialize response");
+    assert_eq!(initialize["result"]["capabilities"]["textDocumentSync"]["change"], 1);
+    let shutdown = read_message(&mut reader).unwrap().expect("no shutdown response");
+    assert_eq!(shutdown, json!({ "jsonrpc": "2.0", "id": 2, "result": null }));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
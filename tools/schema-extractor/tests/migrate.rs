@@ -0,0 +1,87 @@
+//! `migrate` rewrites deprecated keys and outdated values in place
+
+mod common;
+
+use common::{amper_sources, run_extractor, temp_path};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+const OUTDATED: &str = "\
+# The app
+product:
+  type: 'legacy/app'   # renamed in Amper 0.5
+
+settings:
+  jvm:
+    target: 17  # keep this one
+    mainClass: com.example.MainKt
+";
+
+fn write_module(name: &str, content: &str) -> PathBuf {
+    let dir = temp_path(name);
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("module.yaml");
+    fs::write(&file, content).unwrap();
+    file
+}
+
+fn run_migrate(path: &Path, extra_args: &[&str]) -> Output {
+    let sources = amper_sources();
+    let mut args = vec!["migrate", path.to_str().unwrap(), "--source", sources.to_str().unwrap()];
+    args.extend_from_slice(extra_args);
+    run_extractor(&args)
+}
+
+#[test]
+fn replacements_keep_comments_and_quotes() {
+    let file = write_module("migrate-outdated", OUTDATED);
+    let output = run_migrate(&file, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "\
+# The app
+product:
+  type: 'jvm/app'   # renamed in Amper 0.5
+
+settings:
+  jvm:
+    release: 17  # keep this one
+    mainClass: com.example.MainKt
+"
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("module.yaml:3:9: Replaced `legacy/app` with `jvm/app`"), "{stdout}");
+    assert!(stdout.contains("module.yaml:7:5: Renamed `target` to `release`"), "{stdout}");
+}
+
+#[test]
+fn dry_run_prints_a_diff_without_writing() {
+    let file = write_module("migrate-dry-run", "product: legacy/app\n");
+    let output = run_migrate(&file, &["--dry-run"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let path = file.display();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("--- {path}\n+++ {path}\n@@ -1,1 +1,1 @@\n-product: legacy/app\n+product: jvm/app\n")
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "product: legacy/app\n");
+}
+
+#[test]
+fn conflicting_replacements_are_left_for_a_manual_fix() {
+    let content = "settings@jvm:\n  jvm:\n    target: 11\n    release: 17\n";
+    let file = write_module("migrate-conflict", content);
+    let output = run_migrate(&file, &[]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("module.yaml:3:5: Property `target` is deprecated, but `release` is already set"),
+        "{stderr}"
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), content);
+}
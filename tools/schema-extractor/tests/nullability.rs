@@ -0,0 +1,46 @@
+//! Explicit YAML `null` is accepted only for nullable properties
+
+mod common;
+
+use amper_schema_extractor::{Error, SchemaOptions, generate_json_schema, parse_sources};
+use common::{amper_sources, generate_schema};
+use serde_json::json;
+use std::path::Path;
+
+#[test]
+fn nullable_properties_accept_null_and_others_reject_it() {
+    let schema = generate_schema(&amper_sources(), "nullability.json", &[]);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    // `mainClass` and `release` are declared with `nullableValue`
+    let with_nulls = json!({
+        "product": "jvm/app",
+        "settings": { "jvm": { "mainClass": null, "release": null } }
+    });
+    assert!(validator.is_valid(&with_nulls));
+
+    // `settings` is a nested, non-nullable object
+    let null_settings = json!({ "product": "jvm/app", "settings": null });
+    assert!(!validator.is_valid(&null_settings));
+
+    let non_null = &schema["$defs"]["NonNull"];
+    assert!(non_null["errorMessage"].as_str().unwrap().contains("null"));
+}
+
+#[test]
+fn a_class_named_like_the_non_null_definition_is_an_error() {
+    let source = r#"
+        class NonNull : SchemaNode() {
+            val value by nullableValue<String>()
+        }
+
+        class Module : SchemaNode() {
+            val name by value<String>()
+            val marker by value<NonNull>()
+        }
+    "#;
+    let context = parse_sources([(Path::new("module.kt"), source)], false).unwrap();
+
+    let error = generate_json_schema(&context, "Module", &SchemaOptions::default()).unwrap_err();
+    assert!(matches!(&error, Error::DefinitionClash { name, .. } if name == "NonNull"), "{error}");
+}
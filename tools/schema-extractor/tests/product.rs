@@ -0,0 +1,51 @@
+//! `product` accepts its `@Shorthand` type, and `platforms` is limited per product type
+
+mod common;
+
+use common::{amper_sources, generate_schema};
+use serde_json::{Value, json};
+
+fn validator() -> jsonschema::Validator {
+    let schema = generate_schema(&amper_sources(), "product.json", &[]);
+    jsonschema::validator_for(&schema).expect("schema does not compile")
+}
+
+fn module(product: Value) -> Value {
+    json!({ "product": product })
+}
+
+#[test]
+fn product_can_be_written_as_its_type() {
+    let validator = validator();
+
+    assert!(validator.is_valid(&module(json!("jvm/app"))));
+    assert!(validator.is_valid(&module(json!({ "type": "jvm/app" }))));
+    assert!(!validator.is_valid(&module(json!("jvm/application"))));
+}
+
+#[test]
+fn platforms_are_limited_by_the_product_type() {
+    let validator = validator();
+
+    assert!(validator.is_valid(&module(json!({ "type": "ios/app", "platforms": ["iosArm64", "iosX64"] }))));
+    assert!(!validator.is_valid(&module(json!({ "type": "ios/app", "platforms": ["jvm"] }))));
+    assert!(!validator.is_valid(&module(json!({ "type": "jvm/app", "platforms": ["android"] }))));
+    // Only leaf platforms can be built for
+    assert!(!validator.is_valid(&module(json!({ "type": "lib", "platforms": ["ios"] }))));
+    // A library has no default platforms
+    assert!(!validator.is_valid(&module(json!({ "type": "lib" }))));
+    assert!(validator.is_valid(&module(json!({ "type": "lib", "platforms": ["jvm", "linuxX64"] }))));
+}
+
+#[test]
+fn annotations_are_matched_by_name() {
+    let schema = generate_schema(&amper_sources(), "product-annotations.json", &[]);
+    let module = &schema["$defs"]["Module"];
+
+    // `@ModifierAware` keys take `test-` and `@platform` modifiers
+    let patterns = module["patternProperties"].as_object().expect("no patternProperties");
+    assert!(patterns.contains_key("^(test-)?settings(@.+)?$"), "{:?}", patterns.keys());
+    assert!(patterns.contains_key("^(test-)?dependencies(@.+)?$"), "{:?}", patterns.keys());
+    // `@HiddenFromCompletion` is recognized
+    assert_eq!(module["properties"]["internalFlag"]["doNotSuggest"], true);
+}
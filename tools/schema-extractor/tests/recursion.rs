@@ -0,0 +1,63 @@
+//! Self-referential and mutually recursive schema classes produce `$ref` cycles
+
+mod common;
+
+use common::{collect_refs, fixture, generate_schema};
+use serde_json::{Value, json};
+
+fn recursive_schema() -> Value {
+    generate_schema(&fixture("recursive/sources"), "recursive.json", &[])
+}
+
+fn refs_of(definition: &Value) -> Vec<String> {
+    let mut refs = Vec::new();
+    collect_refs(definition, &mut refs);
+    refs
+}
+
+#[test]
+fn self_referential_class_references_itself() {
+    let schema = recursive_schema();
+    let task = &schema["$defs"]["TaskSettings"];
+
+    assert_eq!(task["type"], "object");
+    assert!(refs_of(task).contains(&"#/$defs/TaskSettings".to_string()));
+}
+
+#[test]
+fn mutually_recursive_classes_reference_each_other() {
+    let schema = recursive_schema();
+    let defs = &schema["$defs"];
+
+    assert!(refs_of(&defs["PluginSettings"]).contains(&"#/$defs/PluginOptionsSettings".to_string()));
+    assert!(refs_of(&defs["PluginOptionsSettings"]).contains(&"#/$defs/PluginSettings".to_string()));
+}
+
+#[test]
+fn sealed_hierarchy_can_contain_itself() {
+    let schema = recursive_schema();
+    let defs = &schema["$defs"];
+
+    assert!(refs_of(&defs["NodeSettings"]).contains(&"#/$defs/BranchSettings".to_string()));
+    assert!(refs_of(&defs["BranchSettings"]).contains(&"#/$defs/NodeSettings".to_string()));
+}
+
+#[test]
+fn no_definition_is_left_as_a_placeholder() {
+    let schema = recursive_schema();
+    for (name, definition) in schema["$defs"].as_object().unwrap() {
+        assert!(definition.is_object(), "definition '{}' is not a schema: {}", name, definition);
+    }
+}
+
+#[test]
+fn recursive_schema_validates_nested_documents() {
+    let schema = recursive_schema();
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    let document = json!({
+        "plugin": { "options": { "nested": { "options": {} } } },
+        "tree": { "children": [{ "value": "a" }, { "children": [{ "value": "b" }] }] }
+    });
+    assert!(validator.is_valid(&document));
+}
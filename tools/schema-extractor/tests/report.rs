@@ -0,0 +1,107 @@
+//! `--format json` and `--format sarif` for validation and lint results
+
+mod common;
+
+use common::{amper_sources, fixture, run_extractor};
+use serde_json::{Value, json};
+use std::path::Path;
+
+fn run_report(command: &str, project: &str, sources: &Path, format: &str) -> Value {
+    let project = fixture(project);
+    let output = run_extractor(&[
+        command,
+        project.to_str().unwrap(),
+        "--source",
+        sources.to_str().unwrap(),
+        "--format",
+        format,
+    ]);
+    assert_eq!(output.status.code(), Some(1), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).expect("the report is not JSON")
+}
+
+fn assert_valid_sarif(log: &Value) {
+    let schema: Value = serde_json::from_str(
+        &std::fs::read_to_string(fixture("sarif/sarif-schema-2.1.0.json")).unwrap(),
+    )
+    .unwrap();
+    let validator = jsonschema::validator_for(&schema).expect("the SARIF schema does not compile");
+    let errors: Vec<String> = validator.iter_errors(log).map(|e| format!("{} at {}", e, e.instance_path)).collect();
+    assert!(errors.is_empty(), "invalid SARIF: {:#?}", errors);
+}
+
+#[test]
+fn validation_results_are_valid_sarif() {
+    let log = run_report("validate", "projects/invalid", &amper_sources(), "sarif");
+    assert_valid_sarif(&log);
+
+    let run = &log["runs"][0];
+    let rules: Vec<&str> = run["tool"]["driver"]["rules"].as_array().unwrap().iter().map(|r| r["id"].as_str().unwrap()).collect();
+    assert!(rules.contains(&"invalid-value") && rules.contains(&"syntax-error"), "{:?}", rules);
+
+    // The "did you mean" suggestion becomes a fix replacing the value
+    let product = run["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|result| result["message"]["text"].as_str().unwrap().contains("jvm/application"))
+        .expect("no result for the product type");
+    assert_eq!(product["ruleId"], "invalid-value");
+    assert_eq!(product["level"], "error");
+    assert!(product["locations"][0]["physicalLocation"]["artifactLocation"]["uri"].as_str().unwrap().ends_with("app/module.yaml"));
+    assert_eq!(
+        product["fixes"][0]["artifactChanges"][0]["replacements"][0],
+        json!({
+            "deletedRegion": { "startLine": 1, "startColumn": 10, "endLine": 1, "endColumn": 25 },
+            "insertedContent": { "text": "jvm/app" }
+        })
+    );
+}
+
+#[test]
+fn lint_results_are_valid_sarif() {
+    let log = run_report("lint", "projects/lint", &fixture("lint/sources"), "sarif");
+    assert_valid_sarif(&log);
+
+    // Regions count characters rather than UTF-16 code units
+    assert_eq!(log["runs"][0]["columnKind"], "unicodeCodePoints");
+
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    let levels: Vec<(&str, &str)> =
+        results.iter().map(|r| (r["ruleId"].as_str().unwrap(), r["level"].as_str().unwrap())).collect();
+    assert_eq!(
+        levels,
+        [
+            ("platform-specific-setting", "error"),
+            ("enum-order", "warning"),
+            ("enum-order", "warning"),
+            ("untargeted-qualifier", "warning"),
+            ("platform-specific-setting", "error"),
+            ("platform-specific-setting", "error"),
+        ]
+    );
+    let rule = &log["runs"][0]["tool"]["driver"]["rules"][0];
+    assert!(rule["helpUri"].as_str().unwrap().ends_with("README.md#platform-specific-setting"));
+}
+
+#[test]
+fn json_report_lists_rules_ranges_and_fixes() {
+    let report = run_report("validate", "projects/invalid", &amper_sources(), "json");
+
+    assert_eq!(report["version"], 1);
+    assert_eq!(report["summary"], json!({ "files": 1, "errors": 6, "warnings": 0 }));
+    let first = &report["diagnostics"][0];
+    assert!(first["file"].as_str().unwrap().ends_with("app/module.yaml"));
+    assert_eq!(first["rule"], "invalid-value");
+    assert_eq!(first["severity"], "error");
+    assert_eq!(first["path"], "product");
+    assert_eq!(first["range"], json!({ "start": { "line": 1, "column": 10 }, "end": { "line": 1, "column": 25 } }));
+    assert_eq!(
+        first["fixes"],
+        json!([{
+            "description": "Replace `jvm/application` with `jvm/app`",
+            "range": { "start": { "line": 1, "column": 10 }, "end": { "line": 1, "column": 25 } },
+            "replacement": "jvm/app"
+        }])
+    );
+}
@@ -0,0 +1,50 @@
+//! Any parsed class can be the schema root, and `list-types` shows what was parsed
+
+mod common;
+
+use common::{amper_sources, generate_schema, run_extractor, temp_path};
+use serde_json::json;
+
+#[test]
+fn schema_can_be_rooted_at_any_class() {
+    let schema = generate_schema(&amper_sources(), "settings.json", &["--schema-type", "Settings"]);
+    assert_eq!(schema["allOf"][0]["$ref"], "#/$defs/Settings");
+    assert!(schema["$defs"].get("Module").is_none());
+
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+    assert!(validator.is_valid(&json!({ "compose": "enabled", "jvm": { "release": "17" } })));
+    assert!(!validator.is_valid(&json!({ "product": "jvm/app" })));
+}
+
+#[test]
+fn unknown_root_type_is_an_error() {
+    let sources = amper_sources();
+    for schema_type in ["Nope", "Platform"] {
+        let output = temp_path("unknown.json");
+        let result = run_extractor(&[
+            "--source",
+            sources.to_str().unwrap(),
+            "--output",
+            output.to_str().unwrap(),
+            "--schema-type",
+            schema_type,
+        ]);
+
+        assert!(!result.status.success(), "'{}' should be rejected", schema_type);
+        assert!(String::from_utf8_lossy(&result.stderr).contains(schema_type));
+        assert!(!output.exists());
+    }
+}
+
+#[test]
+fn list_types_prints_classes_and_enums_with_counts() {
+    let result = run_extractor(&["list-types", "--source", amper_sources().to_str().unwrap()]);
+    assert!(result.status.success());
+
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let lines: Vec<&str> = stdout.lines().map(str::trim).collect();
+    assert!(lines.contains(&"Module (6 properties), extends Base"));
+    assert!(lines.contains(&"BomDependency (1 property), extends Dependency"));
+    assert!(lines.contains(&"Dependency (0 properties), sealed: ScopedDependency, BomDependency"));
+    assert!(lines.contains(&"ProductType (5 entries)"));
+}
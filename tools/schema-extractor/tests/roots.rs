@@ -0,0 +1,60 @@
+//! Template and project schemas are generated even though no source declares them
+
+mod common;
+
+use amper_schema_extractor::{SchemaOptions, generate_json_schema, load_context, resolve_root_type};
+use common::{amper_sources, fixture, generate_schema};
+use serde_json::json;
+
+#[test]
+fn template_reuses_module_definitions_without_product() {
+    let schema = generate_schema(&amper_sources(), "template.json", &["--schema-type", "template"]);
+    let template = &schema["$defs"]["Template"];
+
+    let properties = template["properties"].as_object().unwrap();
+    assert!(!properties.contains_key("product"));
+    assert!(!properties.contains_key("apply"));
+    assert!(properties.contains_key("settings"));
+    assert!(properties.contains_key("dependencies"));
+    assert!(schema["$defs"]["Settings"].is_object());
+
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+    assert!(validator.is_valid(&json!({ "settings": { "compose": "enabled" } })));
+    assert!(validator.is_valid(&json!({})));
+    assert!(!validator.is_valid(&json!({ "product": "jvm/app" })));
+}
+
+#[test]
+fn template_mirrors_module_when_there_is_no_base_class() {
+    let schema = generate_schema(
+        &fixture("recursive/sources"),
+        "template-no-base.json",
+        &["--schema-type", "template"],
+    );
+    let properties = schema["$defs"]["Template"]["properties"].as_object().unwrap();
+
+    let mut keys: Vec<&str> = properties.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, ["plugin", "tasks", "tree"]);
+}
+
+#[test]
+fn project_lists_module_globs() {
+    let schema = generate_schema(&amper_sources(), "project.json", &["--schema-type", "project"]);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    assert!(validator.is_valid(&json!({ "modules": ["./app", "./libs/*"] })));
+    assert!(!validator.is_valid(&json!({ "modules": "./app" })));
+    assert!(!validator.is_valid(&json!({ "product": "jvm/app" })));
+}
+
+#[test]
+fn parsed_model_holds_only_declared_classes() {
+    let context = load_context(&amper_sources(), false).unwrap();
+    assert!(!context.classes.contains_key("Template"));
+    assert!(!context.classes.contains_key("Project"));
+
+    assert_eq!(resolve_root_type(&context, "project").unwrap(), "Project");
+    let schema = generate_json_schema(&context, "Project", &SchemaOptions::default()).unwrap();
+    assert!(schema["$defs"]["Project"]["properties"]["modules"].is_object());
+}
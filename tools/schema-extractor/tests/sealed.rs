@@ -0,0 +1,117 @@
+//! Sealed hierarchies become discriminated unions
+
+mod common;
+
+use common::{amper_sources, fixture, generate_schema};
+use serde_json::{Value, json};
+
+fn module_with_dependency(dependency: Value) -> Value {
+    json!({
+        "product": "jvm/app",
+        "settings": {},
+        "dependencies": [dependency]
+    })
+}
+
+#[test]
+fn variants_are_selected_by_their_unique_key() {
+    let schema = generate_schema(&amper_sources(), "sealed-keys.json", &[]);
+    let dependency = &schema["$defs"]["Dependency"];
+
+    let selectors = dependency["then"]["then"]["oneOf"].as_array().unwrap();
+    let selected: Vec<(&str, &str)> = selectors
+        .iter()
+        .map(|s| (s["title"].as_str().unwrap(), s["required"][0].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        selected,
+        [
+            ("ExternalMavenDependency", "coordinates"),
+            ("InternalDependency", "path"),
+            ("CatalogDependency", "catalogKey"),
+            ("BomDependency", "bom"),
+        ]
+    );
+}
+
+#[test]
+fn variants_with_an_optional_key_may_leave_it_out() {
+    let schema = generate_schema(&amper_sources(), "sealed-optional.json", &[]);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    // `path` is the only key of `InternalDependency` and it is nullable
+    assert!(validator.is_valid(&module_with_dependency(json!({ "exported": true }))));
+    assert!(!validator.is_valid(&module_with_dependency(json!({ "exported": "yes" }))));
+    // Once a key is present, the variant it selects still applies
+    assert!(!validator.is_valid(&module_with_dependency(json!({ "bom": "a:b:1.0", "exported": true }))));
+}
+
+#[test]
+fn errors_point_at_the_selected_variant() {
+    let schema = generate_schema(&amper_sources(), "sealed-errors.json", &[]);
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+
+    assert!(validator.is_valid(&module_with_dependency(json!({ "path": "./shared", "exported": true }))));
+    assert!(validator.is_valid(&module_with_dependency(json!({ "coordinates": "a:b:1.0", "scope": "compile-only" }))));
+
+    // Two discriminators at once
+    assert!(!validator.is_valid(&module_with_dependency(json!({ "path": "./shared", "bom": "a:b:1.0" }))));
+
+    // A bad value inside a variant is reported through that variant's branch only
+    let invalid = module_with_dependency(json!({ "coordinates": "a:b:1.0", "scope": "everywhere" }));
+    let errors: Vec<String> = validator
+        .iter_errors(&invalid)
+        .map(|e| e.schema_path.to_string())
+        .collect();
+    assert!(!errors.is_empty());
+    assert!(
+        errors.iter().all(|path| path.contains("/then/allOf/0/then")),
+        "unexpected error locations: {:?}",
+        errors
+    );
+}
+
+#[test]
+fn variants_with_distinct_constants_are_selected_by_value() {
+    let schema = generate_schema(&fixture("sealed/sources"), "sealed-consts.json", &[]);
+    let source = &schema["$defs"]["SourceSettings"];
+
+    let constants: Vec<&str> = source["then"]["then"]["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["properties"]["kind"]["const"].as_str().unwrap())
+        .collect();
+    assert_eq!(constants, ["git", "local"]);
+
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+    assert!(validator.is_valid(&json!({ "source": { "kind": "git", "location": "https://x" } })));
+    assert!(!validator.is_valid(&json!({ "source": { "kind": "svn", "location": "https://x" } })));
+    // The constant is a default, so the key may be left out
+    assert!(validator.is_valid(&json!({ "source": { "location": "https://x" } })));
+    assert!(!validator.is_valid(&json!({ "source": { "location": 1 } })));
+}
+
+#[test]
+fn only_literal_defaults_are_constants() {
+    let schema = generate_schema(&fixture("sealed/sources"), "sealed-literals.json", &[]);
+    let cache = &schema["$defs"]["CacheSettings"];
+
+    let selected: Vec<&str> = cache["then"]["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["required"][0].as_str().unwrap())
+        .collect();
+    assert_eq!(selected, ["directory", "url"]);
+    assert!(!cache.to_string().contains("const"), "{cache}");
+}
+
+#[test]
+fn defaulted_properties_are_optional() {
+    let schema = generate_schema(&fixture("sealed/sources"), "sealed-defaults.json", &[]);
+
+    let required = |name: &str| schema["$defs"][name]["required"].clone();
+    assert_eq!(required("GitSourceSettings"), json!(["location"]));
+    assert_eq!(required("LocalCacheSettings"), json!(["directory"]));
+}
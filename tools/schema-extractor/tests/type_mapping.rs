@@ -0,0 +1,96 @@
+//! Kotlin value types are mapped through the built-in table or a `--type-mapping` file
+
+mod common;
+
+use common::{fixture, generate_schema, run_extractor, temp_path};
+use serde_json::json;
+
+#[test]
+fn wrappers_are_unwrapped_with_the_builtin_mapping() {
+    let schema = generate_schema(&fixture("mapping/sources"), "mapping-default.json", &[]);
+    let properties = &schema["$defs"]["Module"]["properties"];
+
+    assert_eq!(properties["channel"]["$ref"], "#/$defs/Channel");
+    assert_eq!(properties["output"]["type"], "string");
+    assert_eq!(properties["jobs"]["type"], "integer");
+    // `TraceableVersion` unwraps to `Version`, which only a config file maps;
+    // the built-in version pattern belongs to toolchain classes, not to any `version`
+    assert_eq!(properties["version"]["type"], "string");
+    assert!(properties["version"].get("pattern").is_none(), "{}", properties["version"]);
+}
+
+#[test]
+fn property_constraints_are_keyed_by_class() {
+    let config = temp_path("class-mapping.toml");
+    std::fs::write(&config, "[properties.\"Module.version\"]\npattern = \"^2\\\\.\"\n").unwrap();
+    let schema = generate_schema(
+        &fixture("mapping/sources"),
+        "mapping-class.json",
+        &["--type-mapping", config.to_str().unwrap()],
+    );
+    assert_eq!(schema["$defs"]["Module"]["properties"]["version"]["pattern"], r"^2\.");
+
+    std::fs::write(&config, "[properties.version]\npattern = \"^2\\\\.\"\n").unwrap();
+    let result = run_extractor(&[
+        "--source",
+        fixture("mapping/sources").to_str().unwrap(),
+        "--output",
+        temp_path("mapping-unkeyed.json").to_str().unwrap(),
+        "--type-mapping",
+        config.to_str().unwrap(),
+    ]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("keyed as Class.property"));
+}
+
+#[test]
+fn toml_mapping_adds_patterns_and_formats() {
+    let config = fixture("mapping/type-mapping.toml");
+    let schema = generate_schema(
+        &fixture("mapping/sources"),
+        "mapping-toml.json",
+        &["--type-mapping", config.to_str().unwrap()],
+    );
+    let properties = &schema["$defs"]["Module"]["properties"];
+    assert_eq!(properties["version"]["pattern"], r"^\d+(\.\d+)*$");
+    assert_eq!(properties["url"]["format"], "uri");
+
+    let validator = jsonschema::validator_for(&schema).expect("schema does not compile");
+    let module = json!({ "version": "2.1.0", "channel": "eap", "output": "build", "jobs": 4 });
+    assert!(validator.is_valid(&module));
+    let mut unversioned = module.clone();
+    unversioned["version"] = json!("latest");
+    assert!(!validator.is_valid(&unversioned));
+}
+
+#[test]
+fn json_mapping_overrides_builtin_types() {
+    let config = fixture("mapping/type-mapping.json");
+    let schema = generate_schema(
+        &fixture("mapping/sources"),
+        "mapping-json.json",
+        &["--type-mapping", config.to_str().unwrap()],
+    );
+    let properties = &schema["$defs"]["Module"]["properties"];
+    assert_eq!(properties["jobs"]["type"], "string");
+    assert_eq!(properties["output"]["type"], "string");
+}
+
+#[test]
+fn invalid_mapping_is_rejected() {
+    let config = temp_path("bad-mapping.toml");
+    std::fs::write(&config, "[types.Version]\ntype = \"date\"\n").unwrap();
+    let output = temp_path("bad-mapping.json");
+
+    let result = run_extractor(&[
+        "--source",
+        fixture("mapping/sources").to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+        "--type-mapping",
+        config.to_str().unwrap(),
+    ]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("unsupported type 'date'"));
+    assert!(!output.exists());
+}
@@ -0,0 +1,129 @@
+//! `validate` checks Amper YAML files against the generated schemas
+
+mod common;
+
+use common::{amper_sources, fixture, run_extractor, temp_path};
+use std::path::Path;
+use std::process::Output;
+
+fn run_validate(paths: &[&Path], extra_args: &[&str]) -> Output {
+    let mut args = vec!["validate".to_string()];
+    args.extend(paths.iter().map(|p| p.to_str().unwrap().to_string()));
+    args.extend(extra_args.iter().map(|a| a.to_string()));
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_extractor(&args)
+}
+
+fn with_sources(paths: &[&Path]) -> Output {
+    let sources = amper_sources();
+    run_validate(paths, &["--source", sources.to_str().unwrap()])
+}
+
+/// Diagnostics without the file name prefix
+fn problems(output: &Output, file: &Path) -> Vec<String> {
+    let prefix = format!("{}:", file.display());
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.strip_prefix(&prefix).unwrap_or(line).to_string())
+        .collect()
+}
+
+#[test]
+fn valid_project_passes() {
+    let result = with_sources(&[&fixture("projects/valid")]);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stdout));
+    assert!(String::from_utf8_lossy(&result.stderr).contains("4 files are valid"));
+}
+
+#[test]
+fn violations_are_reported_with_positions() {
+    let file = fixture("projects/invalid/app/module.yaml");
+    let result = with_sources(&[&fixture("projects/invalid")]);
+    assert_eq!(result.status.code(), Some(1));
+
+    assert_eq!(
+        problems(&result, &file),
+        [
+            "1:10: product: Value `jvm/application` is not allowed. Did you mean `jvm/app`? Expected one of: `lib`, `jvm/app`, `android/app`, `ios/app`",
+            "4:18: dependencies[0].coordinates: Value `ktor-client-core` does not match the expected format `^[^\\s:]+:[^\\s:]+(:[^\\s:]+){0,2}$`",
+            "6:12: dependencies[1].scope: Value `everywhere` is not allowed. Expected one of: `all`, `compile-only`, `runtime-only`",
+            "10:14: settings.jvm.release: Value `16` is not allowed. Expected one of: `8`, `11`, `17`, `21`",
+            "13:14: settings.compose.enabled: Incorrect type: expected boolean, found string",
+            "14:3: settings.kotlin: Property `kotlin` is not allowed",
+        ]
+    );
+    assert!(String::from_utf8_lossy(&result.stderr).contains("6 problems in 1 of 1 file"));
+}
+
+#[test]
+fn typos_come_with_suggestions() {
+    let module = temp_path("module.yaml");
+    std::fs::write(
+        &module,
+        "product:\n  type: jvm/ap\n  platforms: [jvm, andriod]\ndeps: []\ndependecies@jvm: []\ndependencies:\n  - coordinates: a:b:1\n    scope: runtime\n    exprted: true\nsettings:\n  JVM:\n    mainclass: a.B\n",
+    )
+    .unwrap();
+
+    let result = with_sources(&[&module]);
+    assert_eq!(
+        problems(&result, &module),
+        [
+            "2:9: product.type: Value `jvm/ap` is not allowed. Did you mean `jvm/app`? Expected one of: `lib`, `jvm/app`, `android/app`, `ios/app`",
+            "3:20: product.platforms[1]: Value `andriod` is not allowed. Did you mean `android`? Expected one of: `jvm`, `android`, `iosArm64`, `iosSimulatorArm64`, `iosX64`, `linuxX64`",
+            "4:1: deps: Property `deps` is not allowed. Did you mean `dependencies`?",
+            "5:1: dependecies@jvm: Property `dependecies@jvm` is not allowed. Did you mean `dependencies@jvm`?",
+            "8:12: dependencies[0].scope: Value `runtime` is not allowed. Did you mean `runtime-only`? Expected one of: `all`, `compile-only`, `runtime-only`",
+            "9:5: dependencies[0].exprted: Property `exprted` is not allowed. Did you mean `exported`?",
+            "11:3: settings.JVM: Property `JVM` is not allowed. Did you mean `jvm`?",
+        ]
+    );
+}
+
+#[test]
+fn hidden_and_deprecated_properties_are_not_suggested() {
+    let module = temp_path("module.yaml");
+    std::fs::write(&module, "product: jvm/app\ninternalFlg: true\nsettings:\n  jvm:\n    targt: 17\n").unwrap();
+
+    let result = with_sources(&[&module]);
+    assert_eq!(
+        problems(&result, &module),
+        [
+            "2:1: internalFlg: Property `internalFlg` is not allowed",
+            "5:5: settings.jvm.targt: Property `targt` is not allowed",
+        ]
+    );
+}
+
+#[test]
+fn null_values_and_syntax_errors() {
+    let module = temp_path("module.yaml");
+    std::fs::write(&module, "product: jvm/app\nsettings:\n  jvm: ~\n").unwrap();
+    let broken = temp_path("broken.module-template.yaml");
+    std::fs::write(&broken, "settings: [jvm\n").unwrap();
+
+    let result = with_sources(&[&module, &broken]);
+    assert_eq!(result.status.code(), Some(1));
+    assert_eq!(
+        problems(&result, &module)[0],
+        "3:8: settings.jvm: A value is required here: an empty value or `~` (null) is not allowed."
+    );
+    let syntax = problems(&result, &broken);
+    assert!(syntax.iter().any(|p| p.starts_with("2:1: ") && p.ends_with(": syntax error")), "{:?}", syntax);
+}
+
+#[test]
+fn committed_schemas_can_be_used() {
+    let out_dir = temp_path("validate-schemas");
+    let sources = amper_sources();
+    let result = run_extractor(&["--source", sources.to_str().unwrap(), "--out-dir", out_dir.to_str().unwrap()]);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    let project = fixture("projects/valid");
+    for schema in [out_dir.clone(), out_dir.join("module-schema.json")] {
+        let result = run_validate(&[&project.join("app/module.yaml")], &["--schema", schema.to_str().unwrap()]);
+        assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stdout));
+    }
+
+    let result = run_validate(&[&fixture("projects/invalid")], &["--schema", out_dir.to_str().unwrap()]);
+    assert_eq!(result.status.code(), Some(1));
+}